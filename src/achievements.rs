@@ -0,0 +1,370 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use bevy_asset_loader::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    game::{
+        damage::DamageSource,
+        enemies::EnemyKind,
+        replay::ReplayPlayback,
+        simulation::SimulationSet,
+        stats::{RecordStatsSet, RunStats},
+        GameState,
+    },
+    profile::{self, PlayerProfile},
+};
+
+/// Extension of the achievements file
+pub const ACHIEVEMENTS_EXTENSION: &str = "achievements.ron";
+
+/// Loads achievements through the asset server and
+/// unlocks them during the run.
+/// New achievements only need a new entry in the achievements file.
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<AchievementDefinitions>()
+            .init_asset_loader::<AchievementsLoader>()
+            .init_resource::<AchievementDefinitions>()
+            .add_event::<AchievementUnlockedEvent>()
+            .add_system(update_definitions)
+            .add_system(
+                check_achievements
+                    .after(RecordStatsSet)
+                    .in_set(SimulationSet::Resolve)
+                    .run_if(not(resource_exists::<ReplayPlayback>()))
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(
+                check_victory_achievements
+                    .before(profile::record_run)
+                    .run_if(not(resource_exists::<ReplayPlayback>()))
+                    .in_schedule(OnEnter(GameState::Victory)),
+            );
+    }
+}
+
+/// Achievements file. Only read through `AchievementDefinitions`
+/// resource, handle is kept here so the file stays loaded.
+#[derive(Default, AssetCollection, Resource)]
+pub struct AchievementAssets {
+    #[asset(path = "achievements/all.achievements.ron")]
+    pub achievements: Handle<AchievementDefinitions>,
+}
+
+/// Sent once when the achievement is unlocked
+pub struct AchievementUnlockedEvent(pub AchievementDefinition);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AchievementDefinition {
+    /// Kept in the profile once unlocked, so it should never change
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub condition: Condition,
+}
+
+/// All achievements in the order they are listed
+#[derive(Debug, Default, Clone, Deserialize, TypeUuid, Resource)]
+#[uuid = "4f8e2a6d-1b3c-4d7e-9a05-c6b8d2e1f374"]
+pub struct AchievementDefinitions {
+    pub achievements: Vec<AchievementDefinition>,
+}
+
+impl AchievementDefinitions {
+    /// Parses and validates the achievements file
+    pub fn parse(bytes: &[u8], path: &Path) -> Result<Self, AchievementsError> {
+        let definitions: Self = ron::de::from_bytes(bytes)
+            .map_err(|e| AchievementsError::Parse(path.to_path_buf(), e))?;
+        definitions
+            .validate()
+            .map_err(|reason| AchievementsError::Invalid(path.to_path_buf(), reason))?;
+        Ok(definitions)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        for definition in self.achievements.iter() {
+            let reason = if definition.id.is_empty() {
+                "id can not be empty"
+            } else if !ids.insert(definition.id.as_str()) {
+                "id must be unique"
+            } else if definition.name.is_empty() {
+                "name can not be empty"
+            } else {
+                match definition.condition.validate() {
+                    Ok(()) => continue,
+                    Err(reason) => reason,
+                }
+            };
+            return Err(format!("achievement {:?}: {reason}", definition.id));
+        }
+        Ok(())
+    }
+
+    /// Loads the achievements file without the asset server
+    #[cfg(not(target_family = "wasm"))]
+    pub fn load_from_file(path: &Path) -> Result<Self, AchievementsError> {
+        let bytes =
+            std::fs::read(path).map_err(|e| AchievementsError::Io(path.to_path_buf(), e))?;
+        Self::parse(&bytes, path)
+    }
+}
+
+#[derive(Debug)]
+pub enum AchievementsError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, ron::error::SpannedError),
+    Invalid(PathBuf, String),
+}
+
+impl Display for AchievementsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, e) => f.write_fmt(format_args!("{}: {e}", path.display())),
+            Self::Parse(path, e) => f.write_fmt(format_args!("{}: {e}", path.display())),
+            Self::Invalid(path, reason) => {
+                f.write_fmt(format_args!("{}: {reason}", path.display()))
+            }
+        }
+    }
+}
+
+impl std::error::Error for AchievementsError {}
+
+#[derive(Default)]
+pub struct AchievementsLoader;
+
+impl AssetLoader for AchievementsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let definitions = AchievementDefinitions::parse(bytes, load_context.path())?;
+            load_context.set_default_asset(LoadedAsset::new(definitions));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[ACHIEVEMENTS_EXTENSION]
+    }
+}
+
+/// Copies loaded and changed achievements into `AchievementDefinitions`
+fn update_definitions(
+    assets: Res<Assets<AchievementDefinitions>>,
+    mut events: EventReader<AssetEvent<AchievementDefinitions>>,
+    mut definitions: ResMut<AchievementDefinitions>,
+) {
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle } => {
+                if let Some(loaded) = assets.get(handle) {
+                    *definitions = loaded.clone();
+                }
+            }
+            AssetEvent::Modified { handle } => {
+                if let Some(loaded) = assets.get(handle) {
+                    info!("reloaded achievements");
+                    *definitions = loaded.clone();
+                }
+            }
+            AssetEvent::Removed { .. } => {}
+        }
+    }
+}
+
+/// What the player needs to do to unlock an achievement.
+/// Counts are summed over all runs.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum Condition {
+    Kills(EnemyKind, u64),
+    TotalKills(u64),
+    Crits(u64),
+    Damage(DamageSource, u64),
+    Victories(u32),
+    /// Finish a stage without any wall losing more than this part of max hp
+    StageWallDamage(f32),
+    /// Win a run, optionally without picking any global upgrade
+    Victory {
+        global_upgrades: bool,
+    },
+}
+
+/// Everything conditions are checked against
+pub struct AchievementContext<'a> {
+    pub profile: &'a PlayerProfile,
+    /// Current run, not yet recorded in the profile
+    pub run: Option<&'a RunStats>,
+    /// Current run was won
+    pub won: bool,
+}
+
+impl Condition {
+    fn validate(&self) -> Result<(), &'static str> {
+        match *self {
+            Condition::Kills(_, 0)
+            | Condition::TotalKills(0)
+            | Condition::Crits(0)
+            | Condition::Damage(_, 0)
+            | Condition::Victories(0) => Err("required count must be positive"),
+            Condition::StageWallDamage(max_damage) if !(0.0..=1.0).contains(&max_damage) => {
+                Err("StageWallDamage must be between 0 and 1")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Current and required progress
+    pub fn progress(&self, context: &AchievementContext) -> (u64, u64) {
+        let run = context.run;
+        match *self {
+            Condition::Kills(kind, required) => {
+                let lifetime = context.profile.kills.get(&kind).copied().unwrap_or(0);
+                let current = run.map_or(0, |run| {
+                    run.sides
+                        .iter()
+                        .map(|side| side.kills.get(&kind).copied().unwrap_or(0) as u64)
+                        .sum()
+                });
+                (lifetime + current, required)
+            }
+            Condition::TotalKills(required) => {
+                let lifetime = context.profile.kills.values().sum::<u64>();
+                let current = run.map_or(0, |run| run.total_kills() as u64);
+                (lifetime + current, required)
+            }
+            Condition::Crits(required) => {
+                let current = run.map_or(0, |run| run.total_crits() as u64);
+                (context.profile.crits + current, required)
+            }
+            Condition::Damage(source, required) => {
+                let lifetime = context
+                    .profile
+                    .weapon_damage
+                    .get(&source)
+                    .copied()
+                    .unwrap_or(0);
+                let current = run.map_or(0, |run| {
+                    run.sides
+                        .iter()
+                        .map(|side| side.damage_dealt.get(&source).copied().unwrap_or(0))
+                        .sum()
+                });
+                (lifetime + current, required)
+            }
+            Condition::Victories(required) => {
+                let victories = context.profile.victories + u32::from(context.won);
+                (victories as u64, required as u64)
+            }
+            Condition::StageWallDamage(max_damage) => {
+                let met = run
+                    .and_then(|run| run.best_stage_wall_damage)
+                    .map_or(false, |damage| damage <= max_damage);
+                (u64::from(met), 1)
+            }
+            Condition::Victory { global_upgrades } => {
+                let met = context.won
+                    && run.map_or(false, |run| {
+                        global_upgrades
+                            || !run
+                                .upgrades
+                                .iter()
+                                .any(|upgrade| upgrade.has_global_upgrades())
+                    });
+                (u64::from(met), 1)
+            }
+        }
+    }
+
+    pub fn is_met(&self, context: &AchievementContext) -> bool {
+        let (current, required) = self.progress(context);
+        required <= current
+    }
+}
+
+fn check_achievements(
+    definitions: Res<AchievementDefinitions>,
+    run_stats: Res<RunStats>,
+    mut profile: ResMut<PlayerProfile>,
+    mut unlocked_events: EventWriter<AchievementUnlockedEvent>,
+) {
+    unlock_achievements(
+        &definitions,
+        &run_stats,
+        false,
+        &mut profile,
+        &mut unlocked_events,
+    );
+}
+
+fn check_victory_achievements(
+    definitions: Res<AchievementDefinitions>,
+    run_stats: Res<RunStats>,
+    mut profile: ResMut<PlayerProfile>,
+    mut unlocked_events: EventWriter<AchievementUnlockedEvent>,
+) {
+    unlock_achievements(
+        &definitions,
+        &run_stats,
+        true,
+        &mut profile,
+        &mut unlocked_events,
+    );
+}
+
+fn unlock_achievements(
+    definitions: &AchievementDefinitions,
+    run_stats: &RunStats,
+    won: bool,
+    profile: &mut ResMut<PlayerProfile>,
+    unlocked_events: &mut EventWriter<AchievementUnlockedEvent>,
+) {
+    let context = AchievementContext {
+        profile: &**profile,
+        run: Some(run_stats),
+        won,
+    };
+    let unlocked = definitions
+        .achievements
+        .iter()
+        .filter(|definition| !context.profile.achievements.contains(&definition.id))
+        .filter(|definition| definition.condition.is_met(&context))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    // profile is only marked as changed if something was unlocked
+    for definition in unlocked {
+        profile.achievements.insert(definition.id.clone());
+        unlocked_events.send(AchievementUnlockedEvent(definition));
+    }
+}
+
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_achievements_are_valid() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/achievements/all.achievements.ron");
+        if let Err(e) = AchievementDefinitions::load_from_file(&path) {
+            panic!("{e}");
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+
+use crate::{
+    game::{enemies::EnemySoundEvent, weapons::WeaponSoundEvent},
+    settings::{GameSettings, SoundChannel},
+    GameAssets, GlobalState,
+};
+
+const MOLOTOV_SFX_MULTIPLIER: f64 = 0.2;
+const ELITE_EXPLOSION_SFX_MULTIPLIER: f64 = 0.2;
+
+/// Plays music and sounds of the game.
+/// Game logic only sends events, so it can run without audio.
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_channel::<MusicChannel>()
+            .add_audio_channel::<WeaponsChannel>()
+            .add_audio_channel::<EnemiesChannel>()
+            .add_audio_channel::<UiChannel>()
+            .add_system(play_main_menu_audio.in_schedule(OnEnter(GlobalState::MainMenu)))
+            .add_system(stop_music.in_schedule(OnExit(GlobalState::MainMenu)))
+            .add_system(play_in_game_audio.in_schedule(OnEnter(GlobalState::InGame)))
+            .add_system(stop_music.in_schedule(OnExit(GlobalState::InGame)))
+            .add_system(play_weapon_sounds.in_set(OnUpdate(GlobalState::InGame)))
+            .add_system(play_enemy_sounds.in_set(OnUpdate(GlobalState::InGame)))
+            .add_system(play_ui_sounds.run_if(resource_exists::<GameAssets>()))
+            .add_system(update_volumes.run_if(resource_exists_and_changed::<GameSettings>()));
+    }
+}
+
+/// Background music
+#[derive(Resource)]
+pub struct MusicChannel;
+
+/// Shots and explosions of weapons
+#[derive(Resource)]
+pub struct WeaponsChannel;
+
+/// Sounds made by enemies
+#[derive(Resource)]
+pub struct EnemiesChannel;
+
+/// Menu and interface sounds
+#[derive(Resource)]
+pub struct UiChannel;
+
+fn play_in_game_audio(
+    music: Res<AudioChannel<MusicChannel>>,
+    game_settings: Res<GameSettings>,
+    game_assets: Res<GameAssets>,
+) {
+    music
+        .play(game_assets.background.clone())
+        .with_volume(game_settings.volume(SoundChannel::Music))
+        .looped();
+}
+
+fn play_main_menu_audio(
+    music: Res<AudioChannel<MusicChannel>>,
+    game_settings: Res<GameSettings>,
+    game_assets: Res<GameAssets>,
+) {
+    music
+        .play(game_assets.main_menu.clone())
+        .with_volume(game_settings.volume(SoundChannel::Music))
+        .looped();
+}
+
+fn stop_music(music: Res<AudioChannel<MusicChannel>>) {
+    music.stop();
+}
+
+fn play_weapon_sounds(
+    weapons: Res<AudioChannel<WeaponsChannel>>,
+    game_settings: Res<GameSettings>,
+    game_assets: Res<GameAssets>,
+    mut sound_events: EventReader<WeaponSoundEvent>,
+) {
+    let volume = game_settings.volume(SoundChannel::Weapons);
+    for event in sound_events.iter() {
+        match event {
+            WeaponSoundEvent::CrossbowShoot => {
+                weapons
+                    .play(game_assets.crossbow_shoot.clone())
+                    .with_volume(volume);
+            }
+            WeaponSoundEvent::MolotovExplosion => {
+                weapons
+                    .play(game_assets.explosion.clone())
+                    .with_volume(volume * MOLOTOV_SFX_MULTIPLIER);
+            }
+        }
+    }
+}
+
+fn play_enemy_sounds(
+    enemies: Res<AudioChannel<EnemiesChannel>>,
+    game_settings: Res<GameSettings>,
+    game_assets: Res<GameAssets>,
+    mut sound_events: EventReader<EnemySoundEvent>,
+) {
+    let volume = game_settings.volume(SoundChannel::Enemies);
+    for event in sound_events.iter() {
+        match event {
+            EnemySoundEvent::Shoot => {
+                enemies
+                    .play(game_assets.crossbow_shoot.clone())
+                    .with_volume(volume);
+            }
+            EnemySoundEvent::Explosion => {
+                enemies
+                    .play(game_assets.explosion.clone())
+                    .with_volume(volume * ELITE_EXPLOSION_SFX_MULTIPLIER);
+            }
+        }
+    }
+}
+
+/// Clicks on every pressed button
+fn play_ui_sounds(
+    ui: Res<AudioChannel<UiChannel>>,
+    game_settings: Res<GameSettings>,
+    game_assets: Res<GameAssets>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
+) {
+    for interaction in buttons.iter() {
+        if *interaction == Interaction::Clicked {
+            ui.play(game_assets.click.clone())
+                .with_volume(game_settings.volume(SoundChannel::Ui));
+        }
+    }
+}
+
+/// Applies new volumes to already playing sounds
+fn update_volumes(
+    game_settings: Res<GameSettings>,
+    music: Res<AudioChannel<MusicChannel>>,
+    weapons: Res<AudioChannel<WeaponsChannel>>,
+    enemies: Res<AudioChannel<EnemiesChannel>>,
+    ui: Res<AudioChannel<UiChannel>>,
+) {
+    music.set_volume(game_settings.volume(SoundChannel::Music));
+    weapons.set_volume(game_settings.volume(SoundChannel::Weapons));
+    enemies.set_volume(game_settings.volume(SoundChannel::Enemies));
+    ui.set_volume(game_settings.volume(SoundChannel::Ui));
+}
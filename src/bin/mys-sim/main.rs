@@ -0,0 +1,20 @@
+//! Headless simulation used for balance testing.
+//! Plays seeded games without window, rendering, audio or UI
+//! as fast as possible and prints how each of them went.
+//!
+//! Usage: mys-sim [--games N] [--seed SEED] [--policy random|greedy|first]
+//!                [--mode normal|infinite] [--max-time SECONDS]
+//!                [--castle keep|bastion|arsenal|citadel] [--assets DIR]
+
+#[cfg(not(target_family = "wasm"))]
+mod sim;
+
+#[cfg(not(target_family = "wasm"))]
+fn main() {
+    sim::run();
+}
+
+/// The simulation reads assets straight from the disk,
+/// which the web build can not do
+#[cfg(target_family = "wasm")]
+fn main() {}
@@ -0,0 +1,489 @@
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use bevy::{
+    hierarchy::HierarchyPlugin, prelude::*, time::TimeUpdateStrategy, transform::TransformPlugin,
+};
+use bevy_rapier2d::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use mind_your_sides::{
+    game::{
+        castle::{Castle, CastleAssets, CastleWall},
+        castle_type::CastleType,
+        enemies::{
+            definition::EnemyDefinitions, schedule::WaveSchedules, EnemyDeathEvent, EnemyKind,
+        },
+        mode::{GameMode, RunTimer},
+        rng::RequestedSeed,
+        shop::{ShopEvent, ShopSet},
+        simulation::SIMULATION_TIMESTEP,
+        upgrades::{
+            apply::{ApplyUpgradeEvent, UpgradeSet},
+            Upgrade, Upgrades,
+        },
+        weapons::WeaponsAssets,
+        East, GamePlugin, GameState, North, Side, South, West,
+    },
+    GameAssets, GlobalState,
+};
+
+const USAGE: &str = "usage: mys-sim [--games N] [--seed SEED] [--policy random|greedy|first] \
+                     [--mode normal|infinite] [--max-time SECONDS] \
+                     [--castle keep|bastion|arsenal|citadel] [--assets DIR]";
+
+/// Default time limit for a single game, so infinite mode ends
+const DEFAULT_MAX_TIME: f32 = 60.0 * 60.0;
+/// Updates in a row without a simulation tick after which
+/// the game is aborted. Policies should leave level ups
+/// and the shop within a few updates.
+const MAX_STALLED_UPDATES: u32 = 1000;
+
+/// How upgrades are picked on level up
+#[derive(Debug, Clone, Copy, Resource)]
+enum UpgradePolicy {
+    /// Random upgrade
+    Random,
+    /// Upgrade with most buffs and least debuffs
+    /// for the wall with the lowest health
+    Greedy,
+    /// Always the first upgrade
+    First,
+}
+
+impl UpgradePolicy {
+    fn choose(&self, upgrades: &Upgrades, walls: &[f32; 4], rng: &mut impl Rng) -> usize {
+        match self {
+            UpgradePolicy::Random => rng.gen_range(0..upgrades.upgrades.len()),
+            UpgradePolicy::Greedy => {
+                let weakest_side = walls
+                    .iter()
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .map(|(side, _)| side)
+                    .unwrap_or_default();
+                // first upgrade wins ties
+                upgrades
+                    .upgrades
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .max_by_key(|(_, upgrade)| side_score(upgrade, weakest_side))
+                    .map(|(index, _)| index)
+                    .unwrap_or_default()
+            }
+            UpgradePolicy::First => 0,
+        }
+    }
+}
+
+/// Number of buffs minus number of debuffs the upgrade
+/// gives to the side. Global upgrades count for every side.
+fn side_score(upgrade: &Upgrade, side: usize) -> i32 {
+    let (global_buffs, global_debuffs) = upgrade.global_upgrades();
+    let mut score = global_buffs.wall_upgrade.is_some() as i32
+        + global_buffs.weapon_upgrade.is_some() as i32
+        - global_debuffs.enemy_upgrade.is_some() as i32;
+
+    let (wall, weapon, enemy) = match side {
+        North::INDEX => {
+            let (buffs, debuffs) = upgrade.north_upgrades();
+            (
+                buffs.wall_upgrade.is_some(),
+                buffs.weapon_upgrade.is_some(),
+                debuffs.enemy_upgrade.is_some(),
+            )
+        }
+        South::INDEX => {
+            let (buffs, debuffs) = upgrade.south_upgrades();
+            (
+                buffs.wall_upgrade.is_some(),
+                buffs.weapon_upgrade.is_some(),
+                debuffs.enemy_upgrade.is_some(),
+            )
+        }
+        West::INDEX => {
+            let (buffs, debuffs) = upgrade.west_upgrades();
+            (
+                buffs.wall_upgrade.is_some(),
+                buffs.weapon_upgrade.is_some(),
+                debuffs.enemy_upgrade.is_some(),
+            )
+        }
+        _ => {
+            let (buffs, debuffs) = upgrade.east_upgrades();
+            (
+                buffs.wall_upgrade.is_some(),
+                buffs.weapon_upgrade.is_some(),
+                debuffs.enemy_upgrade.is_some(),
+            )
+        }
+    };
+    score += wall as i32 + weapon as i32 - enemy as i32;
+    score
+}
+
+/// Random source of the `UpgradePolicy::Random`.
+/// Separate from the game one, so policies
+/// do not change the run itself.
+#[derive(Resource)]
+struct PolicyRng(ChaCha8Rng);
+
+/// Enemies killed during the game
+#[derive(Debug, Default, Resource)]
+struct Kills(HashMap<EnemyKind, u32>);
+
+#[derive(Debug, Clone)]
+struct SimConfig {
+    games: u64,
+    seed: u64,
+    policy: UpgradePolicy,
+    mode: GameMode,
+    castle_type: CastleType,
+    max_time: f32,
+    /// Folder enemy definitions are read from
+    assets: PathBuf,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            games: 10,
+            seed: 0,
+            policy: UpgradePolicy::Random,
+            mode: GameMode::Normal,
+            castle_type: CastleType::Keep,
+            max_time: DEFAULT_MAX_TIME,
+            assets: PathBuf::from("assets"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct GameResult {
+    seed: u64,
+    outcome: GameState,
+    time: f32,
+    level: u32,
+    kills: HashMap<EnemyKind, u32>,
+    fallen_walls: Vec<&'static str>,
+    /// Game got stuck in the `outcome` state
+    aborted: bool,
+}
+
+pub fn run() {
+    let config = match parse_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}\n{USAGE}");
+            std::process::exit(1);
+        }
+    };
+
+    let enemy_definitions = match EnemyDefinitions::load_from_dir(&config.assets.join("enemies")) {
+        Ok(enemy_definitions) => enemy_definitions,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    let schedules = match WaveSchedules::load_from_dir(&config.assets.join("schedules")) {
+        Ok(schedules) => schedules,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+
+    let results = (config.seed..config.seed + config.games)
+        .map(|seed| {
+            let result = run_game(&config, &enemy_definitions, &schedules, seed);
+            print_result(&result);
+            result
+        })
+        .collect::<Vec<_>>();
+
+    print_summary(&results);
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<SimConfig, String> {
+    let mut config = SimConfig::default();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {arg}"))?;
+        match arg.as_str() {
+            "--games" => config.games = value.parse().map_err(|e| format!("--games: {e}"))?,
+            "--seed" => config.seed = value.parse().map_err(|e| format!("--seed: {e}"))?,
+            "--max-time" => {
+                config.max_time = value.parse().map_err(|e| format!("--max-time: {e}"))?
+            }
+            "--policy" => {
+                config.policy = match value.as_str() {
+                    "random" => UpgradePolicy::Random,
+                    "greedy" => UpgradePolicy::Greedy,
+                    "first" => UpgradePolicy::First,
+                    _ => return Err(format!("unknown policy {value}")),
+                }
+            }
+            "--mode" => {
+                config.mode = match value.as_str() {
+                    "normal" => GameMode::Normal,
+                    "infinite" => GameMode::Infinite,
+                    _ => return Err(format!("unknown mode {value}")),
+                }
+            }
+            "--assets" => config.assets = PathBuf::from(value),
+            "--castle" => {
+                config.castle_type = match value.as_str() {
+                    "keep" => CastleType::Keep,
+                    "bastion" => CastleType::Bastion,
+                    "arsenal" => CastleType::Arsenal,
+                    "citadel" => CastleType::Citadel,
+                    _ => return Err(format!("unknown castle {value}")),
+                }
+            }
+            _ => return Err(format!("unknown argument {arg}")),
+        }
+    }
+    Ok(config)
+}
+
+/// Builds a new app for every game, so nothing
+/// leaks from one game into another
+fn build_app(
+    config: &SimConfig,
+    enemy_definitions: &EnemyDefinitions,
+    schedules: &WaveSchedules,
+    seed: u64,
+) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(TransformPlugin)
+        .add_plugin(HierarchyPlugin)
+        .add_plugin(RapierPhysicsPlugin::<NoUserData>::default().with_default_system_setup(false))
+        .add_state::<GlobalState>()
+        // every update advances game by exactly one simulation tick
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            SIMULATION_TIMESTEP,
+        )))
+        // presentation is not needed, so assets stay empty
+        .init_resource::<GameAssets>()
+        .init_resource::<CastleAssets>()
+        .init_resource::<WeaponsAssets>()
+        .add_plugin(GamePlugin)
+        .insert_resource(enemy_definitions.clone())
+        .insert_resource(schedules.clone())
+        .insert_resource(RequestedSeed(Some(seed)))
+        .insert_resource(config.mode)
+        .insert_resource(config.castle_type)
+        .insert_resource(config.policy)
+        .insert_resource(PolicyRng(ChaCha8Rng::seed_from_u64(seed)))
+        .init_resource::<Kills>()
+        .add_systems((
+            count_kills::<North>,
+            count_kills::<South>,
+            count_kills::<West>,
+            count_kills::<East>,
+        ))
+        .add_system(
+            choose_upgrade
+                .before(UpgradeSet::Dispatch)
+                .in_set(OnUpdate(GameState::LevelUp)),
+        )
+        .add_system(leave_shop.before(ShopSet).in_set(OnUpdate(GameState::Shop)));
+    app.world
+        .insert_resource(NextState(Some(GlobalState::InGame)));
+    app
+}
+
+fn run_game(
+    config: &SimConfig,
+    enemy_definitions: &EnemyDefinitions,
+    schedules: &WaveSchedules,
+    seed: u64,
+) -> GameResult {
+    let mut app = build_app(config, enemy_definitions, schedules, seed);
+
+    let mut last_time = 0.0;
+    let mut stalled_updates = 0;
+    loop {
+        app.update();
+
+        let outcome = app.world.resource::<State<GameState>>().0.clone();
+        let time = app
+            .world
+            .get_resource::<RunTimer>()
+            .map_or(0.0, |run_timer| run_timer.time_passed);
+        if time == last_time {
+            stalled_updates += 1;
+        } else {
+            stalled_updates = 0;
+            last_time = time;
+        }
+        let aborted = MAX_STALLED_UPDATES <= stalled_updates;
+        if matches!(outcome, GameState::GameOver | GameState::Victory)
+            || config.max_time <= time
+            || aborted
+        {
+            let (level, core_fallen) = app
+                .world
+                .query::<&Castle>()
+                .get_single(&app.world)
+                .map_or((0, false), |castle| (castle.level, castle.core_health <= 0));
+            let fallen_walls = [
+                fallen_wall::<North>(&mut app.world),
+                fallen_wall::<South>(&mut app.world),
+                fallen_wall::<West>(&mut app.world),
+                fallen_wall::<East>(&mut app.world),
+                core_fallen.then_some("Core"),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            let kills = std::mem::take(&mut app.world.resource_mut::<Kills>().0);
+
+            return GameResult {
+                seed,
+                outcome,
+                time,
+                level,
+                kills,
+                fallen_walls,
+                aborted,
+            };
+        }
+    }
+}
+
+fn fallen_wall<S: Side>(world: &mut World) -> Option<&'static str> {
+    let fallen = world
+        .query::<&CastleWall<S>>()
+        .iter(world)
+        .any(|wall| wall.health <= 0);
+    fallen.then_some(side_name::<S>())
+}
+
+fn side_name<S: Side>() -> &'static str {
+    match S::INDEX {
+        North::INDEX => "North",
+        South::INDEX => "South",
+        West::INDEX => "West",
+        _ => "East",
+    }
+}
+
+fn count_kills<S: Side>(
+    mut kills: ResMut<Kills>,
+    mut death_events: EventReader<EnemyDeathEvent<S>>,
+) {
+    for event in death_events.iter() {
+        *kills.0.entry(event.kind).or_default() += 1;
+    }
+}
+
+fn wall_health<S: Side>(walls: &Query<&CastleWall<S>>) -> f32 {
+    walls
+        .get_single()
+        .map_or(1.0, |wall| wall.health as f32 / wall.max_health as f32)
+}
+
+fn choose_upgrade(
+    policy: Res<UpgradePolicy>,
+    upgrades: Res<Upgrades>,
+    north: Query<&CastleWall<North>>,
+    south: Query<&CastleWall<South>>,
+    west: Query<&CastleWall<West>>,
+    east: Query<&CastleWall<East>>,
+    mut rng: ResMut<PolicyRng>,
+    mut apply_upgrade_event: EventWriter<ApplyUpgradeEvent>,
+) {
+    let walls = [
+        wall_health(&north),
+        wall_health(&south),
+        wall_health(&west),
+        wall_health(&east),
+    ];
+    let index = policy.choose(&upgrades, &walls, &mut rng.0);
+    if let Some(event) = ApplyUpgradeEvent::from_index(index) {
+        apply_upgrade_event.send(event);
+    }
+}
+
+/// Gold is not spent, policies only choose upgrades
+fn leave_shop(mut shop_events: EventWriter<ShopEvent>) {
+    shop_events.send(ShopEvent::Leave);
+}
+
+fn print_result(result: &GameResult) {
+    let outcome = match result.outcome {
+        _ if result.aborted => format!("aborted in {:?}", result.outcome),
+        GameState::GameOver => "lost".to_string(),
+        GameState::Victory => "won".to_string(),
+        _ => "time limit".to_string(),
+    };
+    println!(
+        "seed {}: {outcome} after {:.1}s at level {}",
+        result.seed, result.time, result.level
+    );
+    if !result.fallen_walls.is_empty() {
+        println!("  fallen walls: {}", result.fallen_walls.join(", "));
+    }
+    println!("  kills: {}", format_kills(&result.kills));
+}
+
+fn print_summary(results: &[GameResult]) {
+    if results.is_empty() {
+        return;
+    }
+
+    let games = results.len() as f32;
+    let wins = results
+        .iter()
+        .filter(|result| result.outcome == GameState::Victory)
+        .count();
+    let aborted = results.iter().filter(|result| result.aborted).count();
+    let average_time = results.iter().map(|result| result.time).sum::<f32>() / games;
+    let average_level = results
+        .iter()
+        .map(|result| result.level as f32)
+        .sum::<f32>()
+        / games;
+
+    let mut fallen_walls = HashMap::<&str, u32>::new();
+    let mut kills = HashMap::<EnemyKind, u32>::new();
+    for result in results {
+        for wall in result.fallen_walls.iter() {
+            *fallen_walls.entry(wall).or_default() += 1;
+        }
+        for (kind, number) in result.kills.iter() {
+            *kills.entry(*kind).or_default() += number;
+        }
+    }
+    let mut fallen_walls = fallen_walls.into_iter().collect::<Vec<_>>();
+    fallen_walls.sort();
+
+    println!();
+    println!("games: {}, won: {wins}, aborted: {aborted}", results.len());
+    println!("average survival time: {average_time:.1}s");
+    println!("average level: {average_level:.1}");
+    println!(
+        "fallen walls: {}",
+        fallen_walls
+            .iter()
+            .map(|(wall, number)| format!("{wall} {number}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    println!("kills: {}", format_kills(&kills));
+}
+
+fn format_kills(kills: &HashMap<EnemyKind, u32>) -> String {
+    let mut kills = kills
+        .iter()
+        .map(|(kind, number)| format!("{kind:?} {number}"))
+        .collect::<Vec<_>>();
+    kills.sort();
+    kills.join(", ")
+}
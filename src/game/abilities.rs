@@ -0,0 +1,406 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::GlobalState;
+
+use super::{
+    castle::CastleWall,
+    castle_type::CastleType,
+    damage::{
+        area::{DamageArea, DamageAreaBundle},
+        DamageSource, DamageType, EnemyDamageEvent,
+    },
+    enemies::Enemy,
+    simulation::{simulation_tick, SimulationSet},
+    status::{StatusChance, StatusEffect, StatusKind},
+    weapons::WeaponsAssets,
+    East, North, Side, South, West,
+};
+
+const REPAIR_HEAL: f32 = 100.0;
+const REPAIR_COOLDOWN: f32 = 40.0;
+
+const VOLLEY_DAMAGE: f32 = 60.0;
+const VOLLEY_COOLDOWN: f32 = 25.0;
+
+const FREEZE_DURATION: f32 = 4.0;
+const FREEZE_COOLDOWN: f32 = 45.0;
+const FREEZE_COLOR: Color = Color::rgb(0.5, 0.8, 1.0);
+
+const BURNING_OIL_SIZE: f32 = 60.0;
+const BURNING_OIL_DAMAGE: f32 = 15.0;
+const BURNING_OIL_ATTACK_SPEED: f32 = 4.0;
+const BURNING_OIL_LIFESPAN: f32 = 5.0;
+const BURNING_OIL_COOLDOWN: f32 = 30.0;
+/// Burning damage per second as a part of oil damage
+const BURNING_OIL_BURN_POWER: f32 = 0.5;
+const BURNING_OIL_BURN_DURATION: f32 = 3.0;
+
+pub struct AbilitiesPlugin;
+
+impl Plugin for AbilitiesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AbilityEvent<North>>()
+            .add_event::<AbilityEvent<South>>()
+            .add_event::<AbilityEvent<West>>()
+            .add_event::<AbilityEvent<East>>()
+            .init_resource::<CastleAbilities>()
+            .init_resource::<AbilityRequests>()
+            .add_system(setup.in_schedule(OnEnter(GlobalState::InGame)))
+            .add_systems(
+                (ability_cooldowns, use_abilities)
+                    .chain()
+                    .after(simulation_tick)
+                    .in_set(SimulationSet::Progress)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (
+                    apply_ability::<North>,
+                    apply_ability::<South>,
+                    apply_ability::<West>,
+                    apply_ability::<East>,
+                    frozen_update,
+                )
+                    .in_set(SimulationSet::Act)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Ability {
+    /// Heals the wall
+    Repair,
+    /// Damages every enemy on the side
+    Volley,
+    /// Stops every enemy on the side
+    Freeze,
+    /// Sets ground in front of the wall on fire
+    BurningOil,
+}
+
+impl Ability {
+    pub const ALL: [Ability; 4] = [
+        Ability::Repair,
+        Ability::Volley,
+        Ability::Freeze,
+        Ability::BurningOil,
+    ];
+
+    fn cooldown(&self) -> f32 {
+        match self {
+            Ability::Repair => REPAIR_COOLDOWN,
+            Ability::Volley => VOLLEY_COOLDOWN,
+            Ability::Freeze => FREEZE_COOLDOWN,
+            Ability::BurningOil => BURNING_OIL_COOLDOWN,
+        }
+    }
+}
+
+/// Side an ability is used on
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AbilityTarget {
+    #[default]
+    North,
+    South,
+    West,
+    East,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbilityState {
+    pub cooldown: Timer,
+    /// Bonus to the effect of the ability
+    pub power: f32,
+    /// Bonus to the recharge speed of the ability
+    pub recharge: f32,
+}
+
+impl AbilityState {
+    fn new(cooldown: f32) -> Self {
+        let mut cooldown = Timer::from_seconds(cooldown, TimerMode::Once);
+        // abilities are ready at the start of the run
+        cooldown.tick(cooldown.duration());
+        Self {
+            cooldown,
+            power: 0.0,
+            recharge: 0.0,
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.cooldown.finished()
+    }
+
+    /// Restarts the cooldown shortened by the recharge bonus and returns the power of the use,
+    /// or `None` if the ability is still on cooldown
+    fn trigger(&mut self, ability: Ability) -> Option<f32> {
+        if !self.is_ready() {
+            return None;
+        }
+        self.cooldown =
+            Timer::from_seconds(ability.cooldown() / (1.0 + self.recharge), TimerMode::Once);
+        Some(self.power)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Resource)]
+pub struct CastleAbilities {
+    pub repair: AbilityState,
+    pub volley: AbilityState,
+    pub freeze: AbilityState,
+    pub burning_oil: AbilityState,
+}
+
+impl Default for CastleAbilities {
+    fn default() -> Self {
+        Self {
+            repair: AbilityState::new(REPAIR_COOLDOWN),
+            volley: AbilityState::new(VOLLEY_COOLDOWN),
+            freeze: AbilityState::new(FREEZE_COOLDOWN),
+            burning_oil: AbilityState::new(BURNING_OIL_COOLDOWN),
+        }
+    }
+}
+
+impl CastleAbilities {
+    pub fn get(&self, ability: Ability) -> &AbilityState {
+        match ability {
+            Ability::Repair => &self.repair,
+            Ability::Volley => &self.volley,
+            Ability::Freeze => &self.freeze,
+            Ability::BurningOil => &self.burning_oil,
+        }
+    }
+
+    pub fn get_mut(&mut self, ability: Ability) -> &mut AbilityState {
+        match ability {
+            Ability::Repair => &mut self.repair,
+            Ability::Volley => &mut self.volley,
+            Ability::Freeze => &mut self.freeze,
+            Ability::BurningOil => &mut self.burning_oil,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AbilityRequest {
+    pub ability: Ability,
+    pub target: AbilityTarget,
+}
+
+/// Abilities requested by the player.
+/// They are used on the next simulation tick,
+/// so the run stays reproducible.
+#[derive(Debug, Default, Resource)]
+pub struct AbilityRequests(pub Vec<AbilityRequest>);
+
+/// Event to apply ability effect on the side
+pub struct AbilityEvent<S: Side> {
+    pub ability: Ability,
+    pub power: f32,
+    _phantom: PhantomData<S>,
+}
+
+impl<S: Side> AbilityEvent<S> {
+    pub fn new(ability: Ability, power: f32) -> Self {
+        Self {
+            ability,
+            power,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Enemy that does not move or attack
+#[derive(Debug, Clone, Component, Serialize, Deserialize)]
+pub struct Frozen {
+    timer: Timer,
+}
+
+impl Frozen {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(duration, TimerMode::Once),
+        }
+    }
+}
+
+fn setup(
+    castle_type: Res<CastleType>,
+    mut abilities: ResMut<CastleAbilities>,
+    mut requests: ResMut<AbilityRequests>,
+) {
+    *abilities = CastleAbilities::default();
+    let recharge = castle_type.definition().ability_recharge();
+    for ability in Ability::ALL {
+        abilities.get_mut(ability).recharge = recharge;
+    }
+    requests.0.clear();
+}
+
+fn ability_cooldowns(fixed_time: Res<FixedTime>, mut abilities: ResMut<CastleAbilities>) {
+    for ability in Ability::ALL {
+        abilities.get_mut(ability).cooldown.tick(fixed_time.period);
+    }
+}
+
+/// Uses requested abilities which are not on cooldown
+pub fn use_abilities(
+    mut requests: ResMut<AbilityRequests>,
+    mut abilities: ResMut<CastleAbilities>,
+    mut north_events: EventWriter<AbilityEvent<North>>,
+    mut south_events: EventWriter<AbilityEvent<South>>,
+    mut west_events: EventWriter<AbilityEvent<West>>,
+    mut east_events: EventWriter<AbilityEvent<East>>,
+) {
+    for request in requests.0.drain(..) {
+        let Some(power) = abilities.get_mut(request.ability).trigger(request.ability) else {
+            continue;
+        };
+        match request.target {
+            AbilityTarget::North => north_events.send(AbilityEvent::new(request.ability, power)),
+            AbilityTarget::South => south_events.send(AbilityEvent::new(request.ability, power)),
+            AbilityTarget::West => west_events.send(AbilityEvent::new(request.ability, power)),
+            AbilityTarget::East => east_events.send(AbilityEvent::new(request.ability, power)),
+        }
+    }
+}
+
+fn apply_ability<S: Side>(
+    weapon_assets: Res<WeaponsAssets>,
+    mut commands: Commands,
+    mut wall: Query<(&Transform, &mut CastleWall<S>)>,
+    enemies: Query<Entity, With<Enemy<S>>>,
+    mut ability_events: EventReader<AbilityEvent<S>>,
+    mut damage_events: EventWriter<EnemyDamageEvent<S>>,
+) {
+    let (wall_transform, mut wall) = wall.single_mut();
+
+    for event in ability_events.iter() {
+        let multiplier = 1.0 + event.power;
+        match event.ability {
+            Ability::Repair => wall.heal((REPAIR_HEAL * multiplier) as i32),
+            Ability::Volley => {
+                let damage = (VOLLEY_DAMAGE * multiplier) as i32;
+                for enemy in enemies.iter() {
+                    damage_events.send(EnemyDamageEvent::new(
+                        enemy,
+                        damage,
+                        DamageType::Piercing,
+                        false,
+                        DamageSource::Ability,
+                    ));
+                }
+            }
+            Ability::Freeze => {
+                for enemy in enemies.iter() {
+                    commands
+                        .entity(enemy)
+                        .insert(Frozen::new(FREEZE_DURATION * multiplier));
+                }
+            }
+            Ability::BurningOil => {
+                let damage = (BURNING_OIL_DAMAGE * multiplier) as i32;
+                let position = wall_transform.translation
+                    + (S::DIRECTION * (wall.half_thickness + BURNING_OIL_SIZE)).extend(0.0);
+                commands.spawn(DamageAreaBundle::<S>::new(
+                    weapon_assets.fire.clone(),
+                    position,
+                    DamageArea::new(
+                        BURNING_OIL_SIZE,
+                        damage,
+                        damage,
+                        0.0,
+                        BURNING_OIL_ATTACK_SPEED,
+                        BURNING_OIL_LIFESPAN,
+                        DamageSource::Ability,
+                        DamageType::Fire,
+                    )
+                    .with_effects(vec![StatusChance::new(
+                        StatusEffect::new(
+                            StatusKind::Burning,
+                            damage as f32 * BURNING_OIL_BURN_POWER,
+                            BURNING_OIL_BURN_DURATION,
+                        ),
+                        1.0,
+                    )]),
+                ));
+            }
+        }
+    }
+}
+
+fn frozen_update(
+    fixed_time: Res<FixedTime>,
+    mut commands: Commands,
+    mut enemies: Query<(Entity, &mut Frozen, &mut Velocity, &mut TextureAtlasSprite)>,
+) {
+    for (entity, mut frozen, mut velocity, mut sprite) in enemies.iter_mut() {
+        velocity.linvel = Vec2::ZERO;
+        if frozen.timer.tick(fixed_time.period).finished() {
+            sprite.color = Color::WHITE;
+            commands.entity(entity).remove::<Frozen>();
+        } else {
+            sprite.color = FREEZE_COLOR;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::game::upgrades::{apply::apply_ability_upgrade, AbilityUpgrade};
+
+    #[test]
+    fn abilities_are_ready_at_start() {
+        let abilities = CastleAbilities::default();
+        for ability in Ability::ALL {
+            assert!(abilities.get(ability).is_ready());
+        }
+    }
+
+    #[test]
+    fn ability_on_cooldown_is_not_used() {
+        let mut state = AbilityState::new(REPAIR_COOLDOWN);
+        assert_eq!(state.trigger(Ability::Repair), Some(0.0));
+        assert_eq!(state.trigger(Ability::Repair), None);
+
+        state
+            .cooldown
+            .tick(Duration::from_secs_f32(REPAIR_COOLDOWN));
+        assert_eq!(state.trigger(Ability::Repair), Some(0.0));
+    }
+
+    #[test]
+    fn recharge_upgrade_shortens_cooldown() {
+        let mut abilities = CastleAbilities::default();
+        apply_ability_upgrade(&mut abilities, AbilityUpgrade::VolleyRecharge(25.0));
+
+        let state = abilities.get_mut(Ability::Volley);
+        state.trigger(Ability::Volley);
+        assert_eq!(
+            state.cooldown.duration().as_secs_f32(),
+            VOLLEY_COOLDOWN / 1.25
+        );
+    }
+
+    #[test]
+    fn power_upgrade_is_a_percentage() {
+        let mut abilities = CastleAbilities::default();
+        apply_ability_upgrade(&mut abilities, AbilityUpgrade::RepairPower(25.0));
+        apply_ability_upgrade(&mut abilities, AbilityUpgrade::RepairPower(25.0));
+
+        assert_eq!(
+            abilities.get_mut(Ability::Repair).trigger(Ability::Repair),
+            Some(0.5)
+        );
+        assert_eq!(abilities.get(Ability::Freeze).power, 0.0);
+    }
+}
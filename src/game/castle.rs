@@ -3,34 +3,54 @@ use std::marker::PhantomData;
 use bevy::{prelude::*, sprite::Anchor};
 use bevy_asset_loader::prelude::*;
 use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{utils::remove_all_with, GlobalState};
 
 use super::{
+    castle_type::{CastleType, StartingWeapons},
+    simulation::{simulation_tick, ResolveSet, SimulationSet, SimulationTick, WALL_GROUP},
     weapons::{crossbow::CrossbowBundle, molotov::MolotovBundle},
     East, GameState, North, Side, South, West,
 };
 
-const WALL_HEALTH: i32 = 100;
-
 const CASTLE_FIRST_LEVEL_EXP: u32 = 10;
 const CASTLE_NEXT_LEVEL_EXP_GROWTH: f32 = 1.2;
+/// Health of the castle core, damaged by flying enemies
+const CASTLE_CORE_HEALTH: i32 = 300;
 
 pub struct CastlePlugin;
 
 impl Plugin for CastlePlugin {
     fn build(&self, app: &mut App) {
-        app.add_collection_to_loading_state::<_, CastleAssets>(GlobalState::AssetLoading)
+        app.init_resource::<CastleType>()
             .add_system(setup.in_schedule(OnEnter(GlobalState::InGame)))
             .add_systems(
                 (
-                    castle_level_up,
+                    regenerate_wall::<North>,
+                    regenerate_wall::<South>,
+                    regenerate_wall::<West>,
+                    regenerate_wall::<East>,
+                )
+                    .after(simulation_tick)
+                    .in_set(SimulationSet::Progress)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (
+                    check_core_destroyed,
                     check_wall_destroyed::<North>,
                     check_wall_destroyed::<South>,
                     check_wall_destroyed::<West>,
                     check_wall_destroyed::<East>,
                 )
-                    .in_set(OnUpdate(GameState::InGame)),
+                    .in_set(ResolveSet::Defeat)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(
+                castle_level_up
+                    .in_set(ResolveSet::LevelUp)
+                    .in_schedule(CoreSchedule::FixedUpdate),
             )
             .add_system(remove_all_with::<CastleMarker>.in_schedule(OnExit(GlobalState::InGame)))
             .add_system(
@@ -39,7 +59,7 @@ impl Plugin for CastlePlugin {
     }
 }
 
-#[derive(AssetCollection, Resource)]
+#[derive(Default, AssetCollection, Resource)]
 pub struct CastleAssets {
     #[asset(path = "sprites/castle.png")]
     pub castle: Handle<Image>,
@@ -53,12 +73,19 @@ pub struct CastleAssets {
     pub wall_east: Handle<Image>,
 }
 
-#[derive(Component)]
+#[derive(Clone, Serialize, Deserialize, Component)]
 pub struct Castle {
     pub level: u32,
     pub exp: u32,
     pub next_level_exp: u32,
     pub next_level_exp_growth: f32,
+    /// Upgrade choices given without a level up, e.g. for killing a boss
+    pub bonus_upgrades: u32,
+    /// Flying enemies pass the walls and damage the core instead
+    pub core_health: i32,
+    pub core_max_health: i32,
+    /// Collected from enemy loot, spent in the shop
+    pub gold: u32,
 }
 
 #[derive(Component)]
@@ -78,17 +105,25 @@ impl Default for CastleBundle {
                 exp: 0,
                 next_level_exp: CASTLE_FIRST_LEVEL_EXP,
                 next_level_exp_growth: CASTLE_NEXT_LEVEL_EXP_GROWTH,
+                bonus_upgrades: 0,
+                core_health: CASTLE_CORE_HEALTH,
+                core_max_health: CASTLE_CORE_HEALTH,
+                gold: 0,
             },
             marker: CastleMarker,
         }
     }
 }
 
-#[derive(Component)]
+#[derive(Clone, Serialize, Deserialize, Component)]
+#[serde(bound = "")]
 pub struct CastleWall<S: Side> {
     pub health: i32,
     pub max_health: i32,
+    /// Flat damage removed from every hit
+    pub armor: i32,
     pub half_thickness: f32,
+    #[serde(skip)]
     _phantom: PhantomData<S>,
 }
 
@@ -97,6 +132,7 @@ impl<S: Side> CastleWall<S> {
         Self {
             health,
             max_health: health,
+            armor: 0,
             half_thickness,
             _phantom: PhantomData,
         }
@@ -107,6 +143,14 @@ impl<S: Side> CastleWall<S> {
         self.max_health += hp;
     }
 
+    /// Damage left after armor, hits always deal at least 1
+    pub fn damage_taken(&self, damage: i32) -> i32 {
+        if damage <= 0 {
+            return 0;
+        }
+        (damage - self.armor).max(1)
+    }
+
     pub fn heal(&mut self, hp: i32) {
         self.health += hp;
         if self.max_health < self.health {
@@ -122,11 +166,8 @@ pub struct CastleWallMarker;
 pub struct CastleWallBundle<S: Side> {
     rigid_body: RigidBody,
     collider: Collider,
+    collision_groups: CollisionGroups,
     wall: CastleWall<S>,
-    #[bundle]
-    crossbow: CrossbowBundle<S>,
-    #[bundle]
-    molotov: MolotovBundle<S>,
     marker: CastleWallMarker,
 }
 
@@ -135,9 +176,8 @@ impl<S: Side> CastleWallBundle<S> {
         Self {
             rigid_body: RigidBody::Fixed,
             collider: Collider::cuboid(x_len / 2.0, y_len / 2.0),
+            collision_groups: CollisionGroups::new(WALL_GROUP, Group::ALL),
             wall: CastleWall::new(health, y_len / 2.0),
-            crossbow: Default::default(),
-            molotov: Default::default(),
             marker: CastleWallMarker,
         }
     }
@@ -146,17 +186,19 @@ impl<S: Side> CastleWallBundle<S> {
         Self {
             rigid_body: RigidBody::Fixed,
             collider: Collider::cuboid(x_len / 2.0, y_len / 2.0),
+            collision_groups: CollisionGroups::new(WALL_GROUP, Group::ALL),
             wall: CastleWall::new(health, x_len / 2.0),
-            crossbow: Default::default(),
-            molotov: Default::default(),
             marker: CastleWallMarker,
         }
     }
 }
 
-/// Sets up castle in the center of the map
-/// with 4 walls
-fn setup(castle_assets: Res<CastleAssets>, mut commands: Commands) {
+/// Sets up castle of the chosen type
+/// in the center of the map with 4 walls
+fn setup(castle_assets: Res<CastleAssets>, castle_type: Res<CastleType>, mut commands: Commands) {
+    let definition = castle_type.definition();
+    let thickness = definition.wall_thickness;
+
     commands
         .spawn(SpriteBundle {
             transform: Transform::from_translation(Vec3::new(0.0, 0.0, 3.0)),
@@ -167,8 +209,9 @@ fn setup(castle_assets: Res<CastleAssets>, mut commands: Commands) {
 
     // TODO: Refactor hardcoded values
     // North
-    commands
-        .spawn(SpriteBundle {
+    spawn_wall(
+        &mut commands,
+        SpriteBundle {
             sprite: Sprite {
                 custom_size: Some(Vec2 { x: 386.0, y: 92.0 }),
                 anchor: Anchor::BottomCenter,
@@ -177,17 +220,20 @@ fn setup(castle_assets: Res<CastleAssets>, mut commands: Commands) {
             transform: Transform::from_translation(Vec3::new(0.0, 100.0, 2.0)),
             texture: castle_assets.wall_north.clone(),
             ..default()
-        })
-        .insert(CastleWallBundle::<North>::new_horizontal(
-            WALL_HEALTH,
+        },
+        CastleWallBundle::<North>::new_horizontal(
+            definition.wall_health[North::INDEX],
             386.0,
             // we need custom value for north wall, so that
             // enemies don't go behind it
-            150.0,
-        ));
+            150.0 * thickness,
+        ),
+        definition.weapons[North::INDEX],
+    );
     // South
-    commands
-        .spawn(SpriteBundle {
+    spawn_wall(
+        &mut commands,
+        SpriteBundle {
             sprite: Sprite {
                 custom_size: Some(Vec2 { x: 386.0, y: 92.0 }),
                 anchor: Anchor::BottomCenter,
@@ -196,15 +242,18 @@ fn setup(castle_assets: Res<CastleAssets>, mut commands: Commands) {
             transform: Transform::from_translation(Vec3::new(0.0, -193.0, 4.0)),
             texture: castle_assets.wall_south.clone(),
             ..default()
-        })
-        .insert(CastleWallBundle::<South>::new_horizontal(
-            WALL_HEALTH,
+        },
+        CastleWallBundle::<South>::new_horizontal(
+            definition.wall_health[South::INDEX],
             386.0,
-            24.0,
-        ));
+            24.0 * thickness,
+        ),
+        definition.weapons[South::INDEX],
+    );
     // West
-    commands
-        .spawn(SpriteBundle {
+    spawn_wall(
+        &mut commands,
+        SpriteBundle {
             sprite: Sprite {
                 custom_size: Some(Vec2 { x: 24.0, y: 386.0 }),
                 ..default()
@@ -212,15 +261,18 @@ fn setup(castle_assets: Res<CastleAssets>, mut commands: Commands) {
             transform: Transform::from_translation(Vec3::new(-204.0, 0.0, 3.0)),
             texture: castle_assets.wall_west.clone(),
             ..default()
-        })
-        .insert(CastleWallBundle::<West>::new_vertical(
-            WALL_HEALTH,
-            24.0,
+        },
+        CastleWallBundle::<West>::new_vertical(
+            definition.wall_health[West::INDEX],
+            24.0 * thickness,
             386.0,
-        ));
+        ),
+        definition.weapons[West::INDEX],
+    );
     // East
-    commands
-        .spawn(SpriteBundle {
+    spawn_wall(
+        &mut commands,
+        SpriteBundle {
             sprite: Sprite {
                 custom_size: Some(Vec2 { x: 24.0, y: 386.0 }),
                 ..default()
@@ -228,17 +280,49 @@ fn setup(castle_assets: Res<CastleAssets>, mut commands: Commands) {
             transform: Transform::from_translation(Vec3::new(204.0, 0.0, 3.0)),
             texture: castle_assets.wall_east.clone(),
             ..default()
-        })
-        .insert(CastleWallBundle::<East>::new_vertical(
-            WALL_HEALTH,
-            24.0,
+        },
+        CastleWallBundle::<East>::new_vertical(
+            definition.wall_health[East::INDEX],
+            24.0 * thickness,
             386.0,
-        ));
+        ),
+        definition.weapons[East::INDEX],
+    );
+}
+
+fn spawn_wall<S: Side>(
+    commands: &mut Commands,
+    sprite: SpriteBundle,
+    wall: CastleWallBundle<S>,
+    weapons: StartingWeapons,
+) {
+    let mut entity = commands.spawn((sprite, wall));
+    if weapons.crossbow {
+        entity.insert(CrossbowBundle::<S>::default());
+    }
+    if weapons.molotov {
+        entity.insert(MolotovBundle::<S>::default());
+    }
+}
+
+fn regenerate_wall<S: Side>(
+    tick: Res<SimulationTick>,
+    castle_type: Res<CastleType>,
+    mut wall: Query<&mut CastleWall<S>>,
+) {
+    let heal = castle_type.definition().regeneration(tick.0);
+    if heal != 0 {
+        wall.single_mut().heal(heal);
+    }
 }
 
 fn castle_level_up(mut castle: Query<&mut Castle>, mut game_state: ResMut<NextState<GameState>>) {
-    let mut castle = castle.single_mut();
+    // run ended on this tick
+    if game_state.0.is_some() {
+        return;
+    }
 
+    let mut castle = castle.single_mut();
     if castle.exp >= castle.next_level_exp {
         castle.level += 1;
         castle.exp -= castle.next_level_exp;
@@ -246,6 +330,15 @@ fn castle_level_up(mut castle: Query<&mut Castle>, mut game_state: ResMut<NextSt
             (castle.next_level_exp as f32 * castle.next_level_exp_growth) as u32;
 
         game_state.set(GameState::LevelUp);
+    } else if 0 < castle.bonus_upgrades {
+        castle.bonus_upgrades -= 1;
+        game_state.set(GameState::LevelUp);
+    }
+}
+
+fn check_core_destroyed(castle: Query<&Castle>, mut game_state: ResMut<NextState<GameState>>) {
+    if castle.single().core_health <= 0 {
+        game_state.set(GameState::GameOver);
     }
 }
 
@@ -258,3 +351,25 @@ fn check_wall_destroyed<S: Side>(
         game_state.set(GameState::GameOver);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn armor_reduces_wall_damage() {
+        let mut wall = CastleWall::<North>::new(100, 1.0);
+        wall.armor = 4;
+        assert_eq!(wall.damage_taken(10), 6);
+    }
+
+    #[test]
+    fn wall_hits_deal_at_least_one_damage() {
+        let mut wall = CastleWall::<North>::new(100, 1.0);
+        wall.armor = 50;
+        assert_eq!(wall.damage_taken(10), 1);
+        // nothing to reduce
+        assert_eq!(wall.damage_taken(0), 0);
+        assert_eq!(wall.damage_taken(-3), 0);
+    }
+}
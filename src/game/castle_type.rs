@@ -0,0 +1,222 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::profile::PlayerProfile;
+
+use super::{simulation::SIMULATION_TIMESTEP, weapons::GlobalWeaponBuffs};
+
+/// Hp every wall heals with `CastlePerk::Regeneration`
+const REGENERATION_HEAL: i32 = 1;
+/// Number of ticks between regeneration heals
+const REGENERATION_INTERVAL: u64 = 120;
+/// Bonus experience with `CastlePerk::Bounty`
+const BOUNTY_EXP: f32 = 0.2;
+/// Bonus ability recharge speed with `CastlePerk::Vigilance`
+const VIGILANCE_RECHARGE: f32 = 0.25;
+
+/// Castle the run is played with.
+/// New types are unlocked across the sessions.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Resource)]
+pub enum CastleType {
+    /// Balanced castle available from the start
+    #[default]
+    Keep,
+    /// Sturdy walls that heal themselves, but only crossbows
+    Bastion,
+    /// Fragile walls with strong weapons
+    Arsenal,
+    /// North and south are fortified, abilities recharge faster
+    Citadel,
+}
+
+impl CastleType {
+    pub const ALL: [CastleType; 4] = [
+        CastleType::Keep,
+        CastleType::Bastion,
+        CastleType::Arsenal,
+        CastleType::Citadel,
+    ];
+
+    pub fn definition(&self) -> CastleDefinition {
+        match self {
+            CastleType::Keep => CastleDefinition {
+                wall_health: [100; 4],
+                wall_thickness: 1.0,
+                weapons: [StartingWeapons::ALL; 4],
+                weapon_buffs: GlobalWeaponBuffs::default(),
+                perk: None,
+            },
+            CastleType::Bastion => CastleDefinition {
+                wall_health: [150; 4],
+                wall_thickness: 1.5,
+                weapons: [StartingWeapons::CROSSBOW; 4],
+                weapon_buffs: GlobalWeaponBuffs::default(),
+                perk: Some(CastlePerk::Regeneration),
+            },
+            CastleType::Arsenal => CastleDefinition {
+                wall_health: [80; 4],
+                wall_thickness: 1.0,
+                weapons: [StartingWeapons::ALL; 4],
+                weapon_buffs: GlobalWeaponBuffs {
+                    damage: 0.15,
+                    crit_chance: 0.05,
+                    ..default()
+                },
+                perk: Some(CastlePerk::Bounty),
+            },
+            CastleType::Citadel => CastleDefinition {
+                wall_health: [130, 130, 90, 90],
+                wall_thickness: 1.2,
+                weapons: [
+                    StartingWeapons::ALL,
+                    StartingWeapons::ALL,
+                    StartingWeapons::CROSSBOW,
+                    StartingWeapons::CROSSBOW,
+                ],
+                weapon_buffs: GlobalWeaponBuffs::default(),
+                perk: Some(CastlePerk::Vigilance),
+            },
+        }
+    }
+
+    pub fn unlock(&self) -> Unlock {
+        match self {
+            CastleType::Keep => Unlock::Always,
+            CastleType::Bastion => Unlock::Stage(3),
+            CastleType::Arsenal => Unlock::Level(20),
+            CastleType::Citadel => Unlock::Victory,
+        }
+    }
+
+    pub fn is_unlocked(&self, profile: &PlayerProfile) -> bool {
+        self.unlock().is_met(profile)
+    }
+
+    /// Cycles through all castle types
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|t| t == self).unwrap_or_default();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn previous(&self) -> Self {
+        let index = Self::ALL.iter().position(|t| t == self).unwrap_or_default();
+        Self::ALL[(index + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+/// Everything castle type changes at the start of the run
+#[derive(Debug, Clone)]
+pub struct CastleDefinition {
+    /// Max hp of the walls by `Side::INDEX`
+    pub wall_health: [i32; 4],
+    /// Multiplier of the wall thickness
+    pub wall_thickness: f32,
+    /// Weapons of the walls by `Side::INDEX`
+    pub weapons: [StartingWeapons; 4],
+    pub weapon_buffs: GlobalWeaponBuffs,
+    pub perk: Option<CastlePerk>,
+}
+
+impl CastleDefinition {
+    /// Hp walls heal on the `tick`
+    pub fn regeneration(&self, tick: u64) -> i32 {
+        match self.perk {
+            Some(CastlePerk::Regeneration) if tick % REGENERATION_INTERVAL == 0 => {
+                REGENERATION_HEAL
+            }
+            _ => 0,
+        }
+    }
+
+    pub fn exp_multiplier(&self) -> f32 {
+        match self.perk {
+            Some(CastlePerk::Bounty) => 1.0 + BOUNTY_EXP,
+            _ => 1.0,
+        }
+    }
+
+    pub fn ability_recharge(&self) -> f32 {
+        match self.perk {
+            Some(CastlePerk::Vigilance) => VIGILANCE_RECHARGE,
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct StartingWeapons {
+    pub crossbow: bool,
+    pub molotov: bool,
+}
+
+impl StartingWeapons {
+    pub const ALL: Self = Self {
+        crossbow: true,
+        molotov: true,
+    };
+    pub const CROSSBOW: Self = Self {
+        crossbow: true,
+        molotov: false,
+    };
+}
+
+/// Passive bonus of the castle type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastlePerk {
+    /// Walls slowly heal
+    Regeneration,
+    /// Enemies give more experience
+    Bounty,
+    /// Abilities recharge faster
+    Vigilance,
+}
+
+impl std::fmt::Display for CastlePerk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Regeneration => f.write_fmt(format_args!(
+                "walls heal {REGENERATION_HEAL} hp every {:.0}s",
+                REGENERATION_INTERVAL as f32 * SIMULATION_TIMESTEP
+            )),
+            Self::Bounty => f.write_fmt(format_args!("exp: +{:.0}%", BOUNTY_EXP * 100.0)),
+            Self::Vigilance => f.write_fmt(format_args!(
+                "ability recharge: +{:.0}%",
+                VIGILANCE_RECHARGE * 100.0
+            )),
+        }
+    }
+}
+
+/// What the player needs to achieve to unlock a castle type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unlock {
+    Always,
+    /// Survive to the stage
+    Stage(u32),
+    /// Reach castle level
+    Level(u32),
+    /// Win a normal mode run
+    Victory,
+}
+
+impl Unlock {
+    pub fn is_met(&self, profile: &PlayerProfile) -> bool {
+        match self {
+            Unlock::Always => true,
+            Unlock::Stage(stage) => *stage <= profile.best_stage,
+            Unlock::Level(level) => *level <= profile.best_level,
+            Unlock::Victory => 0 < profile.victories,
+        }
+    }
+}
+
+impl std::fmt::Display for Unlock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Always => Ok(()),
+            Self::Stage(stage) => f.write_fmt(format_args!("survive to stage {stage}")),
+            Self::Level(level) => f.write_fmt(format_args!("reach level {level}")),
+            Self::Victory => f.write_str("win a normal run"),
+        }
+    }
+}
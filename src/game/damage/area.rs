@@ -3,11 +3,18 @@ use std::marker::PhantomData;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::projectile::projectile_update;
 
 use crate::{
     game::{
-        animation::AnimationBundle, damage::EnemyDamageEvent, East, GameState, North, Side, South,
-        West,
+        animation::AnimationBundle,
+        damage::{DamageSource, DamageType, EnemyDamageEvent},
+        rng::SideRng,
+        simulation::{SimulationSet, AIR_GROUP},
+        status::{StatusChance, StatusEvent},
+        East, North, Side, South, West,
     },
     utils::remove_all_with,
     GlobalState,
@@ -19,12 +26,15 @@ impl Plugin for AreaPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             (
-                damage_area_update::<North>,
-                damage_area_update::<South>,
-                damage_area_update::<West>,
-                damage_area_update::<East>,
+                // areas share crit stream with projectiles
+                // so order is fixed to keep runs reproducible
+                damage_area_update::<North>.after(projectile_update::<North>),
+                damage_area_update::<South>.after(projectile_update::<South>),
+                damage_area_update::<West>.after(projectile_update::<West>),
+                damage_area_update::<East>.after(projectile_update::<East>),
             )
-                .in_set(OnUpdate(GameState::InGame)),
+                .in_set(SimulationSet::Act)
+                .in_schedule(CoreSchedule::FixedUpdate),
         )
         .add_system(remove_all_with::<DamageAreaMarker>.in_schedule(OnExit(GlobalState::InGame)));
     }
@@ -33,7 +43,8 @@ impl Plugin for AreaPlugin {
 #[derive(Component)]
 pub struct DamageAreaMarker;
 
-#[derive(Clone, Component)]
+#[derive(Clone, Serialize, Deserialize, Component)]
+#[serde(bound = "")]
 pub struct DamageArea<S: Side> {
     size: f32,
     damage: i32,
@@ -41,6 +52,11 @@ pub struct DamageArea<S: Side> {
     crit_chance: f32,
     attack_timer: Timer,
     lifespan: Timer,
+    source: DamageSource,
+    damage_type: DamageType,
+    /// Status effects applied on every hit
+    effects: Vec<StatusChance>,
+    #[serde(skip)]
     _phatom: PhantomData<S>,
 }
 
@@ -52,6 +68,8 @@ impl<S: Side> DamageArea<S> {
         crit_chance: f32,
         attack_speed: f32,
         lifespan: f32,
+        source: DamageSource,
+        damage_type: DamageType,
     ) -> Self {
         Self {
             size,
@@ -60,9 +78,17 @@ impl<S: Side> DamageArea<S> {
             crit_chance,
             attack_timer: Timer::from_seconds(1.0 / attack_speed, TimerMode::Repeating),
             lifespan: Timer::from_seconds(lifespan, TimerMode::Once),
+            source,
+            damage_type,
+            effects: vec![],
             _phatom: PhantomData,
         }
     }
+
+    pub fn with_effects(mut self, effects: Vec<StatusChance>) -> Self {
+        self.effects = effects;
+        self
+    }
 }
 
 #[derive(Bundle)]
@@ -90,37 +116,51 @@ impl<S: Side> DamageAreaBundle<S> {
 }
 
 fn damage_area_update<S: Side>(
-    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
     rapier_context: Res<RapierContext>,
+    mut rng: ResMut<SideRng<S>>,
     mut commands: Commands,
     mut areas: Query<(Entity, &Transform, &mut DamageArea<S>)>,
     mut damage_event: EventWriter<EnemyDamageEvent<S>>,
+    mut status_events: EventWriter<StatusEvent<S>>,
 ) {
-    let mut rng = rand::thread_rng();
     for (area_entity, area_transform, mut area) in areas.iter_mut() {
-        if area.lifespan.tick(time.delta()).finished() {
+        if area.lifespan.tick(fixed_time.period).finished() {
             commands.entity(area_entity).despawn();
         } else {
-            if !area.attack_timer.tick(time.delta()).finished() {
+            if !area.attack_timer.tick(fixed_time.period).finished() {
                 continue;
             }
 
             let callback = |e| {
-                let (damage, was_crit) = if rng.gen_range(0.0..1.0) < area.crit_chance {
+                let (damage, was_crit) = if rng.crit.gen_range(0.0..1.0) < area.crit_chance {
                     (area.crit_damage, true)
                 } else {
                     (area.damage, false)
                 };
 
-                damage_event.send(EnemyDamageEvent::new(e, damage, was_crit));
+                damage_event.send(EnemyDamageEvent::new(
+                    e,
+                    damage,
+                    area.damage_type,
+                    was_crit,
+                    area.source,
+                ));
+                for effect in area.effects.iter() {
+                    if let Some(effect) = effect.roll(&mut rng.status) {
+                        status_events.send(StatusEvent::new(e, effect));
+                    }
+                }
                 true
             };
 
+            // fire on the ground does not reach flying enemies
             rapier_context.intersections_with_shape(
                 area_transform.translation.truncate(),
                 0.0,
                 &Collider::ball(area.size),
-                QueryFilter::only_dynamic(),
+                QueryFilter::only_dynamic()
+                    .groups(CollisionGroups::new(Group::ALL, Group::ALL - AIR_GROUP)),
                 callback,
             );
         }
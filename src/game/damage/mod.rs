@@ -1,15 +1,27 @@
 use std::marker::PhantomData;
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{utils::remove_all_with, GameAssets, GlobalState};
 
-use super::{castle::CastleWall, enemies::Enemy, East, GameState, North, Side, South, West};
+use super::{
+    castle::{Castle, CastleWall},
+    enemies::{boss::Boss, definition::EnemyDefinitions, elite::Shielded, Enemy},
+    simulation::SimulationSet,
+    East, GameState, North, Side, South, West,
+};
 
 pub mod area;
 pub mod projectile;
 
 const DAMAGE_TEXT_LIFESPAN: f32 = 1.0;
+/// Damage number color of hits enemy is weak to
+const WEAK_DAMAGE_COLOR: Color = Color::YELLOW;
+/// Damage number color of hits reduced by armor or resistance
+const RESISTED_DAMAGE_COLOR: Color = Color::rgb(0.35, 0.35, 0.5);
+/// Damage number color of hits absorbed by a shield
+const SHIELDED_DAMAGE_COLOR: Color = Color::CYAN;
 
 pub struct DamagePlugin;
 
@@ -23,42 +35,119 @@ impl Plugin for DamagePlugin {
             .add_event::<WallDamageEvent<South>>()
             .add_event::<WallDamageEvent<West>>()
             .add_event::<WallDamageEvent<East>>()
+            .add_event::<CastleDamageEvent>()
+            .add_event::<EnemyDamagedEvent<North>>()
+            .add_event::<EnemyDamagedEvent<South>>()
+            .add_event::<EnemyDamagedEvent<West>>()
+            .add_event::<EnemyDamagedEvent<East>>()
+            .add_event::<WallDamagedEvent<North>>()
+            .add_event::<WallDamagedEvent<South>>()
+            .add_event::<WallDamagedEvent<West>>()
+            .add_event::<WallDamagedEvent<East>>()
             .add_plugin(area::AreaPlugin)
             .add_plugin(projectile::ProjectilePlugin)
+            .edit_schedule(CoreSchedule::FixedUpdate, |schedule| {
+                schedule
+                    .configure_sets((DamageSet::Enemies, DamageSet::Castle).chain())
+                    .configure_set(DamageSet::Enemies.in_set(SimulationSet::Damage))
+                    .configure_set(DamageSet::Castle.in_set(SimulationSet::Damage));
+            })
             .add_systems(
                 (
                     damage_enemy::<North>,
                     damage_enemy::<South>,
                     damage_enemy::<West>,
                     damage_enemy::<East>,
+                )
+                    .in_set(DamageSet::Enemies)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (
                     damage_wall::<North>,
                     damage_wall::<South>,
                     damage_wall::<West>,
                     damage_wall::<East>,
-                    damage_text_update,
+                    damage_castle,
                 )
-                    .in_set(OnUpdate(GameState::InGame)),
+                    .in_set(DamageSet::Castle)
+                    .in_schedule(CoreSchedule::FixedUpdate),
             )
+            .add_system(damage_text_update.in_set(OnUpdate(GameState::InGame)))
             .add_system(
                 remove_all_with::<DamageTextMarker>.in_schedule(OnExit(GlobalState::InGame)),
             );
     }
 }
 
+/// Enemies are damaged before the castle, so damage dealt
+/// by dying enemies hits the castle on the same tick.
+/// Events are only kept for two frames, they can not wait for the next one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub enum DamageSet {
+    Enemies,
+    Castle,
+}
+
+/// What dealt the damage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DamageSource {
+    Crossbow,
+    Molotov,
+    Ability,
+    /// Damage over time of the burning status
+    Burning,
+    /// Damage over time of the poisoned status
+    Poison,
+}
+
+impl DamageSource {
+    pub const ALL: [DamageSource; 5] = [
+        DamageSource::Crossbow,
+        DamageSource::Molotov,
+        DamageSource::Ability,
+        DamageSource::Burning,
+        DamageSource::Poison,
+    ];
+
+    /// Only weapon hits roll for critical damage
+    pub fn can_crit(&self) -> bool {
+        matches!(self, DamageSource::Crossbow | DamageSource::Molotov)
+    }
+}
+
+/// Kind of the damage. Enemies can resist it or be weak to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DamageType {
+    Piercing,
+    Fire,
+    Poison,
+}
+
 /// Event to damage enemy
 pub struct EnemyDamageEvent<S: Side> {
     pub target: Entity,
     pub damage: i32,
+    pub damage_type: DamageType,
     pub was_crit: bool,
+    pub source: DamageSource,
     _phantom: PhantomData<S>,
 }
 
 impl<S: Side> EnemyDamageEvent<S> {
-    pub fn new(target: Entity, damage: i32, was_crit: bool) -> Self {
+    pub fn new(
+        target: Entity,
+        damage: i32,
+        damage_type: DamageType,
+        was_crit: bool,
+        source: DamageSource,
+    ) -> Self {
         Self {
             target,
             damage,
+            damage_type,
             was_crit,
+            source,
             _phantom: PhantomData,
         }
     }
@@ -79,6 +168,26 @@ impl<S: Side> WallDamageEvent<S> {
     }
 }
 
+/// Event to damage castle core
+pub struct CastleDamageEvent {
+    pub damage: i32,
+}
+
+/// Damage enemy actually took after armor,
+/// resistances, shields and immunity
+pub struct EnemyDamagedEvent<S: Side> {
+    pub damage: i32,
+    pub was_crit: bool,
+    pub source: DamageSource,
+    _phantom: PhantomData<S>,
+}
+
+/// Damage wall actually took after its armor
+pub struct WallDamagedEvent<S: Side> {
+    pub damage: i32,
+    _phantom: PhantomData<S>,
+}
+
 #[derive(Component)]
 pub struct DamageTextMarker {
     lifespan: Timer,
@@ -92,31 +201,67 @@ impl Default for DamageTextMarker {
     }
 }
 
-/// Damage enemies based on the side
+/// Damage enemies based on the side.
+/// Armor and resistances come from the enemy definition,
+/// shields of elites absorb damage before health.
 fn damage_enemy<S: Side>(
     game_assets: Res<GameAssets>,
+    enemy_definitions: Res<EnemyDefinitions>,
     mut commands: Commands,
     mut events: EventReader<EnemyDamageEvent<S>>,
-    mut enemies: Query<(&Transform, &mut Enemy<S>)>,
+    mut damaged_events: EventWriter<EnemyDamagedEvent<S>>,
+    mut enemies: Query<(
+        &Transform,
+        &mut Enemy<S>,
+        Option<&Boss>,
+        Option<&mut Shielded>,
+    )>,
 ) {
     for event in events.iter() {
-        if let Ok((transform, mut enemy)) = enemies.get_mut(event.target) {
-            enemy.health -= event.damage;
+        if let Ok((transform, mut enemy, boss, shielded)) = enemies.get_mut(event.target) {
+            let immune = boss.map_or(false, Boss::is_immune);
+            let damage = enemy_definitions
+                .get(enemy.kind)
+                .map_or(event.damage, |definition| {
+                    definition.damage_taken(event.damage, event.damage_type)
+                });
+            let mut absorbed = false;
+            let mut health_damage = 0;
+            if !immune {
+                health_damage = shielded.map_or(damage, |mut shielded| shielded.absorb(damage));
+                absorbed = health_damage < damage;
+                enemy.health -= health_damage;
+            }
+            damaged_events.send(EnemyDamagedEvent {
+                damage: health_damage,
+                was_crit: event.was_crit,
+                source: event.source,
+                _phantom: PhantomData,
+            });
 
             let mut damage_text_transform = *transform;
             damage_text_transform.translation.y += 5.0;
             damage_text_transform.translation.z += 1.0;
 
-            let (color, font_size) = if event.was_crit {
-                (Color::ORANGE_RED, 50.0)
+            let font_size = if event.was_crit { 50.0 } else { 30.0 };
+            let (text, color) = if immune {
+                ("immune".to_string(), Color::WHITE)
+            } else if absorbed {
+                (format!("{damage}"), SHIELDED_DAMAGE_COLOR)
+            } else if event.damage < damage {
+                (format!("{damage}"), WEAK_DAMAGE_COLOR)
+            } else if damage < event.damage {
+                (format!("{damage}"), RESISTED_DAMAGE_COLOR)
+            } else if event.was_crit {
+                (format!("{damage}"), Color::ORANGE_RED)
             } else {
-                (Color::GRAY, 30.0)
+                (format!("{damage}"), Color::GRAY)
             };
 
             commands.spawn((
                 Text2dBundle {
                     text: Text::from_section(
-                        format!("{}", event.damage),
+                        text,
                         TextStyle {
                             font: game_assets.font.clone(),
                             font_size,
@@ -132,16 +277,22 @@ fn damage_enemy<S: Side>(
     }
 }
 
-/// Damage wall based on the side
+/// Damage wall based on the side, reduced by the wall armor
 fn damage_wall<S: Side>(
     game_assets: Res<GameAssets>,
     mut commands: Commands,
     mut events: EventReader<WallDamageEvent<S>>,
+    mut damaged_events: EventWriter<WallDamagedEvent<S>>,
     mut wall: Query<(&Transform, &mut CastleWall<S>)>,
 ) {
     let (transform, mut wall) = wall.single_mut();
     for event in events.iter() {
-        wall.health -= event.damage;
+        let damage = wall.damage_taken(event.damage);
+        wall.health -= damage;
+        damaged_events.send(WallDamagedEvent {
+            damage,
+            _phantom: PhantomData,
+        });
 
         let mut damage_text_transform = *transform;
         damage_text_transform.translation.y += 5.0;
@@ -150,7 +301,7 @@ fn damage_wall<S: Side>(
         commands.spawn((
             Text2dBundle {
                 text: Text::from_section(
-                    format!("{}", event.damage),
+                    format!("{damage}"),
                     TextStyle {
                         font: game_assets.font.clone(),
                         font_size: 120.0,
@@ -165,6 +316,39 @@ fn damage_wall<S: Side>(
     }
 }
 
+/// Damage castle core hit by flying enemies
+fn damage_castle(
+    game_assets: Res<GameAssets>,
+    mut commands: Commands,
+    mut events: EventReader<CastleDamageEvent>,
+    mut castle: Query<(&Transform, &mut Castle)>,
+) {
+    let (transform, mut castle) = castle.single_mut();
+    for event in events.iter() {
+        castle.core_health -= event.damage;
+
+        let mut damage_text_transform = *transform;
+        damage_text_transform.translation.y += 5.0;
+        damage_text_transform.translation.z += 10.0;
+
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    format!("{}", event.damage),
+                    TextStyle {
+                        font: game_assets.font.clone(),
+                        font_size: 120.0,
+                        color: Color::CRIMSON,
+                    },
+                ),
+                transform: damage_text_transform,
+                ..default()
+            },
+            DamageTextMarker::default(),
+        ));
+    }
+}
+
 fn damage_text_update(
     time: Res<Time>,
     mut commands: Commands,
@@ -3,14 +3,24 @@ use std::marker::PhantomData;
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    game::{damage::EnemyDamageEvent, enemies::Enemy, East, GameState, North, Side, South, West},
+    game::{
+        castle::CastleWall,
+        damage::{DamageSource, DamageType, EnemyDamageEvent, WallDamageEvent},
+        enemies::{wall_distance, Enemy, EnemyKind},
+        rng::SideRng,
+        simulation::{Interpolated, SimulationSet, AIR_GROUP, PROJECTILE_GROUP},
+        status::{StatusChance, StatusEvent},
+        East, North, Side, South, West,
+    },
     utils::remove_all_with,
     GlobalState,
 };
 
 const DEFAULT_ARROW_LIFESPAN: f32 = 10.0;
+const DEFAULT_ENEMY_PROJECTILE_LIFESPAN: f32 = 10.0;
 
 pub struct ProjectilePlugin;
 
@@ -22,35 +32,65 @@ impl Plugin for ProjectilePlugin {
                 projectile_update::<South>,
                 projectile_update::<West>,
                 projectile_update::<East>,
+                // intercepted projectiles should not reach the wall
+                enemy_projectile_update::<North>.after(projectile_update::<North>),
+                enemy_projectile_update::<South>.after(projectile_update::<South>),
+                enemy_projectile_update::<West>.after(projectile_update::<West>),
+                enemy_projectile_update::<East>.after(projectile_update::<East>),
             )
-                .in_set(OnUpdate(GameState::InGame)),
+                .in_set(SimulationSet::Act)
+                .in_schedule(CoreSchedule::FixedUpdate),
         )
         .add_system(remove_all_with::<ProjectileMarker>.in_schedule(OnExit(GlobalState::InGame)));
     }
 }
 
+/// Marks projectiles of both castle weapons and enemies
 #[derive(Component)]
 pub struct ProjectileMarker;
 
-#[derive(Component)]
+#[derive(Clone, Serialize, Deserialize, Component)]
+#[serde(bound = "")]
 pub struct Projectile<S: Side> {
     damage: i32,
     crit_damage: i32,
     crit_chance: f32,
+    /// Can hit flying enemies
+    anti_air: bool,
+    /// Status effects applied on hit
+    effects: Vec<StatusChance>,
     lifespan: Timer,
+    #[serde(skip)]
     _phantom: PhantomData<S>,
 }
 
 impl<S: Side> Projectile<S> {
-    pub fn new(damage: i32, crit_damage: i32, crit_chance: f32, lifespan: f32) -> Self {
+    pub fn new(
+        damage: i32,
+        crit_damage: i32,
+        crit_chance: f32,
+        anti_air: bool,
+        effects: Vec<StatusChance>,
+        lifespan: f32,
+    ) -> Self {
         Self {
             damage,
             crit_damage,
             crit_chance,
+            anti_air,
+            effects,
             lifespan: Timer::from_seconds(lifespan, TimerMode::Once),
             _phantom: PhantomData,
         }
     }
+
+    fn collision_groups(&self) -> CollisionGroups {
+        if self.anti_air {
+            CollisionGroups::new(PROJECTILE_GROUP, Group::ALL)
+        } else {
+            CollisionGroups::new(PROJECTILE_GROUP, Group::ALL - AIR_GROUP)
+        }
+    }
 }
 
 #[derive(Bundle)]
@@ -59,8 +99,10 @@ pub struct ProjectileBundle<S: Side> {
     sprite: SpriteBundle,
     rigid_body: RigidBody,
     collider: Collider,
+    collision_groups: CollisionGroups,
     velocity: Velocity,
     projectile: Projectile<S>,
+    interpolated: Interpolated,
     marker: ProjectileMarker,
 }
 
@@ -71,9 +113,35 @@ impl<S: Side> ProjectileBundle<S> {
         damage: i32,
         crit_damage: i32,
         crit_chance: f32,
+        anti_air: bool,
+        effects: Vec<StatusChance>,
         speed: f32,
         direction: Vec2,
         transform: Transform,
+    ) -> Self {
+        Self::from_projectile(
+            texture,
+            size,
+            Projectile::new(
+                damage,
+                crit_damage,
+                crit_chance,
+                anti_air,
+                effects,
+                DEFAULT_ARROW_LIFESPAN,
+            ),
+            Velocity::linear(speed * direction),
+            transform,
+        )
+    }
+
+    /// Creates bundle for the projectile already in flight
+    pub fn from_projectile(
+        texture: Handle<Image>,
+        size: f32,
+        projectile: Projectile<S>,
+        velocity: Velocity,
+        transform: Transform,
     ) -> Self {
         Self {
             sprite: SpriteBundle {
@@ -83,27 +151,99 @@ impl<S: Side> ProjectileBundle<S> {
             },
             rigid_body: RigidBody::Dynamic,
             collider: Collider::ball(size),
-            velocity: Velocity {
-                linvel: speed * direction,
+            collision_groups: projectile.collision_groups(),
+            velocity,
+            projectile,
+            interpolated: Interpolated::default(),
+            marker: ProjectileMarker,
+        }
+    }
+}
+
+/// Projectile shot by a ranged enemy at the wall of the side
+#[derive(Clone, Serialize, Deserialize, Component)]
+#[serde(bound = "")]
+pub struct EnemyProjectile<S: Side> {
+    /// Kind of the enemy which shot the projectile
+    pub kind: EnemyKind,
+    /// Castle projectiles can shoot it down
+    pub interceptable: bool,
+    damage: i32,
+    lifespan: Timer,
+    #[serde(skip)]
+    _phantom: PhantomData<S>,
+}
+
+impl<S: Side> EnemyProjectile<S> {
+    pub fn new(kind: EnemyKind, damage: i32, interceptable: bool) -> Self {
+        Self {
+            kind,
+            interceptable,
+            damage,
+            lifespan: Timer::from_seconds(DEFAULT_ENEMY_PROJECTILE_LIFESPAN, TimerMode::Once),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Enemy projectiles are sensors, so they
+/// fly through enemies without pushing them
+#[derive(Bundle)]
+pub struct EnemyProjectileBundle<S: Side> {
+    #[bundle]
+    sprite: SpriteBundle,
+    rigid_body: RigidBody,
+    collider: Collider,
+    sensor: Sensor,
+    velocity: Velocity,
+    projectile: EnemyProjectile<S>,
+    interpolated: Interpolated,
+    marker: ProjectileMarker,
+}
+
+impl<S: Side> EnemyProjectileBundle<S> {
+    pub fn new(
+        texture: Handle<Image>,
+        size: f32,
+        projectile: EnemyProjectile<S>,
+        velocity: Velocity,
+        translation: Vec3,
+    ) -> Self {
+        Self {
+            sprite: SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(size * 2.0)),
+                    ..default()
+                },
+                texture,
+                transform: Transform::from_translation(translation),
                 ..default()
             },
-            projectile: Projectile::new(damage, crit_damage, crit_chance, DEFAULT_ARROW_LIFESPAN),
+            rigid_body: RigidBody::Dynamic,
+            collider: Collider::ball(size),
+            sensor: Sensor,
+            velocity,
+            projectile,
+            interpolated: Interpolated::default(),
             marker: ProjectileMarker,
         }
     }
 }
 
-fn projectile_update<S: Side>(
-    time: Res<Time>,
+pub fn projectile_update<S: Side>(
+    fixed_time: Res<FixedTime>,
     enemies: Query<Entity, With<Enemy<S>>>,
+    enemy_projectiles: Query<&EnemyProjectile<S>>,
     rapier_context: Res<RapierContext>,
+    mut rng: ResMut<SideRng<S>>,
     mut commands: Commands,
     mut projectiles: Query<(Entity, &mut Projectile<S>)>,
     mut damage_event: EventWriter<EnemyDamageEvent<S>>,
+    mut status_events: EventWriter<StatusEvent<S>>,
 ) {
-    let mut rng = rand::thread_rng();
+    let mut intercepted = vec![];
     for (projectile_entity, mut projectile) in projectiles.iter_mut() {
-        if projectile.lifespan.tick(time.delta()).finished() {
+        if projectile.lifespan.tick(fixed_time.period).finished() {
             commands.entity(projectile_entity).despawn();
         } else {
             let mut hit = false;
@@ -114,13 +254,46 @@ fn projectile_update<S: Side>(
                 {
                     hit = true;
 
-                    let (damage, was_crit) = if rng.gen_range(0.0..1.0) < projectile.crit_chance {
-                        (projectile.crit_damage, true)
-                    } else {
-                        (projectile.damage, false)
-                    };
+                    let (damage, was_crit) =
+                        if rng.crit.gen_range(0.0..1.0) < projectile.crit_chance {
+                            (projectile.crit_damage, true)
+                        } else {
+                            (projectile.damage, false)
+                        };
 
-                    damage_event.send(EnemyDamageEvent::new(enemy, damage, was_crit));
+                    damage_event.send(EnemyDamageEvent::new(
+                        enemy,
+                        damage,
+                        DamageType::Piercing,
+                        was_crit,
+                        DamageSource::Crossbow,
+                    ));
+                    for effect in projectile.effects.iter() {
+                        if let Some(effect) = effect.roll(&mut rng.status) {
+                            status_events.send(StatusEvent::new(enemy, effect));
+                        }
+                    }
+                }
+            }
+            // enemy projectiles are sensors, so they only intersect
+            for (collider1, collider2, intersecting) in
+                rapier_context.intersections_with(projectile_entity)
+            {
+                let other = if collider1 == projectile_entity {
+                    collider2
+                } else {
+                    collider1
+                };
+                if !intersecting || hit || intercepted.contains(&other) {
+                    continue;
+                }
+                if enemy_projectiles
+                    .get(other)
+                    .map_or(false, |enemy_projectile| enemy_projectile.interceptable)
+                {
+                    hit = true;
+                    intercepted.push(other);
+                    commands.entity(other).despawn();
                 }
             }
             if hit {
@@ -129,3 +302,22 @@ fn projectile_update<S: Side>(
         }
     }
 }
+
+/// Damages the wall once enemy projectiles reach it
+fn enemy_projectile_update<S: Side>(
+    fixed_time: Res<FixedTime>,
+    wall: Query<(&Transform, &CastleWall<S>)>,
+    mut commands: Commands,
+    mut projectiles: Query<(Entity, &Transform, &mut EnemyProjectile<S>)>,
+    mut damage_events: EventWriter<WallDamageEvent<S>>,
+) {
+    let (wall_transform, wall) = wall.single();
+    for (projectile_entity, transform, mut projectile) in projectiles.iter_mut() {
+        if projectile.lifespan.tick(fixed_time.period).finished() {
+            commands.entity(projectile_entity).despawn();
+        } else if wall_distance(wall_transform, wall, transform) <= 0.0 {
+            damage_events.send(WallDamageEvent::new(projectile.damage));
+            commands.entity(projectile_entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    abilities::Frozen, castle::CastleWall, damage::WallDamageEvent, simulation::SimulationSet,
+    status::StatusEffects, East, North, SideId, South, West,
+};
+
+use super::{
+    definition::EnemyDefinitions, scaling::EnemyScaling, spawn::EnemyBuffs, wall_distance, Enemy,
+    EnemyKind, GlobalEnemyBuffs, Side,
+};
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BossSpawnedEvent>()
+            .add_event::<SummonEvent<North>>()
+            .add_event::<SummonEvent<South>>()
+            .add_event::<SummonEvent<West>>()
+            .add_event::<SummonEvent<East>>()
+            .add_systems(
+                (
+                    boss_behaviour::<North>,
+                    boss_behaviour::<South>,
+                    boss_behaviour::<West>,
+                    boss_behaviour::<East>,
+                )
+                    .in_set(SimulationSet::Act)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (
+                    announce_bosses::<North>,
+                    announce_bosses::<South>,
+                    announce_bosses::<West>,
+                    announce_bosses::<East>,
+                )
+                    .in_set(SimulationSet::Resolve)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            );
+    }
+}
+
+/// Part of the enemy definition which turns the enemy into a boss
+#[derive(Debug, Clone, Deserialize)]
+pub struct BossDefinition {
+    /// Name shown in the announcement and on the health bar
+    pub name: String,
+    /// Seconds boss ignores damage after a new phase starts
+    pub immunity: f32,
+    /// Extra upgrade choices given when the boss is killed
+    pub bonus_upgrades: u32,
+    /// Phases ordered by decreasing health
+    pub phases: Vec<BossPhase>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BossPhase {
+    /// Phase starts once health drops to this part of max health
+    pub health: f32,
+    /// Seconds between actions
+    pub cooldown: f32,
+    /// Actions used in order, starting over after the last one
+    pub actions: Vec<BossAction>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum BossAction {
+    /// Moves faster for a while
+    Charge { speed: f32, duration: f32 },
+    /// Spawns enemies around the boss
+    Summon { enemy: EnemyKind, number: u32 },
+    /// Damages the wall if it is in range
+    Slam { damage: i32, range: f32 },
+}
+
+impl BossDefinition {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.immunity < 0.0 {
+            return Err("boss immunity can not be negative");
+        }
+        if self.phases.is_empty() {
+            return Err("boss must have at least one phase");
+        }
+        if self.phases[0].health != 1.0 {
+            return Err("first boss phase must start at full health");
+        }
+        if self
+            .phases
+            .windows(2)
+            .any(|phases| phases[1].health >= phases[0].health || phases[1].health <= 0.0)
+        {
+            return Err("boss phase health must decrease and stay positive");
+        }
+        for phase in self.phases.iter() {
+            if phase.cooldown <= 0.0 {
+                return Err("boss phase cooldown must be positive");
+            }
+            if phase.actions.is_empty() {
+                return Err("boss phase must have at least one action");
+            }
+            for action in phase.actions.iter() {
+                match *action {
+                    BossAction::Charge { speed, duration } => {
+                        if speed <= 0.0 || duration <= 0.0 {
+                            return Err("boss charge speed and duration must be positive");
+                        }
+                    }
+                    BossAction::Summon { number, .. } => {
+                        if number == 0 {
+                            return Err("boss summon number must be positive");
+                        }
+                    }
+                    BossAction::Slam { damage, range } => {
+                        if damage < 0 || range <= 0.0 {
+                            return Err(
+                                "boss slam damage can not be negative, range must be positive",
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sent when a boss appears
+#[derive(Debug, Clone)]
+pub struct BossSpawnedEvent {
+    pub side: SideId,
+    pub name: String,
+}
+
+/// Request to spawn enemies around the position.
+/// Handled by the spawn of the side.
+pub struct SummonEvent<S: Side> {
+    pub enemy: EnemyKind,
+    pub number: u32,
+    pub position: Vec3,
+    _phantom: PhantomData<S>,
+}
+
+impl<S: Side> SummonEvent<S> {
+    pub fn new(enemy: EnemyKind, number: u32, position: Vec3) -> Self {
+        Self {
+            enemy,
+            number,
+            position,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// Phase progress of the boss.
+/// Phases themselves are read from the enemy definition.
+#[derive(Debug, Clone, Serialize, Deserialize, Component)]
+pub struct Boss {
+    pub name: String,
+    pub bonus_upgrades: u32,
+    pub max_health: i32,
+    /// Part of max health left, updated every tick
+    pub health: f32,
+    /// Speed outside of charges
+    pub base_speed: f32,
+    pub phase: usize,
+    /// Index of the next action of the phase
+    pub action: usize,
+    /// Seconds until the next action
+    pub cooldown: f32,
+    /// Seconds until the charge ends
+    pub charge: f32,
+    /// Seconds until boss can be damaged again
+    pub immunity: f32,
+}
+
+impl Boss {
+    pub fn new(definition: &BossDefinition, max_health: i32, speed: f32) -> Self {
+        Self {
+            name: definition.name.clone(),
+            bonus_upgrades: definition.bonus_upgrades,
+            max_health,
+            health: 1.0,
+            base_speed: speed,
+            phase: 0,
+            action: 0,
+            cooldown: definition.phases[0].cooldown,
+            charge: 0.0,
+            immunity: 0.0,
+        }
+    }
+
+    pub fn is_immune(&self) -> bool {
+        0.0 < self.immunity
+    }
+}
+
+/// Advances boss phases and uses the actions of the current phase
+fn boss_behaviour<S: Side>(
+    fixed_time: Res<FixedTime>,
+    enemy_definitions: Res<EnemyDefinitions>,
+    global_buffs: Res<GlobalEnemyBuffs>,
+    buffs: Res<EnemyBuffs<S>>,
+    scaling: Res<EnemyScaling>,
+    wall: Query<(&Transform, &CastleWall<S>)>,
+    mut bosses: Query<(
+        &Transform,
+        &mut Enemy<S>,
+        &mut Boss,
+        &StatusEffects,
+        Option<&Frozen>,
+    )>,
+    mut summon_events: EventWriter<SummonEvent<S>>,
+    mut damage_events: EventWriter<WallDamageEvent<S>>,
+) {
+    let (wall_transform, wall) = wall.single();
+    let delta = fixed_time.period.as_secs_f32();
+    for (transform, mut enemy, mut boss, status, frozen) in bosses.iter_mut() {
+        let Some(definition) = enemy_definitions
+            .get(enemy.kind)
+            .and_then(|definition| definition.boss.as_ref())
+        else {
+            continue;
+        };
+
+        boss.health = enemy.health.max(0) as f32 / boss.max_health as f32;
+        boss.immunity = (boss.immunity - delta).max(0.0);
+
+        // big hits may skip phases, only the last one is used
+        while let Some(next) = definition.phases.get(boss.phase + 1) {
+            if boss.health > next.health {
+                break;
+            }
+            boss.phase += 1;
+            boss.action = 0;
+            boss.cooldown = next.cooldown;
+            boss.immunity = definition.immunity;
+        }
+
+        // frozen and stunned bosses still change phases, but do not act
+        if frozen.is_some() || status.is_stunned() {
+            continue;
+        }
+
+        if 0.0 < boss.charge {
+            boss.charge -= delta;
+            if boss.charge <= 0.0 {
+                enemy.speed = boss.base_speed;
+            }
+        }
+
+        // definition may have less phases after reload
+        let phase = &definition.phases[boss.phase.min(definition.phases.len() - 1)];
+        boss.cooldown -= delta;
+        if 0.0 < boss.cooldown {
+            continue;
+        }
+        boss.cooldown += phase.cooldown;
+        let action = phase.actions[boss.action % phase.actions.len()];
+        boss.action += 1;
+
+        match action {
+            BossAction::Charge { speed, duration } => {
+                enemy.speed = boss.base_speed * speed;
+                boss.charge = duration;
+            }
+            BossAction::Summon { enemy, number } => {
+                summon_events.send(SummonEvent::new(enemy, number, transform.translation));
+            }
+            BossAction::Slam { damage, range } => {
+                if wall_distance(wall_transform, wall, transform) <= range {
+                    let damage = damage as f32
+                        * (1.0 + global_buffs.damage + buffs.damage)
+                        * (1.0 + scaling.damage);
+                    damage_events.send(WallDamageEvent::new(damage as i32));
+                }
+            }
+        }
+    }
+}
+
+/// Announces new bosses, including ones restored from a save
+fn announce_bosses<S: Side>(
+    bosses: Query<&Boss, (Added<Boss>, With<Enemy<S>>)>,
+    mut events: EventWriter<BossSpawnedEvent>,
+) {
+    for boss in bosses.iter() {
+        events.send(BossSpawnedEvent {
+            side: SideId::of::<S>(),
+            name: boss.name.clone(),
+        });
+    }
+}
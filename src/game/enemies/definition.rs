@@ -0,0 +1,422 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use crate::game::damage::DamageType;
+
+use super::{
+    boss::BossDefinition, scaling::EnemyScaling, spawn::EnemyBuffs, Enemy, EnemyAttack, EnemyKind,
+    GlobalEnemyBuffs, Movement, Side,
+};
+
+/// Extension of the enemy definition files
+pub const ENEMY_DEFINITION_EXTENSION: &str = "enemy.ron";
+
+/// Loads enemy definitions through the asset server
+/// and keeps `EnemyDefinitions` up to date when files change
+pub struct EnemyDefinitionsPlugin;
+
+impl Plugin for EnemyDefinitionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<EnemyDefinition>()
+            .init_asset_loader::<EnemyDefinitionLoader>()
+            .init_resource::<EnemyDefinitions>()
+            .add_system(update_definitions);
+    }
+}
+
+/// Stats and looks of a single enemy kind
+#[derive(Debug, Clone, Deserialize, TypeUuid)]
+#[uuid = "4a8c6d0e-3f4b-4e8e-9a53-2b7b1f0c6e21"]
+pub struct EnemyDefinition {
+    pub kind: EnemyKind,
+    pub health: i32,
+    pub speed: f32,
+    pub exp: u32,
+    /// Gold dropped on death
+    #[serde(default)]
+    pub gold: u32,
+    pub damage: i32,
+    /// Distance to the wall enemy attacks from
+    pub range: f32,
+    /// Seconds between attacks
+    pub attack_speed: f32,
+    pub number_per_spawn: u32,
+    pub collider_radius: f32,
+    /// Flat damage removed from every hit
+    #[serde(default)]
+    pub armor: i32,
+    /// Part of the damage of the type ignored.
+    /// Negative values make the enemy weak to the type.
+    #[serde(default)]
+    pub resistances: HashMap<DamageType, f32>,
+    /// Ground enemies if not set
+    #[serde(default)]
+    pub movement: Movement,
+    pub sprite: EnemySpriteDefinition,
+    /// Makes every enemy of the kind a boss
+    #[serde(default)]
+    pub boss: Option<BossDefinition>,
+    /// Makes the enemy shoot at the wall instead of hitting it
+    #[serde(default)]
+    pub ranged: Option<RangedDefinition>,
+    #[serde(skip)]
+    pub texture_atlas: Handle<TextureAtlas>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnemySpriteDefinition {
+    /// Sprite sheet path relative to the assets folder
+    pub path: String,
+    pub tile_size: Vec2,
+    pub columns: usize,
+    pub rows: usize,
+    /// Number of animation frames, starting from the first tile
+    pub frames: usize,
+    pub fps: f64,
+    /// Size the sprite is drawn with
+    pub size: f32,
+}
+
+/// Ranged enemies stop before the wall and shoot projectiles at it
+#[derive(Debug, Clone, Deserialize)]
+pub struct RangedDefinition {
+    /// Distance to the wall enemy stops at
+    pub standoff: f32,
+    pub projectile_speed: f32,
+    /// Radius of the projectile collider
+    pub projectile_size: f32,
+    /// Weapons can shoot the projectile down
+    pub interceptable: bool,
+    /// Projectile sprite path relative to the assets folder
+    pub projectile_sprite: String,
+    #[serde(skip)]
+    pub projectile_texture: Handle<Image>,
+}
+
+impl EnemyDefinition {
+    /// Parses and validates the definition file
+    pub fn parse(bytes: &[u8], path: &Path) -> Result<Self, EnemyDefinitionError> {
+        let definition: Self = ron::de::from_bytes(bytes)
+            .map_err(|e| EnemyDefinitionError::Parse(path.to_path_buf(), e))?;
+        definition
+            .validate()
+            .map_err(|reason| EnemyDefinitionError::Invalid(path.to_path_buf(), reason))?;
+        Ok(definition)
+    }
+
+    fn validate(&self) -> Result<(), &'static str> {
+        if self.health <= 0 {
+            return Err("health must be positive");
+        }
+        if self.speed < 0.0 {
+            return Err("speed can not be negative");
+        }
+        if self.damage < 0 {
+            return Err("damage can not be negative");
+        }
+        if self.collider_radius <= 0.0 {
+            return Err("collider_radius must be positive");
+        }
+        if self.range <= self.collider_radius {
+            return Err("range must be bigger than collider_radius");
+        }
+        if self.attack_speed <= 0.0 {
+            return Err("attack_speed must be positive");
+        }
+        if self.number_per_spawn == 0 {
+            return Err("number_per_spawn must be positive");
+        }
+        if self.armor < 0 {
+            return Err("armor can not be negative");
+        }
+        if self
+            .resistances
+            .values()
+            .any(|resistance| !(-1.0..1.0).contains(resistance))
+        {
+            return Err("resistances must be at least -1 and below 1");
+        }
+        let sprite = &self.sprite;
+        if sprite.tile_size.x <= 0.0 || sprite.tile_size.y <= 0.0 {
+            return Err("sprite tile_size must be positive");
+        }
+        if sprite.columns == 0 || sprite.rows == 0 {
+            return Err("sprite must have at least one column and row");
+        }
+        if sprite.frames == 0 || sprite.columns * sprite.rows < sprite.frames {
+            return Err("sprite frames must fit into the sprite sheet");
+        }
+        if sprite.fps <= 0.0 {
+            return Err("sprite fps must be positive");
+        }
+        if sprite.size <= 0.0 {
+            return Err("sprite size must be positive");
+        }
+        if let Some(boss) = &self.boss {
+            boss.validate()?;
+        }
+        if let Some(ranged) = &self.ranged {
+            if self.movement == Movement::Flying {
+                return Err("flying enemies can not be ranged");
+            }
+            if ranged.standoff <= 0.0 || self.range < ranged.standoff {
+                return Err("ranged standoff must be positive and not bigger than range");
+            }
+            if ranged.projectile_speed <= 0.0 {
+                return Err("ranged projectile_speed must be positive");
+            }
+            if ranged.projectile_size <= 0.0 {
+                return Err("ranged projectile_size must be positive");
+            }
+        }
+        Ok(())
+    }
+
+    /// Damage left after resistance and armor, hits always deal at least 1
+    pub fn damage_taken(&self, damage: i32, damage_type: DamageType) -> i32 {
+        if damage <= 0 {
+            return 0;
+        }
+        let resistance = self.resistances.get(&damage_type).copied().unwrap_or(0.0);
+        ((damage as f32 * (1.0 - resistance)) as i32 - self.armor).max(1)
+    }
+
+    pub fn enemy<S: Side>(
+        &self,
+        global_buffs: &GlobalEnemyBuffs,
+        buffs: &EnemyBuffs<S>,
+        scaling: &EnemyScaling,
+    ) -> Enemy<S> {
+        Enemy::new(
+            self.kind,
+            (self.health as f32
+                * (1.0 + global_buffs.health + buffs.health)
+                * (1.0 + scaling.health)) as i32,
+            self.speed * (1.0 + global_buffs.speed + buffs.speed),
+            (self.exp as f32 * (1.0 - (global_buffs.exp + buffs.exp))) as u32,
+        )
+    }
+
+    pub fn attack<S: Side>(
+        &self,
+        global_buffs: &GlobalEnemyBuffs,
+        buffs: &EnemyBuffs<S>,
+        scaling: &EnemyScaling,
+    ) -> EnemyAttack<S> {
+        EnemyAttack::new(
+            (self.damage as f32
+                * (1.0 + global_buffs.damage + buffs.damage)
+                * (1.0 + scaling.damage)) as i32,
+            self.range,
+            self.attack_speed * (1.0 + global_buffs.attack_speed + buffs.attack_speed),
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum EnemyDefinitionError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, ron::error::SpannedError),
+    Invalid(PathBuf, &'static str),
+    Missing(EnemyKind),
+}
+
+impl Display for EnemyDefinitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, e) => f.write_fmt(format_args!("{}: {e}", path.display())),
+            Self::Parse(path, e) => f.write_fmt(format_args!("{}: {e}", path.display())),
+            Self::Invalid(path, reason) => {
+                f.write_fmt(format_args!("{}: {reason}", path.display()))
+            }
+            Self::Missing(kind) => f.write_fmt(format_args!("no definition for {kind:?}")),
+        }
+    }
+}
+
+impl std::error::Error for EnemyDefinitionError {}
+
+#[derive(Default)]
+pub struct EnemyDefinitionLoader;
+
+impl AssetLoader for EnemyDefinitionLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let mut definition = EnemyDefinition::parse(bytes, load_context.path())?;
+
+            let sprite = &definition.sprite;
+            let image_path = AssetPath::new(PathBuf::from(&sprite.path), None);
+            let image: Handle<Image> = load_context.get_handle(image_path.clone());
+            let texture_atlas = TextureAtlas::from_grid(
+                image,
+                sprite.tile_size,
+                sprite.columns,
+                sprite.rows,
+                None,
+                None,
+            );
+            definition.texture_atlas = load_context.set_labeled_asset(
+                "texture_atlas",
+                LoadedAsset::new(texture_atlas).with_dependency(image_path),
+            );
+
+            let projectile_path = definition.ranged.as_mut().map(|ranged| {
+                let path = AssetPath::new(PathBuf::from(&ranged.projectile_sprite), None);
+                ranged.projectile_texture = load_context.get_handle(path.clone());
+                path
+            });
+
+            let mut asset = LoadedAsset::new(definition);
+            if let Some(path) = projectile_path {
+                asset = asset.with_dependency(path);
+            }
+            load_context.set_default_asset(asset);
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[ENEMY_DEFINITION_EXTENSION]
+    }
+}
+
+/// Definitions of all enemy kinds used by the simulation
+#[derive(Debug, Default, Clone, Resource)]
+pub struct EnemyDefinitions {
+    definitions: HashMap<EnemyKind, EnemyDefinition>,
+}
+
+impl EnemyDefinitions {
+    pub fn get(&self, kind: EnemyKind) -> Option<&EnemyDefinition> {
+        self.definitions.get(&kind)
+    }
+
+    pub fn insert(&mut self, definition: EnemyDefinition) {
+        self.definitions.insert(definition.kind, definition);
+    }
+
+    /// Loads all definitions from the folder without the asset server.
+    /// Used by the headless simulation, so sprites are left empty.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn load_from_dir(dir: &Path) -> Result<Self, EnemyDefinitionError> {
+        let mut definitions = Self::default();
+        let entries =
+            std::fs::read_dir(dir).map_err(|e| EnemyDefinitionError::Io(dir.to_path_buf(), e))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| EnemyDefinitionError::Io(dir.to_path_buf(), e))?
+                .path();
+            let is_definition = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| {
+                    name.ends_with(&format!(".{ENEMY_DEFINITION_EXTENSION}"))
+                });
+            if !is_definition {
+                continue;
+            }
+
+            let bytes =
+                std::fs::read(&path).map_err(|e| EnemyDefinitionError::Io(path.clone(), e))?;
+            definitions.insert(EnemyDefinition::parse(&bytes, &path)?);
+        }
+
+        match EnemyKind::ALL
+            .into_iter()
+            .find(|kind| definitions.get(*kind).is_none())
+        {
+            Some(kind) => Err(EnemyDefinitionError::Missing(kind)),
+            None => Ok(definitions),
+        }
+    }
+}
+
+/// Copies loaded and changed definitions into `EnemyDefinitions`.
+/// Only enemies spawned after the change use new stats.
+fn update_definitions(
+    assets: Res<Assets<EnemyDefinition>>,
+    mut events: EventReader<AssetEvent<EnemyDefinition>>,
+    mut definitions: ResMut<EnemyDefinitions>,
+) {
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle } => {
+                if let Some(definition) = assets.get(handle) {
+                    definitions.insert(definition.clone());
+                }
+            }
+            AssetEvent::Modified { handle } => {
+                if let Some(definition) = assets.get(handle) {
+                    info!("reloaded {:?} definition", definition.kind);
+                    definitions.insert(definition.clone());
+                }
+            }
+            AssetEvent::Removed { .. } => {}
+        }
+    }
+}
+
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_definitions_are_valid() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/enemies");
+        if let Err(e) = EnemyDefinitions::load_from_dir(&dir) {
+            panic!("{e}");
+        }
+    }
+
+    fn armored_goblin(armor: i32, resistances: &[(DamageType, f32)]) -> EnemyDefinition {
+        let path = Path::new("goblin.enemy.ron");
+        let mut definition = match EnemyDefinition::parse(
+            include_bytes!("../../../assets/enemies/goblin.enemy.ron"),
+            path,
+        ) {
+            Ok(definition) => definition,
+            Err(e) => panic!("{e}"),
+        };
+        definition.armor = armor;
+        definition.resistances = resistances.iter().copied().collect();
+        definition
+    }
+
+    #[test]
+    fn armor_and_resistance_reduce_damage() {
+        let definition = armored_goblin(5, &[(DamageType::Fire, 0.5)]);
+        assert_eq!(definition.damage_taken(20, DamageType::Piercing), 15);
+        assert_eq!(definition.damage_taken(20, DamageType::Fire), 5);
+    }
+
+    #[test]
+    fn weakness_increases_damage() {
+        let definition = armored_goblin(0, &[(DamageType::Poison, -0.5)]);
+        assert_eq!(definition.damage_taken(20, DamageType::Poison), 30);
+    }
+
+    #[test]
+    fn hits_deal_at_least_one_damage() {
+        let definition = armored_goblin(100, &[(DamageType::Fire, 0.99)]);
+        assert_eq!(definition.damage_taken(20, DamageType::Piercing), 1);
+        assert_eq!(definition.damage_taken(20, DamageType::Fire), 1);
+        // nothing to reduce
+        assert_eq!(definition.damage_taken(0, DamageType::Piercing), 0);
+        assert_eq!(definition.damage_taken(-5, DamageType::Piercing), 0);
+    }
+}
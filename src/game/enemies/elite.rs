@@ -0,0 +1,432 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{
+        castle::{CastleMarker, CastleWall},
+        damage::{CastleDamageEvent, DamageSet, WallDamageEvent},
+        simulation::SimulationSet,
+        East, GameState, North, South, West,
+    },
+    GameAssets,
+};
+
+use super::{
+    boss::SummonEvent, schedule::EliteSettings, wall_distance, Enemy, EnemyAttack, EnemyKind,
+    EnemySoundEvent, Movement, Side,
+};
+
+/// Tint of elites without status effects
+pub const ELITE_COLOR: Color = Color::rgb(1.0, 0.55, 1.0);
+
+/// Part of max health shielded enemies absorb
+const SHIELD_PART: f32 = 0.5;
+/// Multiplier of speed and attack speed of hasty enemies
+const HASTY_MULTIPLIER: f32 = 1.5;
+/// Part of max health healed every second
+const REGENERATION_PER_SECOND: f32 = 0.02;
+const SPLIT_NUMBER: u32 = 2;
+/// Part of dealt damage vampiric enemies heal
+const VAMPIRIC_PART: f32 = 0.5;
+/// Explosion damage as a multiple of the attack damage
+const EXPLOSION_DAMAGE: f32 = 3.0;
+const EXPLOSION_RADIUS: f32 = 150.0;
+
+const LABEL_FONT_SIZE: f32 = 20.0;
+
+pub struct ElitePlugin;
+
+impl Plugin for ElitePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            (
+                regenerate::<North>,
+                regenerate::<South>,
+                regenerate::<West>,
+                regenerate::<East>,
+            )
+                .in_set(SimulationSet::Act)
+                .in_schedule(CoreSchedule::FixedUpdate),
+        )
+        .add_systems(
+            (
+                elite_death::<North>,
+                elite_death::<South>,
+                elite_death::<West>,
+                elite_death::<East>,
+            )
+                .after(DamageSet::Enemies)
+                .before(DamageSet::Castle)
+                .in_set(SimulationSet::Damage)
+                .in_schedule(CoreSchedule::FixedUpdate),
+        )
+        .add_system(spawn_elite_labels.in_set(OnUpdate(GameState::InGame)));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Affix {
+    /// Absorbs damage before losing health
+    Shielded,
+    /// Moves and attacks faster
+    Hasty,
+    /// Heals over time
+    Regenerating,
+    /// Splits into smaller enemies on death
+    Splitting,
+    /// Heals by damaging the castle
+    Vampiric,
+    /// Damages the castle on death if close to it
+    Explosive,
+}
+
+impl Affix {
+    pub const ALL: [Affix; 6] = [
+        Affix::Shielded,
+        Affix::Hasty,
+        Affix::Regenerating,
+        Affix::Splitting,
+        Affix::Vampiric,
+        Affix::Explosive,
+    ];
+}
+
+/// Enemy promoted to an elite.
+/// Each affix is a separate component.
+#[derive(Debug, Clone, Serialize, Deserialize, Component)]
+pub struct Elite {
+    /// Affixes and the enemy kind, shown above the enemy
+    pub name: String,
+    /// Healing never goes above it
+    pub max_health: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Component)]
+pub struct Shielded {
+    pub shield: i32,
+}
+
+impl Shielded {
+    /// Damage left after the shield
+    pub fn absorb(&mut self, damage: i32) -> i32 {
+        let absorbed = damage.clamp(0, self.shield);
+        self.shield -= absorbed;
+        damage - absorbed
+    }
+}
+
+/// Speed and attack speed are raised on promotion
+#[derive(Debug, Clone, Serialize, Deserialize, Component)]
+pub struct Hasty;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Component)]
+pub struct Regenerating {
+    pub per_second: f32,
+    /// Healing accumulated since the last whole point
+    pending: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Component)]
+pub struct Splitting {
+    pub number: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Component)]
+pub struct Vampiric {
+    /// Part of the dealt damage healed
+    pub part: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Component)]
+pub struct Explosive {
+    pub damage: i32,
+    pub radius: f32,
+}
+
+#[derive(Component)]
+struct EliteLabel;
+
+/// Elite with all of its affix components.
+/// Used to promote enemies and to save them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EliteComponents {
+    elite: Elite,
+    shielded: Option<Shielded>,
+    hasty: Option<Hasty>,
+    regenerating: Option<Regenerating>,
+    splitting: Option<Splitting>,
+    vampiric: Option<Vampiric>,
+    explosive: Option<Explosive>,
+}
+
+impl EliteComponents {
+    /// Rolls affixes and applies elite stats to the enemy
+    pub fn roll<S: Side>(
+        rng: &mut impl Rng,
+        settings: &EliteSettings,
+        enemy: &mut Enemy<S>,
+        attack: &mut EnemyAttack<S>,
+    ) -> Self {
+        let number = rng.gen_range(1..=settings.max_affixes.min(Affix::ALL.len()));
+        let mut affixes = Affix::ALL
+            .choose_multiple(rng, number)
+            .copied()
+            .collect::<Vec<_>>();
+        // names keep the same affix order
+        affixes.sort_by_key(|affix| *affix as usize);
+
+        enemy.health = (enemy.health as f32 * settings.health_multiplier) as i32;
+        enemy.exp = (enemy.exp as f32 * settings.exp_multiplier) as u32;
+
+        let mut components = Self {
+            elite: Elite {
+                name: elite_name(&affixes, enemy.kind),
+                max_health: enemy.health,
+            },
+            shielded: None,
+            hasty: None,
+            regenerating: None,
+            splitting: None,
+            vampiric: None,
+            explosive: None,
+        };
+        for affix in affixes {
+            match affix {
+                Affix::Shielded => {
+                    components.shielded = Some(Shielded {
+                        shield: (enemy.health as f32 * SHIELD_PART) as i32,
+                    })
+                }
+                Affix::Hasty => {
+                    enemy.speed *= HASTY_MULTIPLIER;
+                    let duration = attack.attack_timer.duration().div_f32(HASTY_MULTIPLIER);
+                    attack.attack_timer.set_duration(duration);
+                    components.hasty = Some(Hasty);
+                }
+                Affix::Regenerating => {
+                    components.regenerating = Some(Regenerating {
+                        per_second: enemy.health as f32 * REGENERATION_PER_SECOND,
+                        pending: 0.0,
+                    })
+                }
+                Affix::Splitting => {
+                    components.splitting = Some(Splitting {
+                        number: SPLIT_NUMBER,
+                    })
+                }
+                Affix::Vampiric => {
+                    components.vampiric = Some(Vampiric {
+                        part: VAMPIRIC_PART,
+                    })
+                }
+                Affix::Explosive => {
+                    components.explosive = Some(Explosive {
+                        damage: (attack.damage as f32 * EXPLOSION_DAMAGE) as i32,
+                        radius: EXPLOSION_RADIUS,
+                    })
+                }
+            }
+        }
+        components
+    }
+
+    /// Collects components of the elite enemy
+    pub fn collect(world: &World, entity: Entity) -> Option<Self> {
+        Some(Self {
+            elite: world.get::<Elite>(entity)?.clone(),
+            shielded: world.get::<Shielded>(entity).cloned(),
+            hasty: world.get::<Hasty>(entity).cloned(),
+            regenerating: world.get::<Regenerating>(entity).cloned(),
+            splitting: world.get::<Splitting>(entity).cloned(),
+            vampiric: world.get::<Vampiric>(entity).cloned(),
+            explosive: world.get::<Explosive>(entity).cloned(),
+        })
+    }
+
+    /// Inserts components into a spawned enemy or one restored from a save
+    pub fn insert(self, entity: &mut impl InsertComponent) {
+        entity.insert_component(self.elite);
+        if let Some(shielded) = self.shielded {
+            entity.insert_component(shielded);
+        }
+        if let Some(hasty) = self.hasty {
+            entity.insert_component(hasty);
+        }
+        if let Some(regenerating) = self.regenerating {
+            entity.insert_component(regenerating);
+        }
+        if let Some(splitting) = self.splitting {
+            entity.insert_component(splitting);
+        }
+        if let Some(vampiric) = self.vampiric {
+            entity.insert_component(vampiric);
+        }
+        if let Some(explosive) = self.explosive {
+            entity.insert_component(explosive);
+        }
+    }
+}
+
+/// Entity components can be inserted into,
+/// either through commands or directly in the world
+pub trait InsertComponent {
+    fn insert_component(&mut self, component: impl Component);
+}
+
+impl InsertComponent for EntityCommands<'_, '_, '_> {
+    fn insert_component(&mut self, component: impl Component) {
+        self.insert(component);
+    }
+}
+
+impl InsertComponent for EntityMut<'_> {
+    fn insert_component(&mut self, component: impl Component) {
+        self.insert(component);
+    }
+}
+
+fn elite_name(affixes: &[Affix], kind: EnemyKind) -> String {
+    let mut name = affixes
+        .iter()
+        .map(|affix| format!("{affix:?}"))
+        .collect::<Vec<_>>();
+    name.push(format!("{kind:?}"));
+    name.join(" ")
+}
+
+fn regenerate<S: Side>(
+    fixed_time: Res<FixedTime>,
+    mut enemies: Query<(&mut Enemy<S>, &Elite, &mut Regenerating)>,
+) {
+    let delta = fixed_time.period.as_secs_f32();
+    for (mut enemy, elite, mut regenerating) in enemies.iter_mut() {
+        if enemy.health <= 0 || elite.max_health <= enemy.health {
+            regenerating.pending = 0.0;
+            continue;
+        }
+        regenerating.pending += regenerating.per_second * delta;
+        let heal = regenerating.pending.floor();
+        regenerating.pending -= heal;
+        enemy.health = (enemy.health + heal as i32).min(elite.max_health);
+    }
+}
+
+/// Splits and explodes elites which are about to die.
+/// Runs before they despawn with their affixes and before
+/// the castle is damaged, so explosions hit it on the same tick.
+fn elite_death<S: Side>(
+    castle: Query<&Transform, With<CastleMarker>>,
+    wall: Query<(&Transform, &CastleWall<S>)>,
+    enemies: Query<
+        (
+            &Transform,
+            &Enemy<S>,
+            &Movement,
+            Option<&Splitting>,
+            Option<&Explosive>,
+        ),
+        With<Elite>,
+    >,
+    mut summon_events: EventWriter<SummonEvent<S>>,
+    mut wall_damage_events: EventWriter<WallDamageEvent<S>>,
+    mut castle_damage_events: EventWriter<CastleDamageEvent>,
+    mut sound_events: EventWriter<EnemySoundEvent>,
+) {
+    let castle_transform = castle.single();
+    let (wall_transform, wall) = wall.single();
+    for (transform, enemy, movement, splitting, explosive) in enemies.iter() {
+        if 0 < enemy.health {
+            continue;
+        }
+        if let Some(splitting) = splitting {
+            summon_events.send(SummonEvent::new(
+                enemy.kind,
+                splitting.number,
+                transform.translation,
+            ));
+        }
+        if let Some(explosive) = explosive {
+            sound_events.send(EnemySoundEvent::Explosion);
+            match movement {
+                Movement::Ground => {
+                    if wall_distance(wall_transform, wall, transform) <= explosive.radius {
+                        wall_damage_events.send(WallDamageEvent::new(explosive.damage));
+                    }
+                }
+                Movement::Flying => {
+                    let distance = (castle_transform.translation - transform.translation)
+                        .truncate()
+                        .length();
+                    if distance <= explosive.radius {
+                        castle_damage_events.send(CastleDamageEvent {
+                            damage: explosive.damage,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Shows names above new elites, including ones restored from a save
+fn spawn_elite_labels(
+    game_assets: Res<GameAssets>,
+    mut commands: Commands,
+    elites: Query<(Entity, &Elite, &TextureAtlasSprite), Added<Elite>>,
+) {
+    for (entity, elite, sprite) in elites.iter() {
+        let offset = sprite.custom_size.map_or(0.0, |size| size.y / 2.0) + LABEL_FONT_SIZE;
+        let label = commands
+            .spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        elite.name.clone(),
+                        TextStyle {
+                            font: game_assets.font.clone(),
+                            font_size: LABEL_FONT_SIZE,
+                            color: ELITE_COLOR,
+                        },
+                    ),
+                    transform: Transform::from_xyz(0.0, offset, 1.0),
+                    ..default()
+                },
+                EliteLabel,
+            ))
+            .id();
+        commands.entity(entity).add_child(label);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shield_absorbs_up_to_its_value() {
+        let mut shielded = Shielded { shield: 30 };
+        assert_eq!(shielded.absorb(20), 0);
+        assert_eq!(shielded.shield, 10);
+
+        assert_eq!(shielded.absorb(25), 15);
+        assert_eq!(shielded.shield, 0);
+
+        assert_eq!(shielded.absorb(7), 7);
+        assert_eq!(shielded.shield, 0);
+    }
+
+    #[test]
+    fn shield_ignores_empty_hits() {
+        let mut shielded = Shielded { shield: 30 };
+        assert_eq!(shielded.absorb(0), 0);
+        assert_eq!(shielded.shield, 30);
+    }
+
+    #[test]
+    fn elite_name_lists_affixes_before_the_kind() {
+        assert_eq!(
+            elite_name(&[Affix::Shielded, Affix::Hasty], EnemyKind::Goblin),
+            "Shielded Hasty Goblin"
+        );
+    }
+}
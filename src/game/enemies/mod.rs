@@ -3,37 +3,65 @@ use std::{fmt::Display, marker::PhantomData};
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{utils::remove_all_with, GlobalState};
 
-use self::spawn::EnemyBuffs;
+use self::{
+    boss::Boss,
+    definition::{EnemyDefinition, EnemyDefinitions},
+    elite::{Elite, Vampiric},
+    scaling::{EnemyScaling, InfiniteScaling},
+    schedule::{WaveSchedule, WaveSchedules},
+    spawn::EnemyBuffs,
+};
 
 use super::{
+    abilities::Frozen,
     animation::AnimationBundle,
-    castle::{Castle, CastleWall},
-    damage::WallDamageEvent,
-    East, GameState, North, Side, South, West,
+    castle::{Castle, CastleMarker, CastleWall},
+    castle_type::CastleType,
+    damage::{
+        projectile::{EnemyProjectile, EnemyProjectileBundle},
+        CastleDamageEvent, WallDamageEvent,
+    },
+    mode::GameMode,
+    simulation::{Interpolated, SimulationSet, AIR_GROUP, GROUND_GROUP, PROJECTILE_GROUP},
+    status::StatusEffects,
+    East, North, Side, South, West,
 };
 
+pub mod boss;
+pub mod definition;
+pub mod elite;
+pub mod pressure;
+pub mod scaling;
+pub mod schedule;
 pub mod spawn;
 
 /// Needed to make enemies move.
 /// Otherwise we would need set enormous speeds.
-#[cfg(target_family = "wasm")]
-const ENEMY_FORCE_MULTIPLIER: f32 = 300.0;
-#[cfg(not(target_family = "wasm"))]
 const ENEMY_FORCE_MULTIPLIER: f32 = 1000.0;
 
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_state::<SpawnState>()
-            .add_collection_to_loading_state::<_, EnemySprites>(GlobalState::AssetLoading)
+        app.add_event::<EnemyDeathEvent<North>>()
+            .add_event::<EnemyDeathEvent<South>>()
+            .add_event::<EnemyDeathEvent<West>>()
+            .add_event::<EnemyDeathEvent<East>>()
+            .add_event::<EnemySoundEvent>()
+            .init_resource::<EnemyDefinitions>()
+            .init_resource::<WaveSchedules>()
             .add_system(setup.in_schedule(OnEnter(GlobalState::InGame)))
+            .add_system(
+                enemy_spawn_state_progress
+                    .in_set(SimulationSet::Progress)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
             .add_systems(
                 (
-                    enemy_spawn_state_progress,
                     enemy_movement::<North>,
                     enemy_movement::<South>,
                     enemy_movement::<West>,
@@ -42,14 +70,24 @@ impl Plugin for EnemyPlugin {
                     enemy_attack::<South>,
                     enemy_attack::<West>,
                     enemy_attack::<East>,
+                )
+                    .in_set(SimulationSet::Act)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (
                     enemy_death::<North>,
                     enemy_death::<South>,
                     enemy_death::<West>,
                     enemy_death::<East>,
                 )
-                    .in_set(OnUpdate(GameState::InGame)),
+                    .in_set(SimulationSet::Death)
+                    .in_schedule(CoreSchedule::FixedUpdate),
             )
             .add_system(remove_all_with::<EnemyMarker>.in_schedule(OnExit(GlobalState::InGame)))
+            .add_plugin(boss::BossPlugin)
+            .add_plugin(elite::ElitePlugin)
+            .add_plugin(pressure::PressurePlugin)
             .add_plugin(spawn::SpawnPlugin::<North>::default())
             .add_plugin(spawn::SpawnPlugin::<South>::default())
             .add_plugin(spawn::SpawnPlugin::<West>::default())
@@ -57,22 +95,28 @@ impl Plugin for EnemyPlugin {
     }
 }
 
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, States)]
+/// Stage of the run. Stage durations and enemies
+/// spawned during them come from the `WaveSchedule`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SpawnState {
     #[default]
-    // Bats and Gablin
-    // 1 min
     Stage1,
-    // + Skull and Spear goblin
-    // 2 min
     Stage2,
-    // + Huge ivy
-    // 3 min
     Stage3,
-    // + Boss crab
     Stage4,
 }
 
+impl SpawnState {
+    pub fn number(&self) -> u32 {
+        match self {
+            SpawnState::Stage1 => 1,
+            SpawnState::Stage2 => 2,
+            SpawnState::Stage3 => 3,
+            SpawnState::Stage4 => 4,
+        }
+    }
+}
+
 impl Display for SpawnState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -84,68 +128,103 @@ impl Display for SpawnState {
     }
 }
 
-#[derive(Debug, Resource)]
+/// Run condition for systems which should only
+/// run during specific spawn stage
+pub fn in_spawn_state(
+    state: SpawnState,
+) -> impl FnMut(Option<Res<SpawnStateTimer>>) -> bool + Clone {
+    move |spawn_state_timer: Option<Res<SpawnStateTimer>>| {
+        spawn_state_timer.map_or(false, |timer| timer.current_state == state)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Resource)]
 pub struct SpawnStateTimer {
     pub current_state: SpawnState,
     pub timer: Option<Timer>,
+    /// Number of difficulty tiers passed after the final stage.
+    /// Only grows in infinite mode.
+    pub tier: u32,
 }
 
-impl Default for SpawnStateTimer {
-    fn default() -> Self {
+impl SpawnStateTimer {
+    /// Starts the first stage with
+    /// stage durations from the wave schedule
+    pub fn new(stage_durations: [f32; 3]) -> Self {
         Self {
             current_state: Default::default(),
-            timer: Some(Timer::from_seconds(Self::FIST_STAGE, TimerMode::Once)),
+            timer: Some(Timer::from_seconds(stage_durations[0], TimerMode::Once)),
+            tier: 0,
         }
     }
-}
 
-impl SpawnStateTimer {
-    const FIST_STAGE: f32 = 60.0;
-    const SECOND_STAGE: f32 = 60.0 * 2.0;
-    const THIRD_STAGE: f32 = 60.0 * 2.0;
+    /// Stage number which keeps growing
+    /// after the final stage in infinite mode
+    pub fn stage_number(&self) -> u32 {
+        self.current_state.number() + self.tier
+    }
 
-    fn next_state(&mut self) {
+    fn next_state(
+        &mut self,
+        game_mode: GameMode,
+        infinite_scaling: &InfiniteScaling,
+        stage_durations: [f32; 3],
+    ) {
         match self.current_state {
             SpawnState::Stage1 => {
                 self.current_state = SpawnState::Stage2;
-                self.timer = Some(Timer::from_seconds(Self::SECOND_STAGE, TimerMode::Once));
+                self.timer = Some(Timer::from_seconds(stage_durations[1], TimerMode::Once));
             }
             SpawnState::Stage2 => {
                 self.current_state = SpawnState::Stage3;
-                self.timer = Some(Timer::from_seconds(Self::THIRD_STAGE, TimerMode::Once));
+                self.timer = Some(Timer::from_seconds(stage_durations[2], TimerMode::Once));
             }
             SpawnState::Stage3 => {
                 self.current_state = SpawnState::Stage4;
-                self.timer = None;
+                self.timer = match game_mode {
+                    GameMode::Normal => None,
+                    GameMode::Infinite => Some(Timer::from_seconds(
+                        infinite_scaling.tier_duration,
+                        TimerMode::Repeating,
+                    )),
+                };
             }
             _ => {}
         }
     }
 }
 
-#[derive(AssetCollection, Resource)]
-pub struct EnemySprites {
-    #[asset(texture_atlas(tile_size_x = 32.0, tile_size_y = 32.0, columns = 4, rows = 1,))]
-    #[asset(path = "sprites/mad_crab.png")]
-    pub mad_crab: Handle<TextureAtlas>,
-    #[asset(texture_atlas(tile_size_x = 32.0, tile_size_y = 32.0, columns = 4, rows = 1,))]
-    #[asset(path = "sprites/goblin.png")]
-    pub goblin: Handle<TextureAtlas>,
-    #[asset(texture_atlas(tile_size_x = 32.0, tile_size_y = 32.0, columns = 4, rows = 1,))]
-    #[asset(path = "sprites/spear_goblin.png")]
-    pub spear_goblin: Handle<TextureAtlas>,
-    #[asset(texture_atlas(tile_size_x = 32.0, tile_size_y = 32.0, columns = 4, rows = 1,))]
-    #[asset(path = "sprites/bat.png")]
-    pub bat: Handle<TextureAtlas>,
-    #[asset(texture_atlas(tile_size_x = 32.0, tile_size_y = 32.0, columns = 4, rows = 1,))]
-    #[asset(path = "sprites/skull.png")]
-    pub skull: Handle<TextureAtlas>,
-    #[asset(texture_atlas(tile_size_x = 32.0, tile_size_y = 32.0, columns = 4, rows = 1,))]
-    #[asset(path = "sprites/poison_ivy.png")]
-    pub poison_ivy: Handle<TextureAtlas>,
+/// Enemy definition files. Only read through `EnemyDefinitions`,
+/// handles are kept here so definitions stay loaded.
+#[derive(Default, AssetCollection, Resource)]
+pub struct EnemyAssets {
+    #[asset(path = "enemies/mad_crab.enemy.ron")]
+    pub mad_crab: Handle<EnemyDefinition>,
+    #[asset(path = "enemies/goblin.enemy.ron")]
+    pub goblin: Handle<EnemyDefinition>,
+    #[asset(path = "enemies/spear_goblin.enemy.ron")]
+    pub spear_goblin: Handle<EnemyDefinition>,
+    #[asset(path = "enemies/bat.enemy.ron")]
+    pub bat: Handle<EnemyDefinition>,
+    #[asset(path = "enemies/skull.enemy.ron")]
+    pub skull: Handle<EnemyDefinition>,
+    #[asset(path = "enemies/poison_ivy.enemy.ron")]
+    pub poison_ivy: Handle<EnemyDefinition>,
 }
 
-#[derive(Debug, Default, Resource)]
+/// Wave schedule files. Only read through `WaveSchedules`.
+/// New schedules for a mode or a castle type are added here.
+#[derive(Default, AssetCollection, Resource)]
+pub struct ScheduleAssets {
+    #[asset(path = "schedules/normal.schedule.ron")]
+    pub normal: Handle<WaveSchedule>,
+    #[asset(path = "schedules/infinite.schedule.ron")]
+    pub infinite: Handle<WaveSchedule>,
+    #[asset(path = "schedules/bastion.schedule.ron")]
+    pub bastion: Handle<WaveSchedule>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
 pub struct GlobalEnemyBuffs {
     pub health: f32,
     pub speed: f32,
@@ -154,17 +233,84 @@ pub struct GlobalEnemyBuffs {
     pub attack_speed: f32,
 }
 
-#[derive(Debug, Default, Component)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EnemyKind {
+    MadCrab,
+    Goblin,
+    SpearGoblin,
+    Bat,
+    Skull,
+    PoisonIvy,
+}
+
+impl EnemyKind {
+    pub const ALL: [EnemyKind; 6] = [
+        EnemyKind::Bat,
+        EnemyKind::Goblin,
+        EnemyKind::SpearGoblin,
+        EnemyKind::Skull,
+        EnemyKind::PoisonIvy,
+        EnemyKind::MadCrab,
+    ];
+}
+
+/// How the enemy gets to the castle
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Component)]
+pub enum Movement {
+    /// Walks to the wall and attacks it
+    #[default]
+    Ground,
+    /// Flies over the walls and attacks the castle core.
+    /// Only anti-air weapons can hit it.
+    Flying,
+}
+
+impl Movement {
+    fn collision_groups(&self) -> CollisionGroups {
+        match self {
+            Movement::Ground => CollisionGroups::new(GROUND_GROUP, Group::ALL),
+            Movement::Flying => CollisionGroups::new(AIR_GROUP, AIR_GROUP | PROJECTILE_GROUP),
+        }
+    }
+}
+
+/// Event to play enemy sound
+#[derive(Debug, Clone, Copy)]
+pub enum EnemySoundEvent {
+    Shoot,
+    Explosion,
+}
+
+/// Event sent when enemy dies
+pub struct EnemyDeathEvent<S: Side> {
+    pub kind: EnemyKind,
+    _phantom: PhantomData<S>,
+}
+
+impl<S: Side> EnemyDeathEvent<S> {
+    pub fn new(kind: EnemyKind) -> Self {
+        Self {
+            kind,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Component)]
+#[serde(bound = "")]
 pub struct Enemy<S: Side> {
+    pub kind: EnemyKind,
     pub health: i32,
     pub speed: f32,
     pub exp: u32,
+    #[serde(skip)]
     _phantom: PhantomData<S>,
 }
 
 impl<S: Side> Enemy<S> {
-    pub fn new(health: i32, speed: f32, exp: u32) -> Self {
+    pub fn new(kind: EnemyKind, health: i32, speed: f32, exp: u32) -> Self {
         Self {
+            kind,
             health,
             speed,
             exp,
@@ -173,11 +319,13 @@ impl<S: Side> Enemy<S> {
     }
 }
 
-#[derive(Debug, Default, Component)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Component)]
+#[serde(bound = "")]
 pub struct EnemyAttack<S: Side> {
     damage: i32,
     range: f32,
     attack_timer: Timer,
+    #[serde(skip)]
     _phantom: PhantomData<S>,
 }
 
@@ -201,241 +349,236 @@ impl<S: Side> EnemyAttack<S> {
 pub struct EnemyMarker;
 
 #[derive(Bundle)]
-pub struct EnemyBundle<S: Side, E: EnemyType<S>> {
+pub struct EnemyBundle<S: Side> {
     #[bundle]
     animation_bundle: AnimationBundle,
     rigid_body: RigidBody,
     collider: Collider,
+    collision_groups: CollisionGroups,
     locked_axis: LockedAxes,
     velocity: Velocity,
     damping: Damping,
     enemy: Enemy<S>,
     attack: EnemyAttack<S>,
-    enemy_type: E,
+    movement: Movement,
+    status: StatusEffects,
+    interpolated: Interpolated,
     marker: EnemyMarker,
 }
 
-impl<S: Side, E: EnemyType<S>> EnemyBundle<S, E> {
-    fn new(
-        size: f32,
-        texture_atlas: Handle<TextureAtlas>,
+impl<S: Side> EnemyBundle<S> {
+    pub fn new(
+        definition: &EnemyDefinition,
         position: Vec3,
         global_buffs: &GlobalEnemyBuffs,
         buffs: &EnemyBuffs<S>,
+        scaling: &EnemyScaling,
+    ) -> Self {
+        Self::from_enemy(
+            definition,
+            position,
+            definition.enemy(global_buffs, buffs, scaling),
+            definition.attack(global_buffs, buffs, scaling),
+        )
+    }
+
+    /// Creates bundle with already calculated stats
+    pub fn from_enemy(
+        definition: &EnemyDefinition,
+        position: Vec3,
+        enemy: Enemy<S>,
+        attack: EnemyAttack<S>,
     ) -> Self {
+        let sprite = &definition.sprite;
         Self {
-            // Double side for sprites to better correlate with collider size
-            animation_bundle: AnimationBundle::new(texture_atlas, size * 2.0, 3, 5.0, position),
+            animation_bundle: AnimationBundle::new(
+                definition.texture_atlas.clone(),
+                sprite.size,
+                sprite.frames - 1,
+                sprite.fps,
+                position,
+            ),
             rigid_body: RigidBody::Dynamic,
-            collider: Collider::ball(size * 0.5),
+            collider: Collider::ball(definition.collider_radius),
+            collision_groups: definition.movement.collision_groups(),
             locked_axis: LockedAxes::ROTATION_LOCKED,
             velocity: Velocity::default(),
             damping: Damping {
                 linear_damping: 5.0,
                 angular_damping: 10.0,
             },
-            enemy: E::enemy(global_buffs, buffs),
-            attack: E::attack(global_buffs, buffs),
-            enemy_type: E::default(),
+            enemy,
+            attack,
+            movement: definition.movement,
+            status: StatusEffects::default(),
+            interpolated: Interpolated::default(),
             marker: EnemyMarker,
         }
     }
 }
 
-pub trait EnemyType<S: Side>: Component + Default {
-    const HEALTH: i32;
-    const SPEED: f32;
-    const EXP: u32;
-    const DAMAGE: i32;
-    const SIZE: f32;
-    // Range should be bigger then enemy size / 2
-    const RANGE: f32;
-    const ATTACK_SPEED: f32;
-    const NUMBER_PER_SPAWN: u32;
-
-    fn enemy(global_buffs: &GlobalEnemyBuffs, buffs: &EnemyBuffs<S>) -> Enemy<S> {
-        Enemy::new(
-            (Self::HEALTH as f32 * (1.0 + global_buffs.health + buffs.health)) as i32,
-            Self::SPEED * (1.0 + global_buffs.speed + buffs.speed),
-            (Self::EXP as f32 * (1.0 - (global_buffs.exp + buffs.exp))) as u32,
-        )
-    }
-
-    fn attack(global_buffs: &GlobalEnemyBuffs, buffs: &EnemyBuffs<S>) -> EnemyAttack<S> {
-        EnemyAttack::new(
-            (Self::DAMAGE as f32 * (1.0 + global_buffs.damage + buffs.damage)) as i32,
-            Self::RANGE,
-            Self::ATTACK_SPEED * (1.0 + global_buffs.attack_speed + buffs.attack_speed),
-        )
-    }
-
-    fn texture_atlas(enemy_sprites: &EnemySprites) -> Handle<TextureAtlas>;
-}
-
-#[derive(Debug, Default, Component)]
-pub struct MadCrab;
-
-impl<S: Side> EnemyType<S> for MadCrab {
-    const HEALTH: i32 = 1000;
-    const SPEED: f32 = 8.0;
-    const EXP: u32 = 200;
-    const DAMAGE: i32 = 100;
-    const SIZE: f32 = 128.0;
-    const RANGE: f32 = 200.0;
-    const ATTACK_SPEED: f32 = 1.1;
-    const NUMBER_PER_SPAWN: u32 = 3;
-
-    fn texture_atlas(enemy_sprites: &EnemySprites) -> Handle<TextureAtlas> {
-        enemy_sprites.mad_crab.clone()
-    }
-}
-
-#[derive(Debug, Default, Component)]
-pub struct Goblin;
-
-impl<S: Side> EnemyType<S> for Goblin {
-    const HEALTH: i32 = 80;
-    const SPEED: f32 = 15.0;
-    const EXP: u32 = 5;
-    const DAMAGE: i32 = 10;
-    const SIZE: f32 = 16.0;
-    const RANGE: f32 = 20.0;
-    const ATTACK_SPEED: f32 = 1.0;
-    const NUMBER_PER_SPAWN: u32 = 3;
-
-    fn texture_atlas(enemy_sprites: &EnemySprites) -> Handle<TextureAtlas> {
-        enemy_sprites.goblin.clone()
-    }
-}
-
-#[derive(Debug, Default, Component)]
-pub struct SpearGoblin;
-
-impl<S: Side> EnemyType<S> for SpearGoblin {
-    const HEALTH: i32 = 100;
-    const SPEED: f32 = 10.0;
-    const EXP: u32 = 8;
-    const DAMAGE: i32 = 15;
-    const SIZE: f32 = 16.0;
-    const RANGE: f32 = 20.0;
-    const ATTACK_SPEED: f32 = 1.2;
-    const NUMBER_PER_SPAWN: u32 = 2;
-
-    fn texture_atlas(enemy_sprites: &EnemySprites) -> Handle<TextureAtlas> {
-        enemy_sprites.spear_goblin.clone()
-    }
-}
-
-#[derive(Debug, Default, Component)]
-pub struct Bat;
-
-impl<S: Side> EnemyType<S> for Bat {
-    const HEALTH: i32 = 30;
-    const SPEED: f32 = 10.0;
-    const EXP: u32 = 5;
-    const DAMAGE: i32 = 5;
-    const SIZE: f32 = 16.0;
-    const RANGE: f32 = 20.0;
-    const ATTACK_SPEED: f32 = 1.5;
-    const NUMBER_PER_SPAWN: u32 = 5;
-
-    fn texture_atlas(enemy_sprites: &EnemySprites) -> Handle<TextureAtlas> {
-        enemy_sprites.bat.clone()
-    }
-}
-
-#[derive(Debug, Default, Component)]
-pub struct Skull;
-
-impl<S: Side> EnemyType<S> for Skull {
-    const HEALTH: i32 = 120;
-    const SPEED: f32 = 8.0;
-    const EXP: u32 = 50;
-    const DAMAGE: i32 = 15;
-    const SIZE: f32 = 32.0;
-    const RANGE: f32 = 40.0;
-    const ATTACK_SPEED: f32 = 1.0;
-    const NUMBER_PER_SPAWN: u32 = 1;
-
-    fn texture_atlas(enemy_sprites: &EnemySprites) -> Handle<TextureAtlas> {
-        enemy_sprites.skull.clone()
-    }
-}
-
-#[derive(Debug, Default, Component)]
-pub struct PoisonIvy;
-
-impl<S: Side> EnemyType<S> for PoisonIvy {
-    const HEALTH: i32 = 250;
-    const SPEED: f32 = 12.0;
-    const EXP: u32 = 100;
-    const DAMAGE: i32 = 20;
-    const SIZE: f32 = 64.0;
-    const RANGE: f32 = 70.0;
-    const ATTACK_SPEED: f32 = 1.0;
-    const NUMBER_PER_SPAWN: u32 = 2;
-
-    fn texture_atlas(enemy_sprites: &EnemySprites) -> Handle<TextureAtlas> {
-        enemy_sprites.poison_ivy.clone()
-    }
-}
-
-fn setup(mut spawn_state: ResMut<NextState<SpawnState>>, mut commands: Commands) {
-    spawn_state.set(SpawnState::Stage1);
+fn setup(
+    game_mode: Res<GameMode>,
+    castle_type: Res<CastleType>,
+    schedules: Res<WaveSchedules>,
+    mut commands: Commands,
+) {
+    let schedule = schedules.get(*game_mode, *castle_type);
     commands.insert_resource(GlobalEnemyBuffs::default());
-    commands.insert_resource(SpawnStateTimer::default());
+    commands.insert_resource(SpawnStateTimer::new(schedule.stage_durations));
+    commands.insert_resource(EnemyScaling::default());
 }
 
 fn enemy_spawn_state_progress(
-    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
+    game_mode: Res<GameMode>,
+    castle_type: Res<CastleType>,
+    schedules: Res<WaveSchedules>,
+    mut enemy_scaling: ResMut<EnemyScaling>,
     mut spawn_state_timer: ResMut<SpawnStateTimer>,
-    mut enemy_state: ResMut<NextState<SpawnState>>,
 ) {
+    let schedule = schedules.get(*game_mode, *castle_type);
     if let Some(timer) = &mut spawn_state_timer.timer {
-        if timer.tick(time.delta()).finished() {
-            spawn_state_timer.next_state();
-            enemy_state.set(spawn_state_timer.current_state);
+        if timer.tick(fixed_time.period).finished() {
+            // after the final stage only difficulty tiers progress
+            if spawn_state_timer.current_state == SpawnState::Stage4 {
+                spawn_state_timer.tier += 1;
+                enemy_scaling.next_tier(&schedule.scaling, spawn_state_timer.tier);
+            } else {
+                spawn_state_timer.next_state(
+                    *game_mode,
+                    &schedule.scaling,
+                    schedule.stage_durations,
+                );
+            }
         }
     }
 }
 
 /// Moved enemies in direction of the wall
-/// Keeps them pointed at the wall
+/// Keeps them pointed at the wall.
+/// Ranged enemies stop at their standoff distance,
+/// flying ones fly to the castle core instead.
+/// Slowed enemies move slower, stunned ones stand still.
 fn enemy_movement<S: Side>(
-    time: Res<Time>,
-    wall: Query<&Transform, With<CastleWall<S>>>,
-    mut enemies: Query<(&Transform, &Enemy<S>, &mut Velocity)>,
+    fixed_time: Res<FixedTime>,
+    enemy_definitions: Res<EnemyDefinitions>,
+    castle: Query<&Transform, With<CastleMarker>>,
+    wall: Query<(&Transform, &CastleWall<S>)>,
+    mut enemies: Query<
+        (
+            &Transform,
+            &Enemy<S>,
+            &EnemyAttack<S>,
+            &Movement,
+            &StatusEffects,
+            &mut Velocity,
+        ),
+        Without<Frozen>,
+    >,
 ) {
-    let wall_transform = wall.single();
+    let castle_transform = castle.single();
+    let (wall_transform, wall) = wall.single();
+
+    for (enemy_transform, enemy, attack, movement, status, mut enemy_velocity) in enemies.iter_mut()
+    {
+        if status.is_stunned() {
+            enemy_velocity.linvel = Vec2::ZERO;
+            continue;
+        }
+
+        let (target, stop) = match movement {
+            Movement::Ground => {
+                let standoff = enemy_definitions
+                    .get(enemy.kind)
+                    .and_then(|definition| definition.ranged.as_ref())
+                    .map(|ranged| ranged.standoff);
+                let stop = standoff.map_or(false, |standoff| {
+                    wall_distance(wall_transform, wall, enemy_transform) <= standoff
+                });
+                (wall_transform, stop)
+            }
+            Movement::Flying => {
+                let distance = (castle_transform.translation - enemy_transform.translation)
+                    .truncate()
+                    .length();
+                (castle_transform, distance <= attack.range)
+            }
+        };
+        if stop {
+            enemy_velocity.linvel = Vec2::ZERO;
+            continue;
+        }
 
-    for (enemy_transform, enemy, mut enemy_velocity) in enemies.iter_mut() {
-        let vector = (wall_transform.translation - enemy_transform.translation).truncate();
+        let vector = (target.translation - enemy_transform.translation).truncate();
         let direction = vector.normalize();
 
-        let movement = direction * time.delta().as_secs_f32();
-        enemy_velocity.linvel = movement * enemy.speed * ENEMY_FORCE_MULTIPLIER;
+        let movement = direction * fixed_time.period.as_secs_f32();
+        enemy_velocity.linvel =
+            movement * enemy.speed * status.speed_multiplier() * ENEMY_FORCE_MULTIPLIER;
     }
 }
 
+/// Distance from the enemy to the wall surface
+/// along the direction of the side
+pub fn wall_distance<S: Side>(
+    wall_transform: &Transform,
+    wall: &CastleWall<S>,
+    enemy_transform: &Transform,
+) -> f32 {
+    (wall_transform
+        .translation
+        .truncate()
+        .dot(S::DIRECTION.abs())
+        - enemy_transform
+            .translation
+            .truncate()
+            .dot(S::DIRECTION.abs()))
+    .abs()
+        - wall.half_thickness
+}
+
+/// Melee enemies damage the wall directly, ranged ones
+/// shoot projectiles at it and flying ones damage the core.
+/// Attacks of stunned enemies are paused, vampiric elites heal by attacking.
 fn enemy_attack<S: Side>(
-    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
+    enemy_definitions: Res<EnemyDefinitions>,
+    castle: Query<&Transform, With<CastleMarker>>,
     wall: Query<(&Transform, &CastleWall<S>)>,
-    mut enemies: Query<(&Transform, &mut EnemyAttack<S>)>,
+    mut commands: Commands,
+    mut enemies: Query<
+        (
+            &Transform,
+            &mut Enemy<S>,
+            &Movement,
+            &StatusEffects,
+            &mut EnemyAttack<S>,
+            Option<(&Elite, &Vampiric)>,
+        ),
+        Without<Frozen>,
+    >,
     mut damage_events: EventWriter<WallDamageEvent<S>>,
+    mut castle_damage_events: EventWriter<CastleDamageEvent>,
+    mut sound_events: EventWriter<EnemySoundEvent>,
 ) {
+    let castle_transform = castle.single();
     let (wall_transform, wall) = wall.single();
 
-    for (enemy_transform, mut enemy_attack) in enemies.iter_mut() {
-        let distance = (wall_transform
-            .translation
-            .truncate()
-            .dot(S::DIRECTION.abs())
-            - enemy_transform
-                .translation
+    for (enemy_transform, mut enemy, movement, status, mut enemy_attack, vampiric) in
+        enemies.iter_mut()
+    {
+        if status.is_stunned() {
+            enemy_attack.attack_timer.pause();
+            continue;
+        }
+
+        let distance = match movement {
+            Movement::Ground => wall_distance(wall_transform, wall, enemy_transform),
+            Movement::Flying => (castle_transform.translation - enemy_transform.translation)
                 .truncate()
-                .dot(S::DIRECTION.abs()))
-        .abs()
-            - wall.half_thickness;
+                .length(),
+        };
 
         if enemy_attack.range < distance {
             continue;
@@ -443,24 +586,62 @@ fn enemy_attack<S: Side>(
 
         enemy_attack.attack_timer.unpause();
 
-        if !enemy_attack.attack_timer.tick(time.delta()).finished() {
+        if !enemy_attack.attack_timer.tick(fixed_time.period).finished() {
+            continue;
+        }
+
+        if let Some((elite, vampiric)) = vampiric {
+            let heal = (enemy_attack.damage as f32 * vampiric.part) as i32;
+            enemy.health = (enemy.health + heal).min(elite.max_health);
+        }
+
+        if *movement == Movement::Flying {
+            castle_damage_events.send(CastleDamageEvent {
+                damage: enemy_attack.damage,
+            });
             continue;
         }
 
-        damage_events.send(WallDamageEvent::new(enemy_attack.damage));
+        let ranged = enemy_definitions
+            .get(enemy.kind)
+            .and_then(|definition| definition.ranged.as_ref());
+        match ranged {
+            Some(ranged) => {
+                let direction = (wall_transform.translation - enemy_transform.translation)
+                    .truncate()
+                    .normalize();
+                commands.spawn(EnemyProjectileBundle::<S>::new(
+                    ranged.projectile_texture.clone(),
+                    ranged.projectile_size,
+                    EnemyProjectile::new(enemy.kind, enemy_attack.damage, ranged.interceptable),
+                    Velocity::linear(direction * ranged.projectile_speed),
+                    enemy_transform.translation,
+                ));
+                sound_events.send(EnemySoundEvent::Shoot);
+            }
+            None => damage_events.send(WallDamageEvent::new(enemy_attack.damage)),
+        }
     }
 }
 
-fn enemy_death<S: Side>(
-    enemies: Query<(Entity, &Enemy<S>)>,
+pub fn enemy_death<S: Side>(
+    castle_type: Res<CastleType>,
+    enemies: Query<(Entity, &Enemy<S>, Option<&Boss>)>,
     mut commands: Commands,
     mut castle: Query<&mut Castle>,
+    mut death_events: EventWriter<EnemyDeathEvent<S>>,
 ) {
+    let exp_multiplier = castle_type.definition().exp_multiplier();
     let mut castle = castle.single_mut();
-    for (enemy_entity, enemy) in enemies.iter() {
+    for (enemy_entity, enemy, boss) in enemies.iter() {
         if enemy.health <= 0 {
-            castle.exp += enemy.exp;
-            commands.entity(enemy_entity).despawn();
+            castle.exp += (enemy.exp as f32 * exp_multiplier) as u32;
+            if let Some(boss) = boss {
+                castle.bonus_upgrades += boss.bonus_upgrades;
+            }
+            // elites have their name label as a child
+            commands.entity(enemy_entity).despawn_recursive();
+            death_events.send(EnemyDeathEvent::new(enemy.kind));
         }
     }
 }
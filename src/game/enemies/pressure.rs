@@ -0,0 +1,222 @@
+use bevy::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{
+        castle_type::CastleType, mode::GameMode, rng::GameRng, simulation::SimulationSet, SideId,
+    },
+    GlobalState,
+};
+
+use super::{
+    enemy_spawn_state_progress,
+    schedule::{SidePressure, WaveSchedules},
+};
+
+pub struct PressurePlugin;
+
+impl Plugin for PressurePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PressureWarningEvent>()
+            .add_system(setup.in_schedule(OnEnter(GlobalState::InGame)))
+            .add_system(
+                update_pressure
+                    .in_set(SimulationSet::Progress)
+                    .after(enemy_spawn_state_progress)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            );
+    }
+}
+
+/// Sent when spawn pressure is about to move
+#[derive(Debug, Clone, Copy)]
+pub struct PressureWarningEvent {
+    pub side: SideId,
+    pub warning: PressureWarning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressureWarning {
+    /// Side becomes the focus after the warning time
+    Focus,
+    /// Flank on the side started
+    Flank,
+}
+
+/// How threatening the side is right now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Threat {
+    Quiet,
+    Normal,
+    High,
+    Extreme,
+}
+
+impl Threat {
+    fn from_multiplier(multiplier: f32) -> Self {
+        if multiplier < 1.0 {
+            Threat::Quiet
+        } else if multiplier <= 1.0 {
+            Threat::Normal
+        } else if multiplier <= 2.0 {
+            Threat::High
+        } else {
+            Threat::Extreme
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlankState {
+    pub side: SideId,
+    pub remaining: f32,
+    pub multiplier: f32,
+}
+
+/// Current spawn pressure of every side
+#[derive(Debug, Clone, Serialize, Deserialize, Resource)]
+pub struct PressureState {
+    /// Seconds since the start of the run
+    pub time: f32,
+    pub focus: Option<SideId>,
+    /// Announced side which becomes the focus next
+    pub next_focus: Option<SideId>,
+    /// Seconds until the focus moves to `next_focus`
+    pub focus_remaining: f32,
+    pub flank: Option<FlankState>,
+    /// Number of times each schedule flank happened
+    pub flanks: Vec<u32>,
+    /// Spawn rate multipliers by `Side::INDEX`
+    pub multipliers: [f32; 4],
+}
+
+impl Default for PressureState {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            focus: None,
+            next_focus: None,
+            focus_remaining: 0.0,
+            flank: None,
+            flanks: vec![],
+            multipliers: [1.0; 4],
+        }
+    }
+}
+
+impl PressureState {
+    pub fn multiplier(&self, side: SideId) -> f32 {
+        self.multipliers[side.index()]
+    }
+
+    pub fn threat(&self, side: SideId) -> Threat {
+        Threat::from_multiplier(self.multiplier(side))
+    }
+
+    fn update_multipliers(&mut self, pressure: &SidePressure) {
+        self.multipliers = [1.0; 4];
+        if let Some(focus) = self.focus {
+            self.multipliers[focus.index()] *= pressure.focus;
+            self.multipliers[focus.opposite().index()] *= pressure.quiet;
+        }
+        if let Some(flank) = &self.flank {
+            self.multipliers[flank.side.index()] *= flank.multiplier;
+        }
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.insert_resource(PressureState::default());
+}
+
+/// Random side other than the current one
+fn random_side(rng: &mut impl Rng, current: Option<SideId>) -> SideId {
+    let sides = SideId::ALL
+        .into_iter()
+        .filter(|side| Some(*side) != current)
+        .collect::<Vec<_>>();
+    sides[rng.gen_range(0..sides.len())]
+}
+
+/// Rotates the focus, starts and ends flanks
+pub fn update_pressure(
+    fixed_time: Res<FixedTime>,
+    game_mode: Res<GameMode>,
+    castle_type: Res<CastleType>,
+    schedules: Res<WaveSchedules>,
+    mut game_rng: ResMut<GameRng>,
+    mut state: ResMut<PressureState>,
+    mut warning_events: EventWriter<PressureWarningEvent>,
+) {
+    let Some(pressure) = &schedules.get(*game_mode, *castle_type).pressure else {
+        // schedule without pressure may be reloaded during the run
+        if state.multipliers != [1.0; 4] {
+            *state = PressureState::default();
+        }
+        return;
+    };
+
+    let delta = fixed_time.period.as_secs_f32();
+    state.time += delta;
+    if state.time < pressure.start {
+        return;
+    }
+
+    // first focus is announced as soon as pressure starts
+    if state.focus.is_none() && state.next_focus.is_none() {
+        let side = random_side(&mut game_rng.pressure, None);
+        state.next_focus = Some(side);
+        state.focus_remaining = pressure.warning;
+        warning_events.send(PressureWarningEvent {
+            side,
+            warning: PressureWarning::Focus,
+        });
+    }
+
+    state.focus_remaining -= delta;
+    if state.focus_remaining <= 0.0 {
+        if let Some(next_focus) = state.next_focus.take() {
+            state.focus = Some(next_focus);
+        }
+        state.focus_remaining += pressure.focus_duration;
+    }
+    if state.next_focus.is_none() && state.focus_remaining <= pressure.warning {
+        let side = random_side(&mut game_rng.pressure, state.focus);
+        state.next_focus = Some(side);
+        warning_events.send(PressureWarningEvent {
+            side,
+            warning: PressureWarning::Focus,
+        });
+    }
+
+    let flank_ended = state.flank.as_mut().map_or(false, |flank| {
+        flank.remaining -= delta;
+        flank.remaining <= 0.0
+    });
+    if flank_ended {
+        state.flank = None;
+    }
+    let time = state.time;
+    state.flanks.resize(pressure.flanks.len(), 0);
+    for (i, flank) in pressure.flanks.iter().enumerate() {
+        if !flank.is_due(time, state.flanks[i]) {
+            continue;
+        }
+        state.flanks[i] += 1;
+        let side = flank
+            .side
+            .unwrap_or_else(|| random_side(&mut game_rng.pressure, None));
+        state.flank = Some(FlankState {
+            side,
+            remaining: flank.duration,
+            multiplier: flank.multiplier,
+        });
+        warning_events.send(PressureWarningEvent {
+            side,
+            warning: PressureWarning::Flank,
+        });
+    }
+
+    state.update_multipliers(pressure);
+}
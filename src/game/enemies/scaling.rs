@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Describes how enemies keep getting stronger
+/// after the final stage in infinite mode.
+/// Every `tier_duration` seconds a new tier starts and
+/// each stat grows by its `*_per_tier` value multiplied by
+/// `growth` to the power of number of already passed tiers.
+/// Set by the wave schedule of the infinite mode.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct InfiniteScaling {
+    pub tier_duration: f32,
+    pub health_per_tier: f32,
+    pub damage_per_tier: f32,
+    pub spawn_rate_per_tier: f32,
+    pub growth: f32,
+}
+
+impl Default for InfiniteScaling {
+    fn default() -> Self {
+        Self {
+            tier_duration: 60.0,
+            health_per_tier: 0.25,
+            damage_per_tier: 0.15,
+            spawn_rate_per_tier: 0.1,
+            growth: 1.1,
+        }
+    }
+}
+
+/// Current difficulty scaling applied on top
+/// of all enemy buffs
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
+pub struct EnemyScaling {
+    pub health: f32,
+    pub damage: f32,
+    pub spawn_rate: f32,
+}
+
+impl EnemyScaling {
+    pub fn next_tier(&mut self, scaling: &InfiniteScaling, tier: u32) {
+        let multiplier = scaling.growth.powi(tier as i32 - 1);
+        self.health += scaling.health_per_tier * multiplier;
+        self.damage += scaling.damage_per_tier * multiplier;
+        self.spawn_rate += scaling.spawn_rate_per_tier * multiplier;
+    }
+}
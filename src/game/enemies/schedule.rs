@@ -0,0 +1,516 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use crate::game::{castle_type::CastleType, mode::GameMode, Side, SideId};
+
+use super::{elite::Affix, scaling::InfiniteScaling, EnemyKind};
+
+/// Extension of the wave schedule files
+pub const WAVE_SCHEDULE_EXTENSION: &str = "schedule.ron";
+
+/// Loads wave schedules through the asset server
+/// and keeps `WaveSchedules` up to date when files change
+pub struct WaveSchedulesPlugin;
+
+impl Plugin for WaveSchedulesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<WaveSchedule>()
+            .init_asset_loader::<WaveScheduleLoader>()
+            .init_resource::<WaveSchedules>()
+            .add_system(update_schedules);
+    }
+}
+
+/// Describes which enemies spawn on which sides during the run
+#[derive(Debug, Clone, Deserialize, TypeUuid)]
+#[uuid = "9d3b2f71-6c0a-4b8e-b5d4-7e1f0a2c9b64"]
+pub struct WaveSchedule {
+    pub mode: GameMode,
+    /// Castle type the schedule is made for.
+    /// Schedules without it are used for all castles of the mode.
+    #[serde(default)]
+    pub castle_type: Option<CastleType>,
+    /// Durations of the first three stages.
+    /// The final stage lasts until the end of the run.
+    pub stage_durations: [f32; 3],
+    /// Enemies spawned over and over
+    #[serde(default)]
+    pub waves: Vec<Wave>,
+    /// Enemies spawned all at once at specific time
+    #[serde(default)]
+    pub bursts: Vec<Burst>,
+    /// Makes some sides busier than others.
+    /// All sides spawn equally if not set.
+    #[serde(default)]
+    pub pressure: Option<SidePressure>,
+    /// Promotes some spawned enemies to elites.
+    /// No elites spawn if not set.
+    #[serde(default)]
+    pub elites: Option<EliteSettings>,
+    /// Growth of enemies after the final stage.
+    /// Only used in infinite mode.
+    #[serde(default)]
+    pub scaling: InfiniteScaling,
+}
+
+impl Default for WaveSchedule {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::default(),
+            castle_type: None,
+            stage_durations: [60.0, 60.0 * 2.0, 60.0 * 2.0],
+            waves: vec![],
+            bursts: vec![],
+            pressure: None,
+            elites: None,
+            scaling: InfiniteScaling::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Wave {
+    pub enemy: EnemyKind,
+    #[serde(default = "all_sides")]
+    pub sides: Vec<SideId>,
+    /// Seconds since the start of the run the wave starts at
+    #[serde(default)]
+    pub start: f32,
+    /// Seconds since the start of the run the wave stops at
+    #[serde(default)]
+    pub end: Option<f32>,
+    /// Seconds between spawns
+    pub interval: f32,
+    /// Enemies per spawn. `number_per_spawn` of the enemy if not set.
+    #[serde(default)]
+    pub number: Option<u32>,
+    #[serde(default)]
+    pub ramp: Ramp,
+}
+
+impl Wave {
+    pub fn is_active<S: Side>(&self, time: f32) -> bool {
+        self.sides.contains(&SideId::of::<S>())
+            && self.start <= time
+            && self.end.map_or(true, |end| time < end)
+    }
+
+    /// Interval at the time with the ramp applied
+    pub fn interval_at(&self, time: f32) -> f32 {
+        let minutes = (time - self.start) / 60.0;
+        (self.interval - self.ramp.interval * minutes)
+            .max(self.ramp.min_interval.min(self.interval))
+    }
+
+    /// Enemies per spawn at the time with the ramp applied
+    pub fn number_at(&self, time: f32, number_per_spawn: u32) -> u32 {
+        let minutes = (time - self.start) / 60.0;
+        self.number.unwrap_or(number_per_spawn) + (self.ramp.number * minutes) as u32
+    }
+}
+
+/// How the wave grows every minute after its start
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Ramp {
+    /// Seconds the interval shrinks by
+    #[serde(default)]
+    pub interval: f32,
+    /// Interval never gets shorter than this
+    #[serde(default)]
+    pub min_interval: f32,
+    /// Enemies added to every spawn
+    #[serde(default)]
+    pub number: f32,
+}
+
+/// One-off spawn, like a boss arriving
+#[derive(Debug, Clone, Deserialize)]
+pub struct Burst {
+    pub enemy: EnemyKind,
+    #[serde(default = "all_sides")]
+    pub sides: Vec<SideId>,
+    /// Seconds since the start of the run
+    pub time: f32,
+    pub number: u32,
+    /// Seconds after which the burst happens again
+    #[serde(default)]
+    pub repeat: Option<f32>,
+}
+
+impl Burst {
+    /// Burst should happen if its next time came.
+    /// `spawned` is the number of times it already happened.
+    pub fn is_due<S: Side>(&self, time: f32, spawned: u32) -> bool {
+        if !self.sides.contains(&SideId::of::<S>()) {
+            return false;
+        }
+        match (spawned, self.repeat) {
+            (0, _) => self.time <= time,
+            (_, Some(repeat)) => self.time + repeat * spawned as f32 <= time,
+            (_, None) => false,
+        }
+    }
+}
+
+/// Spawn pressure moving between sides.
+/// Multipliers scale spawn rate of the waves on the side.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SidePressure {
+    /// Seconds since the start of the run the focus starts at
+    #[serde(default)]
+    pub start: f32,
+    /// Seconds the focus stays on one side
+    pub focus_duration: f32,
+    /// Seconds the next focus side is announced in advance
+    pub warning: f32,
+    /// Multiplier of the focused side
+    pub focus: f32,
+    /// Multiplier of the side opposite to the focused one
+    pub quiet: f32,
+    #[serde(default)]
+    pub flanks: Vec<Flank>,
+}
+
+/// Short surge on a single side
+#[derive(Debug, Clone, Deserialize)]
+pub struct Flank {
+    /// Seconds since the start of the run
+    pub time: f32,
+    /// Seconds after which the flank happens again
+    #[serde(default)]
+    pub repeat: Option<f32>,
+    /// Random side if not set
+    #[serde(default)]
+    pub side: Option<SideId>,
+    pub duration: f32,
+    pub multiplier: f32,
+}
+
+impl Flank {
+    /// Flank should start if its next time came.
+    /// `started` is the number of times it already happened.
+    pub fn is_due(&self, time: f32, started: u32) -> bool {
+        match (started, self.repeat) {
+            (0, _) => self.time <= time,
+            (_, Some(repeat)) => self.time + repeat * started as f32 <= time,
+            (_, None) => false,
+        }
+    }
+}
+
+/// Chance of spawned enemies to become elites
+#[derive(Debug, Clone, Deserialize)]
+pub struct EliteSettings {
+    /// Seconds since the start of the run elites start at
+    #[serde(default)]
+    pub start: f32,
+    /// Chance at the start
+    pub chance: f32,
+    /// Chance added every minute after the start
+    #[serde(default)]
+    pub chance_per_minute: f32,
+    /// Chance never gets bigger than this
+    pub max_chance: f32,
+    /// Elites get from 1 up to this number of affixes
+    pub max_affixes: usize,
+    pub health_multiplier: f32,
+    pub exp_multiplier: f32,
+}
+
+impl EliteSettings {
+    /// Chance at the time with the growth applied
+    pub fn chance_at(&self, time: f32) -> f32 {
+        if time < self.start {
+            return 0.0;
+        }
+        let minutes = (time - self.start) / 60.0;
+        (self.chance + self.chance_per_minute * minutes).min(self.max_chance)
+    }
+}
+
+fn all_sides() -> Vec<SideId> {
+    SideId::ALL.to_vec()
+}
+
+impl WaveSchedule {
+    /// Parses and validates the schedule file
+    pub fn parse(bytes: &[u8], path: &Path) -> Result<Self, WaveScheduleError> {
+        let schedule: Self = ron::de::from_bytes(bytes)
+            .map_err(|e| WaveScheduleError::Parse(path.to_path_buf(), e))?;
+        schedule
+            .validate()
+            .map_err(|reason| WaveScheduleError::Invalid(path.to_path_buf(), reason))?;
+        Ok(schedule)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.stage_durations.iter().any(|duration| *duration <= 0.0) {
+            return Err("stage_durations must be positive".to_string());
+        }
+        for (i, wave) in self.waves.iter().enumerate() {
+            let reason = if wave.sides.is_empty() {
+                "sides can not be empty"
+            } else if wave.start < 0.0 {
+                "start can not be negative"
+            } else if wave.end.map_or(false, |end| end <= wave.start) {
+                "end must be after start"
+            } else if wave.interval <= 0.0 {
+                "interval must be positive"
+            } else if wave.number == Some(0) {
+                "number must be positive"
+            } else if wave.ramp.interval < 0.0 || wave.ramp.number < 0.0 {
+                "ramp can not be negative"
+            } else if 0.0 < wave.ramp.interval && wave.ramp.min_interval <= 0.0 {
+                "ramp with interval needs positive min_interval"
+            } else {
+                continue;
+            };
+            return Err(format!("wave {i} ({:?}): {reason}", wave.enemy));
+        }
+        for (i, burst) in self.bursts.iter().enumerate() {
+            let reason = if burst.sides.is_empty() {
+                "sides can not be empty"
+            } else if burst.time < 0.0 {
+                "time can not be negative"
+            } else if burst.number == 0 {
+                "number must be positive"
+            } else if burst.repeat.map_or(false, |repeat| repeat <= 0.0) {
+                "repeat must be positive"
+            } else {
+                continue;
+            };
+            return Err(format!("burst {i} ({:?}): {reason}", burst.enemy));
+        }
+        if let Some(pressure) = &self.pressure {
+            let reason = if pressure.start < 0.0 {
+                Some("start can not be negative")
+            } else if pressure.focus_duration <= 0.0 {
+                Some("focus_duration must be positive")
+            } else if pressure.warning < 0.0 || pressure.focus_duration < pressure.warning {
+                Some("warning must be between 0 and focus_duration")
+            } else if pressure.focus < 0.0 || pressure.quiet < 0.0 {
+                Some("multipliers can not be negative")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(format!("pressure: {reason}"));
+            }
+            for (i, flank) in pressure.flanks.iter().enumerate() {
+                let reason = if flank.time < 0.0 {
+                    "time can not be negative"
+                } else if flank.duration <= 0.0 {
+                    "duration must be positive"
+                } else if flank.multiplier < 0.0 {
+                    "multiplier can not be negative"
+                } else if flank.repeat.map_or(false, |repeat| repeat <= 0.0) {
+                    "repeat must be positive"
+                } else {
+                    continue;
+                };
+                return Err(format!("flank {i}: {reason}"));
+            }
+        }
+        if let Some(elites) = &self.elites {
+            let reason = if elites.start < 0.0 {
+                Some("start can not be negative")
+            } else if elites.chance < 0.0 || elites.chance_per_minute < 0.0 {
+                Some("chance can not be negative")
+            } else if !(0.0..=1.0).contains(&elites.max_chance) {
+                Some("max_chance must be between 0 and 1")
+            } else if !(1..=Affix::ALL.len()).contains(&elites.max_affixes) {
+                Some("max_affixes must be between 1 and the number of affixes")
+            } else if elites.health_multiplier <= 0.0 || elites.exp_multiplier < 0.0 {
+                Some("health_multiplier must be positive, exp_multiplier not negative")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(format!("elites: {reason}"));
+            }
+        }
+        let scaling = &self.scaling;
+        let reason = if scaling.tier_duration <= 0.0 {
+            Some("tier_duration must be positive")
+        } else if scaling.health_per_tier < 0.0
+            || scaling.damage_per_tier < 0.0
+            || scaling.spawn_rate_per_tier < 0.0
+        {
+            Some("growth per tier can not be negative")
+        } else if scaling.growth <= 0.0 {
+            Some("growth must be positive")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(format!("scaling: {reason}"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum WaveScheduleError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, ron::error::SpannedError),
+    Invalid(PathBuf, String),
+}
+
+impl Display for WaveScheduleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, e) => f.write_fmt(format_args!("{}: {e}", path.display())),
+            Self::Parse(path, e) => f.write_fmt(format_args!("{}: {e}", path.display())),
+            Self::Invalid(path, reason) => {
+                f.write_fmt(format_args!("{}: {reason}", path.display()))
+            }
+        }
+    }
+}
+
+impl std::error::Error for WaveScheduleError {}
+
+#[derive(Default)]
+pub struct WaveScheduleLoader;
+
+impl AssetLoader for WaveScheduleLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let schedule = WaveSchedule::parse(bytes, load_context.path())?;
+            load_context.set_default_asset(LoadedAsset::new(schedule));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[WAVE_SCHEDULE_EXTENSION]
+    }
+}
+
+/// All known schedules
+#[derive(Debug, Default, Clone, Resource)]
+pub struct WaveSchedules {
+    schedules: Vec<WaveSchedule>,
+    /// Used if nothing is loaded for the mode
+    fallback: WaveSchedule,
+}
+
+impl WaveSchedules {
+    /// Schedule made for the castle type,
+    /// otherwise the one for the whole mode
+    pub fn get(&self, mode: GameMode, castle_type: CastleType) -> &WaveSchedule {
+        self.schedules
+            .iter()
+            .find(|schedule| schedule.mode == mode && schedule.castle_type == Some(castle_type))
+            .or_else(|| {
+                self.schedules
+                    .iter()
+                    .find(|schedule| schedule.mode == mode && schedule.castle_type.is_none())
+            })
+            .unwrap_or(&self.fallback)
+    }
+
+    /// Adds the schedule replacing the one
+    /// for the same mode and castle type
+    pub fn insert(&mut self, schedule: WaveSchedule) {
+        match self.schedules.iter_mut().find(|existing| {
+            existing.mode == schedule.mode && existing.castle_type == schedule.castle_type
+        }) {
+            Some(existing) => *existing = schedule,
+            None => self.schedules.push(schedule),
+        }
+    }
+
+    /// Loads all schedules from the folder without the asset server.
+    /// Used by the headless simulation.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn load_from_dir(dir: &Path) -> Result<Self, WaveScheduleError> {
+        let mut schedules = Self::default();
+        let entries =
+            std::fs::read_dir(dir).map_err(|e| WaveScheduleError::Io(dir.to_path_buf(), e))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| WaveScheduleError::Io(dir.to_path_buf(), e))?
+                .path();
+            let is_schedule = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map_or(false, |name| {
+                    name.ends_with(&format!(".{WAVE_SCHEDULE_EXTENSION}"))
+                });
+            if !is_schedule {
+                continue;
+            }
+
+            let bytes = std::fs::read(&path).map_err(|e| WaveScheduleError::Io(path.clone(), e))?;
+            schedules.insert(WaveSchedule::parse(&bytes, &path)?);
+        }
+        Ok(schedules)
+    }
+}
+
+/// Copies loaded and changed schedules into `WaveSchedules`.
+/// Running games pick up changes on the next tick.
+fn update_schedules(
+    assets: Res<Assets<WaveSchedule>>,
+    mut events: EventReader<AssetEvent<WaveSchedule>>,
+    mut schedules: ResMut<WaveSchedules>,
+) {
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle } => {
+                if let Some(schedule) = assets.get(handle) {
+                    schedules.insert(schedule.clone());
+                }
+            }
+            AssetEvent::Modified { handle } => {
+                if let Some(schedule) = assets.get(handle) {
+                    info!(
+                        "reloaded {:?} schedule for {:?}",
+                        schedule.mode, schedule.castle_type
+                    );
+                    schedules.insert(schedule.clone());
+                }
+            }
+            AssetEvent::Removed { .. } => {}
+        }
+    }
+}
+
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_schedules_are_valid() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/schedules");
+        let schedules = match WaveSchedules::load_from_dir(&dir) {
+            Ok(schedules) => schedules,
+            Err(e) => panic!("{e}"),
+        };
+        // every mode needs a schedule used by all castles
+        for mode in [GameMode::Normal, GameMode::Infinite] {
+            assert!(
+                schedules
+                    .schedules
+                    .iter()
+                    .any(|schedule| schedule.mode == mode && schedule.castle_type.is_none()),
+                "no {mode:?} schedule"
+            );
+        }
+    }
+}
@@ -1,19 +1,33 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, time::Duration};
 
 use bevy::prelude::*;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::{game::GameState, utils::remove_all_with, GlobalState};
+use crate::{
+    game::{
+        castle_type::CastleType, mode::GameMode, rng::SideRng, simulation::SimulationSet, SideId,
+    },
+    utils::remove_all_with,
+    GlobalState,
+};
 
 use super::{
-    Bat, EnemyBundle, EnemyMarker, EnemySprites, EnemyType, GlobalEnemyBuffs, Goblin, MadCrab,
-    PoisonIvy, Side, Skull, SpawnState, SpearGoblin,
+    boss::{Boss, SummonEvent},
+    definition::{EnemyDefinition, EnemyDefinitions},
+    elite::EliteComponents,
+    pressure::{update_pressure, PressureState},
+    scaling::EnemyScaling,
+    schedule::{EliteSettings, WaveSchedule, WaveSchedules},
+    EnemyBundle, EnemyMarker, GlobalEnemyBuffs, Side,
 };
 
 const DEFAULT_ENEMY_SPAWN_POSITON: f32 = 1000.0;
 
 const DEFAULT_ENEMY_SPAWN_RADIUS: f32 = 200.0;
-const DEFAULT_ENEMY_SPAWN_RATE: f32 = 10.0;
+
+/// Radius of the circle summoned enemies appear on
+const SUMMON_RADIUS: f32 = 150.0;
 
 #[derive(Default)]
 pub struct SpawnPlugin<S: Side> {
@@ -23,43 +37,16 @@ pub struct SpawnPlugin<S: Side> {
 impl<S: Side> Plugin for SpawnPlugin<S> {
     fn build(&self, app: &mut App) {
         app.add_system(setup::<S>.in_schedule(OnEnter(GlobalState::InGame)))
-            .add_systems(
-                (enemy_spawn::<S, Bat>, enemy_spawn::<S, Goblin>)
-                    .in_set(OnUpdate(SpawnState::Stage1))
-                    .in_set(OnUpdate(GameState::InGame)),
-            )
-            .add_systems(
-                (
-                    enemy_spawn::<S, Bat>,
-                    enemy_spawn::<S, Goblin>,
-                    enemy_spawn::<S, SpearGoblin>,
-                    enemy_spawn::<S, Skull>,
-                )
-                    .in_set(OnUpdate(SpawnState::Stage2))
-                    .in_set(OnUpdate(GameState::InGame)),
-            )
-            .add_systems(
-                (
-                    enemy_spawn::<S, Bat>,
-                    enemy_spawn::<S, Goblin>,
-                    enemy_spawn::<S, SpearGoblin>,
-                    enemy_spawn::<S, Skull>,
-                    enemy_spawn::<S, PoisonIvy>,
-                )
-                    .in_set(OnUpdate(SpawnState::Stage3))
-                    .in_set(OnUpdate(GameState::InGame)),
+            .add_system(
+                enemy_spawn::<S>
+                    .in_set(SimulationSet::Progress)
+                    .after(update_pressure)
+                    .in_schedule(CoreSchedule::FixedUpdate),
             )
-            .add_systems(
-                (
-                    enemy_spawn::<S, Bat>,
-                    enemy_spawn::<S, Goblin>,
-                    enemy_spawn::<S, SpearGoblin>,
-                    enemy_spawn::<S, Skull>,
-                    enemy_spawn::<S, PoisonIvy>,
-                    enemy_spawn::<S, MadCrab>,
-                )
-                    .in_set(OnUpdate(SpawnState::Stage4))
-                    .in_set(OnUpdate(GameState::InGame)),
+            .add_system(
+                summon_enemies::<S>
+                    .in_set(SimulationSet::Resolve)
+                    .in_schedule(CoreSchedule::FixedUpdate),
             )
             .add_system(remove_all_with::<EnemyMarker>.in_schedule(OnEnter(GlobalState::MainMenu)))
             .add_system(
@@ -71,13 +58,15 @@ impl<S: Side> Plugin for SpawnPlugin<S> {
 #[derive(Debug, Default, Component)]
 pub struct EnemySpawnMarker;
 
-#[derive(Debug, Default, Resource)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
+#[serde(bound = "")]
 pub struct EnemyBuffs<S: Side> {
     pub health: f32,
     pub speed: f32,
     pub exp: f32,
     pub damage: f32,
     pub attack_speed: f32,
+    #[serde(skip)]
     _phantom: PhantomData<S>,
 }
 
@@ -108,80 +97,216 @@ impl<S: Side> EnemyBuffs<S> {
     }
 }
 
-#[derive(Debug, Component)]
-pub struct EnemySpawn<S: Side, E: EnemyType<S>> {
-    pub number: u32,
+/// Progress of the wave schedule on one side
+#[derive(Debug, Clone, Serialize, Deserialize, Component)]
+#[serde(bound = "")]
+pub struct EnemySpawn<S: Side> {
     pub radius: f32,
-    pub timer: Timer,
+    /// Seconds since the start of the run
+    pub time: f32,
+    /// Timers of the schedule waves in the schedule order
+    pub wave_timers: Vec<Timer>,
+    /// Number of times each schedule burst happened
+    pub bursts: Vec<u32>,
+    #[serde(skip)]
     _phantom: PhantomData<S>,
-    _phantom2: PhantomData<E>,
 }
 
-impl<S: Side, E: EnemyType<S>> Default for EnemySpawn<S, E> {
+impl<S: Side> Default for EnemySpawn<S> {
     fn default() -> Self {
         Self {
-            number: E::NUMBER_PER_SPAWN,
             radius: DEFAULT_ENEMY_SPAWN_RADIUS,
-            timer: Timer::from_seconds(DEFAULT_ENEMY_SPAWN_RATE, TimerMode::Repeating),
+            time: 0.0,
+            wave_timers: vec![],
+            bursts: vec![],
             _phantom: PhantomData,
-            _phantom2: PhantomData,
         }
     }
 }
 
+impl<S: Side> EnemySpawn<S> {
+    /// Matches timers to the schedule,
+    /// which may change during the run when files are reloaded
+    fn sync(&mut self, schedule: &WaveSchedule) {
+        self.wave_timers.truncate(schedule.waves.len());
+        for wave in schedule.waves.iter().skip(self.wave_timers.len()) {
+            self.wave_timers
+                .push(Timer::from_seconds(wave.interval, TimerMode::Repeating));
+        }
+        self.bursts.resize(schedule.bursts.len(), 0);
+    }
+}
+
 #[derive(Default, Bundle)]
-pub struct EnemySpawnBundle<S: Side, E: EnemyType<S>> {
-    spawn: EnemySpawn<S, E>,
+pub struct EnemySpawnBundle<S: Side> {
+    spawn: EnemySpawn<S>,
     marker: EnemySpawnMarker,
 }
 
-/// Sets up 4 spawns at each side of the screen
+/// Sets up the spawn at the side of the screen
 fn setup<S: Side>(mut commands: Commands) {
     commands.insert_resource(EnemyBuffs::<S>::default());
 
-    // North
     commands
         .spawn(TransformBundle::from_transform(
             Transform::from_translation((S::DIRECTION * DEFAULT_ENEMY_SPAWN_POSITON).extend(0.0)),
         ))
-        .insert(EnemySpawnBundle::<S, Bat>::default())
-        .insert(EnemySpawnBundle::<S, Goblin>::default())
-        .insert(EnemySpawnBundle::<S, SpearGoblin>::default())
-        .insert(EnemySpawnBundle::<S, Skull>::default())
-        .insert(EnemySpawnBundle::<S, PoisonIvy>::default())
-        .insert(EnemySpawnBundle::<S, MadCrab>::default());
+        .insert(EnemySpawnBundle::<S>::default());
 }
 
-/// Spawns enemies in a circle arond the spawn point equally spread
-/// on a circle
-fn enemy_spawn<S: Side, E: EnemyType<S>>(
-    time: Res<Time>,
-    enemy_sprites: Res<EnemySprites>,
+/// Spawns enemies of the waves and bursts of the schedule
+/// in a circle arond the spawn point equally spread.
+/// Some of them are promoted to elites.
+fn enemy_spawn<S: Side>(
+    fixed_time: Res<FixedTime>,
+    game_mode: Res<GameMode>,
+    castle_type: Res<CastleType>,
+    schedules: Res<WaveSchedules>,
+    enemy_definitions: Res<EnemyDefinitions>,
     global_buffs: Res<GlobalEnemyBuffs>,
     buffs: Res<EnemyBuffs<S>>,
+    scaling: Res<EnemyScaling>,
+    pressure: Res<PressureState>,
+    mut rng: ResMut<SideRng<S>>,
     mut commands: Commands,
-    mut spawns: Query<(&Transform, &mut EnemySpawn<S, E>)>,
+    mut spawns: Query<(&Transform, &mut EnemySpawn<S>)>,
 ) {
+    let schedule = schedules.get(*game_mode, *castle_type);
+    let delta = fixed_time
+        .period
+        .mul_f32((1.0 + scaling.spawn_rate) * pressure.multiplier(SideId::of::<S>()));
     for (transform, mut spawn) in spawns.iter_mut() {
-        if !spawn.timer.tick(time.delta()).finished() {
-            continue;
+        spawn.sync(schedule);
+        spawn.time += fixed_time.period.as_secs_f32();
+        let time = spawn.time;
+        let elites = schedule
+            .elites
+            .as_ref()
+            .map(|settings| (settings, settings.chance_at(time)));
+
+        let mut groups = vec![];
+        for (wave, timer) in schedule.waves.iter().zip(spawn.wave_timers.iter_mut()) {
+            if !wave.is_active::<S>(time) {
+                continue;
+            }
+            timer.set_duration(Duration::from_secs_f32(wave.interval_at(time)));
+            if !timer.tick(delta).finished() {
+                continue;
+            }
+            if let Some(definition) = enemy_definitions.get(wave.enemy) {
+                groups.push((
+                    definition,
+                    wave.number_at(time, definition.number_per_spawn),
+                ));
+            }
+        }
+        for (burst, spawned) in schedule.bursts.iter().zip(spawn.bursts.iter_mut()) {
+            if !burst.is_due::<S>(time, *spawned) {
+                continue;
+            }
+            *spawned += 1;
+            if let Some(definition) = enemy_definitions.get(burst.enemy) {
+                groups.push((definition, burst.number));
+            }
         }
 
-        for n in 0..spawn.number {
-            let position = transform.translation
-                + Quat::from_rotation_z(
-                    (2.0 * std::f32::consts::PI / spawn.number as f32) * n as f32
-                        + rand::thread_rng().gen_range(0.0..std::f32::consts::FRAC_PI_6),
-                )
-                .mul_vec3(Vec3::Y * spawn.radius);
-
-            commands.spawn(EnemyBundle::<S, E>::new(
-                E::SIZE,
-                E::texture_atlas(&enemy_sprites),
-                position,
+        for (definition, number) in groups {
+            spawn_group(
+                &mut commands,
+                &mut rng.spawn,
+                definition,
+                transform.translation,
+                spawn.radius,
+                number,
+                elites,
                 &global_buffs,
                 &buffs,
-            ));
+                &scaling,
+            );
+        }
+    }
+}
+
+/// Spawns enemies requested by bosses around them
+fn summon_enemies<S: Side>(
+    enemy_definitions: Res<EnemyDefinitions>,
+    global_buffs: Res<GlobalEnemyBuffs>,
+    buffs: Res<EnemyBuffs<S>>,
+    scaling: Res<EnemyScaling>,
+    mut rng: ResMut<SideRng<S>>,
+    mut commands: Commands,
+    mut events: EventReader<SummonEvent<S>>,
+) {
+    for event in events.iter() {
+        if let Some(definition) = enemy_definitions.get(event.enemy) {
+            spawn_group(
+                &mut commands,
+                &mut rng.spawn,
+                definition,
+                event.position,
+                SUMMON_RADIUS,
+                event.number,
+                None,
+                &global_buffs,
+                &buffs,
+                &scaling,
+            );
+        }
+    }
+}
+
+/// Spawns `number` enemies equally spread in a circle around the `center`.
+/// With `elites` settings and chance some of them become elites, bosses never do.
+fn spawn_group<S: Side>(
+    commands: &mut Commands,
+    rng: &mut impl Rng,
+    definition: &EnemyDefinition,
+    center: Vec3,
+    radius: f32,
+    number: u32,
+    elites: Option<(&EliteSettings, f32)>,
+    global_buffs: &GlobalEnemyBuffs,
+    buffs: &EnemyBuffs<S>,
+    scaling: &EnemyScaling,
+) {
+    for n in 0..number {
+        let position = center
+            + Quat::from_rotation_z(
+                (2.0 * std::f32::consts::PI / number as f32) * n as f32
+                    + rng.gen_range(0.0..std::f32::consts::FRAC_PI_6),
+            )
+            .mul_vec3(Vec3::Y * radius);
+
+        let mut enemy = definition.enemy::<S>(global_buffs, buffs, scaling);
+        let mut attack = definition.attack::<S>(global_buffs, buffs, scaling);
+        let elite = match elites {
+            Some((settings, chance))
+                if definition.boss.is_none()
+                    && 0.0 < chance
+                    && rng.gen_range(0.0..1.0) < chance =>
+            {
+                Some(EliteComponents::roll(
+                    rng,
+                    settings,
+                    &mut enemy,
+                    &mut attack,
+                ))
+            }
+            _ => None,
+        };
+        let boss = definition
+            .boss
+            .as_ref()
+            .map(|boss| Boss::new(boss, enemy.health, enemy.speed));
+
+        let mut entity = commands.spawn(EnemyBundle::<S>::from_enemy(
+            definition, position, enemy, attack,
+        ));
+        if let Some(boss) = boss {
+            entity.insert(boss);
+        }
+        if let Some(elite) = elite {
+            elite.insert(&mut entity);
         }
     }
 }
@@ -0,0 +1,245 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{utils::remove_all_with, GlobalState};
+
+use super::{
+    castle::{Castle, CastleWall},
+    enemies::{
+        boss::Boss, definition::EnemyDefinitions, elite::Elite, enemy_death, Enemy, EnemyKind,
+    },
+    rng::SideRng,
+    simulation::{simulation_tick, SimulationSet},
+    East, North, Side, South, West,
+};
+
+/// Seconds pickups lay on the ground before they are collected
+const COLLECT_DELAY: f32 = 1.5;
+/// Elites drop this many times more gold
+const ELITE_GOLD_MULTIPLIER: u32 = 5;
+const HEAL_ORB_CHANCE: f32 = 0.03;
+/// Part of the wall max health heal orbs restore
+const HEAL_ORB_PART: f32 = 0.05;
+const DAMAGE_BOOST_CHANCE: f32 = 0.01;
+/// Damage added to all weapons while the boost lasts
+const DAMAGE_BOOST: f32 = 0.5;
+const DAMAGE_BOOST_DURATION: f32 = 10.0;
+
+const PICKUP_SIZE: f32 = 10.0;
+const GOLD_COLOR: Color = Color::rgb(1.0, 0.85, 0.2);
+const HEAL_ORB_COLOR: Color = Color::rgb(0.3, 1.0, 0.4);
+const DAMAGE_BOOST_COLOR: Color = Color::rgb(1.0, 0.3, 0.3);
+
+pub struct LootPlugin;
+
+impl Plugin for LootPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(GlobalState::InGame)))
+            .add_systems(
+                (
+                    damage_boost_update,
+                    collect_pickups::<North>,
+                    collect_pickups::<South>,
+                    collect_pickups::<West>,
+                    collect_pickups::<East>,
+                )
+                    .after(simulation_tick)
+                    .in_set(SimulationSet::Progress)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (
+                    // despawned enemies can not be read anymore
+                    drop_loot::<North>.before(enemy_death::<North>),
+                    drop_loot::<South>.before(enemy_death::<South>),
+                    drop_loot::<West>.before(enemy_death::<West>),
+                    drop_loot::<East>.before(enemy_death::<East>),
+                )
+                    .in_set(SimulationSet::Death)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(remove_all_with::<PickupMarker>.in_schedule(OnExit(GlobalState::InGame)));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PickupKind {
+    Gold(u32),
+    /// Heals the wall of the side
+    HealOrb,
+    /// Increases damage of all weapons for a while
+    DamageBoost,
+}
+
+impl PickupKind {
+    fn color(&self) -> Color {
+        match self {
+            PickupKind::Gold(_) => GOLD_COLOR,
+            PickupKind::HealOrb => HEAL_ORB_COLOR,
+            PickupKind::DamageBoost => DAMAGE_BOOST_COLOR,
+        }
+    }
+}
+
+/// Loot dropped by an enemy of the side
+#[derive(Debug, Clone, Serialize, Deserialize, Component)]
+#[serde(bound = "")]
+pub struct Pickup<S: Side> {
+    pub kind: PickupKind,
+    /// Seconds until the pickup is collected
+    delay: f32,
+    #[serde(skip)]
+    _phantom: PhantomData<S>,
+}
+
+impl<S: Side> Pickup<S> {
+    pub fn new(kind: PickupKind) -> Self {
+        Self {
+            kind,
+            delay: COLLECT_DELAY,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct PickupMarker;
+
+#[derive(Bundle)]
+pub struct PickupBundle<S: Side> {
+    #[bundle]
+    sprite: SpriteBundle,
+    pickup: Pickup<S>,
+    marker: PickupMarker,
+}
+
+impl<S: Side> PickupBundle<S> {
+    pub fn new(pickup: Pickup<S>, position: Vec3) -> Self {
+        Self {
+            sprite: SpriteBundle {
+                sprite: Sprite {
+                    color: pickup.kind.color(),
+                    custom_size: Some(Vec2::splat(PICKUP_SIZE)),
+                    ..default()
+                },
+                transform: Transform::from_translation(position),
+                ..default()
+            },
+            pickup,
+            marker: PickupMarker,
+        }
+    }
+}
+
+/// Temporary damage boost from the pickups
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
+pub struct DamageBoost {
+    /// Seconds until the boost ends
+    pub remaining: f32,
+}
+
+impl DamageBoost {
+    /// Damage added to weapons, 0 if the boost is not active
+    pub fn damage(&self) -> f32 {
+        if 0.0 < self.remaining {
+            DAMAGE_BOOST
+        } else {
+            0.0
+        }
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.insert_resource(DamageBoost::default());
+}
+
+fn damage_boost_update(fixed_time: Res<FixedTime>, mut boost: ResMut<DamageBoost>) {
+    if 0.0 < boost.remaining {
+        boost.remaining = (boost.remaining - fixed_time.period.as_secs_f32()).max(0.0);
+    }
+}
+
+/// Dead enemies drop gold and sometimes
+/// a heal orb or a damage boost
+fn drop_loot<S: Side>(
+    enemy_definitions: Res<EnemyDefinitions>,
+    enemies: Query<(&Transform, &Enemy<S>, Option<&Elite>, Option<&Boss>)>,
+    mut rng: ResMut<SideRng<S>>,
+    mut commands: Commands,
+) {
+    for (transform, enemy, elite, boss) in enemies.iter() {
+        if 0 < enemy.health {
+            continue;
+        }
+        let position = transform.translation.truncate().extend(1.0);
+
+        let gold = gold_dropped(&enemy_definitions, enemy.kind, elite.is_some());
+        if 0 < gold {
+            commands.spawn(PickupBundle::<S>::new(
+                Pickup::new(PickupKind::Gold(gold)),
+                position,
+            ));
+        }
+
+        // both chances are always rolled, so the stream
+        // advances the same way for every enemy
+        let heal_orb = rng.loot.gen_range(0.0..1.0) < HEAL_ORB_CHANCE;
+        let damage_boost = rng.loot.gen_range(0.0..1.0) < DAMAGE_BOOST_CHANCE;
+        // bosses always leave a heal orb behind
+        if heal_orb || boss.is_some() {
+            commands.spawn(PickupBundle::<S>::new(
+                Pickup::new(PickupKind::HealOrb),
+                position + Vec3::new(PICKUP_SIZE, 0.0, 0.0),
+            ));
+        }
+        if damage_boost {
+            commands.spawn(PickupBundle::<S>::new(
+                Pickup::new(PickupKind::DamageBoost),
+                position - Vec3::new(PICKUP_SIZE, 0.0, 0.0),
+            ));
+        }
+    }
+}
+
+fn gold_dropped(enemy_definitions: &EnemyDefinitions, kind: EnemyKind, elite: bool) -> u32 {
+    let gold = enemy_definitions
+        .get(kind)
+        .map_or(0, |definition| definition.gold);
+    if elite {
+        gold * ELITE_GOLD_MULTIPLIER
+    } else {
+        gold
+    }
+}
+
+/// Collects pickups once their delay ends
+fn collect_pickups<S: Side>(
+    fixed_time: Res<FixedTime>,
+    mut boost: ResMut<DamageBoost>,
+    mut castle: Query<&mut Castle>,
+    mut wall: Query<&mut CastleWall<S>>,
+    mut pickups: Query<(Entity, &mut Pickup<S>)>,
+    mut commands: Commands,
+) {
+    let delta = fixed_time.period.as_secs_f32();
+    let mut castle = castle.single_mut();
+    let mut wall = wall.single_mut();
+    for (entity, mut pickup) in pickups.iter_mut() {
+        pickup.delay -= delta;
+        if 0.0 < pickup.delay {
+            continue;
+        }
+        match pickup.kind {
+            PickupKind::Gold(gold) => castle.gold += gold,
+            PickupKind::HealOrb => {
+                let heal = (wall.max_health as f32 * HEAL_ORB_PART) as i32;
+                wall.heal(heal);
+            }
+            PickupKind::DamageBoost => boost.remaining = DAMAGE_BOOST_DURATION,
+        }
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,17 +1,26 @@
 use core::fmt::Debug;
 
 use bevy::prelude::*;
-use bevy_kira_audio::prelude::*;
-use bevy_kira_audio::Audio;
 use bevy_rapier2d::prelude::RapierConfiguration;
+use serde::{Deserialize, Serialize};
 
-use crate::GameSettings;
-use crate::{impl_into_state, utils::set_state, GameAssets, GlobalState, IntoState};
+use crate::{impl_into_state, utils::set_state, GlobalState, IntoState};
 
+pub mod abilities;
 pub mod animation;
 pub mod castle;
+pub mod castle_type;
 pub mod damage;
 pub mod enemies;
+pub mod loot;
+pub mod mode;
+pub mod replay;
+pub mod rng;
+pub mod save;
+pub mod shop;
+pub mod simulation;
+pub mod stats;
+pub mod status;
 pub mod upgrades;
 pub mod weapons;
 
@@ -28,17 +37,16 @@ impl Plugin for GamePlugin {
                 set_state::<GameState, { GameState::NotInGame as u8 }>
                     .in_schedule(OnExit(GlobalState::InGame)),
             )
-            .add_system(play_main_menu_audio.in_schedule(OnEnter(GlobalState::MainMenu)))
-            .add_system(stop_audio.in_schedule(OnExit(GlobalState::MainMenu)))
-            .add_system(play_in_game_audio.in_schedule(OnEnter(GlobalState::InGame)))
-            .add_system(stop_audio.in_schedule(OnExit(GlobalState::InGame)))
-            .add_system(in_game_key_input.in_set(OnUpdate(GameState::InGame)))
             .add_system(stop_physics.in_schedule(OnEnter(GameState::Paused)))
             .add_system(resume_physics.in_schedule(OnExit(GameState::Paused)))
             .add_system(stop_physics.in_schedule(OnEnter(GameState::GameOver)))
             .add_system(resume_physics.in_schedule(OnExit(GameState::GameOver)))
+            .add_system(stop_physics.in_schedule(OnEnter(GameState::Victory)))
+            .add_system(resume_physics.in_schedule(OnExit(GameState::Victory)))
             .add_system(stop_physics.in_schedule(OnEnter(GameState::LevelUp)))
             .add_system(resume_physics.in_schedule(OnExit(GameState::LevelUp)))
+            .add_system(stop_physics.in_schedule(OnEnter(GameState::Shop)))
+            .add_system(resume_physics.in_schedule(OnExit(GameState::Shop)))
             .add_system(stop_physics.in_schedule(OnEnter(GameState::StatsNorth)))
             .add_system(resume_physics.in_schedule(OnExit(GameState::StatsNorth)))
             .add_system(stop_physics.in_schedule(OnEnter(GameState::StatsSouth)))
@@ -47,10 +55,19 @@ impl Plugin for GamePlugin {
             .add_system(resume_physics.in_schedule(OnExit(GameState::StatsWest)))
             .add_system(stop_physics.in_schedule(OnEnter(GameState::StatsEast)))
             .add_system(resume_physics.in_schedule(OnExit(GameState::StatsEast)))
+            .add_plugin(abilities::AbilitiesPlugin)
             .add_plugin(animation::AnimationPlugin)
             .add_plugin(castle::CastlePlugin)
             .add_plugin(damage::DamagePlugin)
             .add_plugin(enemies::EnemyPlugin)
+            .add_plugin(loot::LootPlugin)
+            .add_plugin(mode::ModePlugin)
+            .add_plugin(rng::RngPlugin)
+            .add_plugin(save::SavePlugin)
+            .add_plugin(shop::ShopPlugin)
+            .add_plugin(simulation::SimulationPlugin)
+            .add_plugin(stats::RunStatsPlugin)
+            .add_plugin(status::StatusPlugin)
             .add_plugin(weapons::WeaponsPlugin)
             .add_plugin(upgrades::UpgradesPlugin);
     }
@@ -71,7 +88,10 @@ pub enum GameState {
     InGame,
     Paused,
     GameOver,
+    Victory,
     LevelUp,
+    /// Gold is spent between the stages
+    Shop,
     StatsNorth,
     StatsSouth,
     StatsWest,
@@ -91,51 +111,55 @@ pub struct West;
 #[derive(Debug, Default, Clone, Copy)]
 pub struct East;
 
+/// Side as a value, for data files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SideId {
+    North,
+    South,
+    West,
+    East,
+}
+
+impl SideId {
+    /// Ordered by `Side::INDEX`
+    pub const ALL: [SideId; 4] = [SideId::North, SideId::South, SideId::West, SideId::East];
+
+    pub fn of<S: Side>() -> Self {
+        Self::ALL[S::INDEX]
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            SideId::North => SideId::South,
+            SideId::South => SideId::North,
+            SideId::West => SideId::East,
+            SideId::East => SideId::West,
+        }
+    }
+}
+
 pub trait Side: Debug + Default + Clone + Copy + Send + Sync + 'static {
     const DIRECTION: Vec2;
+    const INDEX: usize;
 }
 
 impl Side for North {
     const DIRECTION: Vec2 = Vec2::Y;
+    const INDEX: usize = 0;
 }
 impl Side for South {
     const DIRECTION: Vec2 = Vec2::NEG_Y;
+    const INDEX: usize = 1;
 }
 impl Side for West {
     const DIRECTION: Vec2 = Vec2::NEG_X;
+    const INDEX: usize = 2;
 }
 impl Side for East {
     const DIRECTION: Vec2 = Vec2::X;
-}
-
-fn play_in_game_audio(
-    audio: Res<Audio>,
-    game_settings: Res<GameSettings>,
-    game_assets: Res<GameAssets>,
-) {
-    audio
-        .play(game_assets.background.clone())
-        .with_volume(game_settings.sound_volume)
-        .looped();
-}
-
-fn play_main_menu_audio(
-    audio: Res<Audio>,
-    game_settings: Res<GameSettings>,
-    game_assets: Res<GameAssets>,
-) {
-    audio
-        .play(game_assets.main_menu.clone())
-        .with_volume(game_settings.sound_volume)
-        .looped();
-}
-
-fn stop_audio(audio: Res<Audio>) {
-    audio.stop();
-}
-
-fn in_game_key_input(keyboard: Res<Input<KeyCode>>, mut game_state: ResMut<NextState<GameState>>) {
-    if keyboard.pressed(KeyCode::Escape) {
-        game_state.set(GameState::Paused);
-    }
+    const INDEX: usize = 3;
 }
@@ -0,0 +1,83 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::GlobalState;
+
+use super::{
+    simulation::{ResolveSet, SimulationSet},
+    GameState,
+};
+
+/// Time in seconds castle needs to survive in normal mode
+const NORMAL_MODE_DURATION: f32 = 60.0 * 30.0;
+
+pub struct ModePlugin;
+
+impl Plugin for ModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .add_system(setup.in_schedule(OnEnter(GlobalState::InGame)))
+            .add_system(
+                run_timer_update
+                    .in_set(SimulationSet::Progress)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(
+                check_victory
+                    .in_set(ResolveSet::Victory)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            );
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Resource)]
+pub enum GameMode {
+    /// Hold the castle for 30 minutes to win
+    #[default]
+    Normal,
+    /// Hold for as long as possible while enemies
+    /// keep getting stronger
+    Infinite,
+}
+
+impl GameMode {
+    /// Time in seconds after which the run is won.
+    /// `None` if mode can not be won.
+    pub fn victory_time(&self) -> Option<f32> {
+        match self {
+            GameMode::Normal => Some(NORMAL_MODE_DURATION),
+            GameMode::Infinite => None,
+        }
+    }
+}
+
+/// Time passed since the start of the run.
+/// Only advances while the game is not paused.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
+pub struct RunTimer {
+    pub time_passed: f32,
+}
+
+fn setup(mut commands: Commands) {
+    commands.insert_resource(RunTimer::default());
+}
+
+fn run_timer_update(fixed_time: Res<FixedTime>, mut run_timer: ResMut<RunTimer>) {
+    run_timer.time_passed += fixed_time.period.as_secs_f32();
+}
+
+fn check_victory(
+    game_mode: Res<GameMode>,
+    run_timer: Res<RunTimer>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    // castle fell on the same tick
+    if game_state.0.is_some() {
+        return;
+    }
+    if let Some(victory_time) = game_mode.victory_time() {
+        if victory_time <= run_timer.time_passed {
+            game_state.set(GameState::Victory);
+        }
+    }
+}
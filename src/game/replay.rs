@@ -0,0 +1,397 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    storage::{self, VersionedFile},
+    GlobalState,
+};
+
+use super::{
+    abilities::{use_abilities, AbilityRequest, AbilityRequests},
+    castle_type::CastleType,
+    mode::GameMode,
+    rng::{self, GameRng, RequestedSeed},
+    save::LoadedRun,
+    shop::{ShopEvent, ShopSet},
+    simulation::{simulation_tick, SimulationSet, SimulationTick},
+    upgrades::apply::{ApplyUpgradeEvent, UpgradeSet},
+    East, GameState, North, Side, South, West,
+};
+
+/// Version of the replay format.
+/// Replays with other versions are not played.
+pub const REPLAY_VERSION: u32 = 14;
+
+const LAST_REPLAY: &str = "replays/last.ron";
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            setup
+                .after(rng::setup)
+                .run_if(not(resource_exists::<ReplayPlayback>()))
+                // continued runs can not be replayed from the start
+                .run_if(not(resource_exists::<LoadedRun>()))
+                .in_schedule(OnEnter(GlobalState::InGame)),
+        )
+        .add_system(
+            setup_playback
+                .after(rng::setup)
+                .run_if(resource_exists::<ReplayPlayback>())
+                .in_schedule(OnEnter(GlobalState::InGame)),
+        )
+        .add_system(record_state_changes.run_if(resource_exists::<ReplayRecorder>()))
+        .add_system(
+            record_upgrades
+                .run_if(resource_exists::<ReplayRecorder>())
+                .in_set(UpgradeSet::Dispatch)
+                .in_set(OnUpdate(GameState::LevelUp)),
+        )
+        .add_system(
+            record_shop
+                .run_if(resource_exists::<ReplayRecorder>())
+                .in_set(ShopSet)
+                .in_set(OnUpdate(GameState::Shop)),
+        )
+        .add_system(
+            record_abilities
+                .run_if(resource_exists::<ReplayRecorder>())
+                .after(simulation_tick)
+                .before(use_abilities)
+                .in_set(SimulationSet::Progress)
+                .in_schedule(CoreSchedule::FixedUpdate),
+        )
+        .add_system(
+            save_replay
+                .run_if(resource_exists::<ReplayRecorder>())
+                .in_schedule(OnEnter(GameState::GameOver)),
+        )
+        .add_system(
+            save_replay
+                .run_if(resource_exists::<ReplayRecorder>())
+                .in_schedule(OnEnter(GameState::Victory)),
+        )
+        .add_system(
+            playback_upgrades
+                .run_if(resource_exists::<ReplayPlayback>())
+                .before(UpgradeSet::Dispatch)
+                .in_set(OnUpdate(GameState::LevelUp)),
+        )
+        .add_system(
+            playback_shop
+                .run_if(resource_exists::<ReplayPlayback>())
+                .before(ShopSet)
+                .in_set(OnUpdate(GameState::Shop)),
+        )
+        .add_system(
+            playback_abilities
+                .run_if(resource_exists::<ReplayPlayback>())
+                .after(simulation_tick)
+                .before(use_abilities)
+                .in_set(SimulationSet::Progress)
+                .in_schedule(CoreSchedule::FixedUpdate),
+        )
+        .add_system(cleanup.in_schedule(OnExit(GlobalState::InGame)));
+    }
+}
+
+/// Everything needed to reproduce a run:
+/// the seed, the mode, the castle and every player decision
+/// with the simulation tick it was made at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    /// Seed was chosen by the player
+    pub seeded: bool,
+    pub mode: GameMode,
+    pub castle_type: CastleType,
+    pub inputs: Vec<ReplayEntry>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, seeded: bool, mode: GameMode, castle_type: CastleType) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            seed,
+            seeded,
+            mode,
+            castle_type,
+            inputs: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ReplayEntry {
+    pub tick: u64,
+    pub input: ReplayInput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReplayInput {
+    Pause,
+    /// Stats panel of the side with `Side::INDEX` was opened
+    Stats(usize),
+    Resume,
+    /// Index of the chosen upgrade
+    Upgrade(usize),
+    Ability(AbilityRequest),
+    /// Purchase in the shop or leaving it
+    Shop(ShopEvent),
+}
+
+impl VersionedFile for Replay {
+    const NAME: &'static str = LAST_REPLAY;
+    const VERSION: u32 = REPLAY_VERSION;
+}
+
+/// Replay of the current run
+#[derive(Debug, Resource)]
+pub struct ReplayRecorder(pub Replay);
+
+/// Replay being played.
+/// Only upgrades, shop visits and abilities are re-driven, pauses
+/// and stats panels do not affect the simulation.
+#[derive(Debug, Resource)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    next_input: usize,
+    next_ability: usize,
+    /// Seed player requested before the replay started
+    requested_seed: Option<u64>,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay, requested_seed: Option<u64>) -> Self {
+        Self {
+            replay,
+            next_input: 0,
+            next_ability: 0,
+            requested_seed,
+        }
+    }
+
+    /// Returns next recorded upgrade
+    fn next_upgrade(&mut self) -> Option<ReplayEntry> {
+        while let Some(entry) = self.replay.inputs.get(self.next_input) {
+            self.next_input += 1;
+            if let ReplayInput::Upgrade(_) = entry.input {
+                return Some(*entry);
+            }
+        }
+        None
+    }
+
+    /// Returns next recorded shop decision
+    fn next_shop_event(&mut self) -> Option<ReplayEntry> {
+        while let Some(entry) = self.replay.inputs.get(self.next_input) {
+            self.next_input += 1;
+            if let ReplayInput::Shop(_) = entry.input {
+                return Some(*entry);
+            }
+        }
+        None
+    }
+
+    /// Returns abilities recorded up to the `tick`
+    fn next_abilities(&mut self, tick: u64) -> Vec<AbilityRequest> {
+        let mut abilities = Vec::new();
+        while let Some(entry) = self.replay.inputs.get(self.next_ability) {
+            if tick < entry.tick {
+                break;
+            }
+            self.next_ability += 1;
+            if let ReplayInput::Ability(request) = entry.input {
+                abilities.push(request);
+            }
+        }
+        abilities
+    }
+}
+
+/// Loads replay of the last finished run
+pub fn load_last_replay() -> Option<Replay> {
+    storage::load_versioned::<Replay>()
+}
+
+fn setup(
+    game_rng: Res<GameRng>,
+    game_mode: Res<GameMode>,
+    castle_type: Res<CastleType>,
+    mut commands: Commands,
+) {
+    commands.insert_resource(ReplayRecorder(Replay::new(
+        game_rng.seed,
+        game_rng.requested,
+        *game_mode,
+        *castle_type,
+    )));
+}
+
+/// Replays are started with their seed requested,
+/// the run keeps whether the recorded one was seeded
+fn setup_playback(playback: Res<ReplayPlayback>, mut game_rng: ResMut<GameRng>) {
+    game_rng.requested = playback.replay.seeded;
+}
+
+fn record_state_changes(
+    tick: Res<SimulationTick>,
+    game_state: Res<State<GameState>>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    if !game_state.is_changed() {
+        return;
+    }
+
+    let input = match game_state.0 {
+        GameState::Paused => ReplayInput::Pause,
+        GameState::StatsNorth => ReplayInput::Stats(North::INDEX),
+        GameState::StatsSouth => ReplayInput::Stats(South::INDEX),
+        GameState::StatsWest => ReplayInput::Stats(West::INDEX),
+        GameState::StatsEast => ReplayInput::Stats(East::INDEX),
+        GameState::InGame => match recorder.0.inputs.last() {
+            Some(ReplayEntry {
+                input: ReplayInput::Pause | ReplayInput::Stats(_),
+                ..
+            }) => ReplayInput::Resume,
+            _ => return,
+        },
+        _ => return,
+    };
+    recorder.0.inputs.push(ReplayEntry {
+        tick: tick.0,
+        input,
+    });
+}
+
+fn record_upgrades(
+    tick: Res<SimulationTick>,
+    mut apply_upgrade_events: EventReader<ApplyUpgradeEvent>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    for event in apply_upgrade_events.iter() {
+        recorder.0.inputs.push(ReplayEntry {
+            tick: tick.0,
+            input: ReplayInput::Upgrade(*event as usize),
+        });
+    }
+}
+
+fn record_shop(
+    tick: Res<SimulationTick>,
+    mut shop_events: EventReader<ShopEvent>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    for event in shop_events.iter() {
+        recorder.0.inputs.push(ReplayEntry {
+            tick: tick.0,
+            input: ReplayInput::Shop(*event),
+        });
+    }
+}
+
+fn record_abilities(
+    tick: Res<SimulationTick>,
+    requests: Res<AbilityRequests>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    for request in requests.0.iter() {
+        recorder.0.inputs.push(ReplayEntry {
+            tick: tick.0,
+            input: ReplayInput::Ability(*request),
+        });
+    }
+}
+
+fn save_replay(recorder: Res<ReplayRecorder>) {
+    storage::save_versioned(&recorder.0);
+}
+
+/// Chooses recorded upgrade. If replay has no more
+/// upgrades the player takes control over the run.
+fn playback_upgrades(
+    tick: Res<SimulationTick>,
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    mut requested_seed: ResMut<RequestedSeed>,
+    mut apply_upgrade_event: EventWriter<ApplyUpgradeEvent>,
+) {
+    let event = match playback.next_upgrade() {
+        Some(ReplayEntry {
+            tick: upgrade_tick,
+            input: ReplayInput::Upgrade(index),
+        }) => {
+            if upgrade_tick != tick.0 {
+                warn!(
+                    "replay upgrade was recorded at tick {upgrade_tick} but played at tick {}",
+                    tick.0
+                );
+            }
+            ApplyUpgradeEvent::from_index(index)
+        }
+        _ => None,
+    };
+
+    match event {
+        Some(event) => apply_upgrade_event.send(event),
+        None => {
+            warn!("replay has no more upgrades");
+            requested_seed.0 = playback.requested_seed;
+            commands.remove_resource::<ReplayPlayback>();
+        }
+    }
+}
+
+/// Repeats recorded shop decisions one per frame. If replay
+/// has no more of them the player takes control over the run.
+fn playback_shop(
+    tick: Res<SimulationTick>,
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    mut requested_seed: ResMut<RequestedSeed>,
+    mut shop_events: EventWriter<ShopEvent>,
+) {
+    match playback.next_shop_event() {
+        Some(ReplayEntry {
+            tick: shop_tick,
+            input: ReplayInput::Shop(event),
+        }) => {
+            if shop_tick != tick.0 {
+                warn!(
+                    "replay shop decision was recorded at tick {shop_tick} but played at tick {}",
+                    tick.0
+                );
+            }
+            shop_events.send(event);
+        }
+        _ => {
+            warn!("replay has no more shop decisions");
+            requested_seed.0 = playback.requested_seed;
+            commands.remove_resource::<ReplayPlayback>();
+        }
+    }
+}
+
+fn playback_abilities(
+    tick: Res<SimulationTick>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut requests: ResMut<AbilityRequests>,
+) {
+    let abilities = playback.next_abilities(tick.0);
+    requests.0.extend(abilities);
+}
+
+fn cleanup(
+    playback: Option<Res<ReplayPlayback>>,
+    mut requested_seed: ResMut<RequestedSeed>,
+    mut commands: Commands,
+) {
+    if let Some(playback) = playback {
+        requested_seed.0 = playback.requested_seed;
+        commands.remove_resource::<ReplayPlayback>();
+    }
+    commands.remove_resource::<ReplayRecorder>();
+}
@@ -0,0 +1,116 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::GlobalState;
+
+use super::{East, North, Side, South, West};
+
+/// Stream used for upgrades generation
+const UPGRADES_STREAM: u64 = 0;
+/// Number of streams each side uses
+const SIDE_STREAMS: u64 = 5;
+/// Stream used for spawn pressure, after all side streams
+const PRESSURE_STREAM: u64 = UPGRADES_STREAM + 1 + 4 * SIDE_STREAMS;
+/// Stream used for shop offers
+const SHOP_STREAM: u64 = PRESSURE_STREAM + 1;
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RequestedSeed>()
+            .insert_resource(GameRng::new(0, false))
+            .add_system(setup.in_schedule(OnEnter(GlobalState::InGame)))
+            .add_systems(
+                (
+                    setup_side::<North>,
+                    setup_side::<South>,
+                    setup_side::<West>,
+                    setup_side::<East>,
+                )
+                    .after(setup)
+                    .in_schedule(OnEnter(GlobalState::InGame)),
+            );
+    }
+}
+
+/// Seed for the next run.
+/// If `None` random seed is used.
+#[derive(Debug, Default, Resource)]
+pub struct RequestedSeed(pub Option<u64>);
+
+/// Source of all randomness in the run.
+/// Everything that needs random numbers uses its own
+/// stream, so the run can be reproduced from the `seed`.
+#[derive(Debug, Clone, Serialize, Deserialize, Resource)]
+pub struct GameRng {
+    pub seed: u64,
+    /// Seed was chosen by the player
+    pub requested: bool,
+    pub upgrades: ChaCha8Rng,
+    pub pressure: ChaCha8Rng,
+    pub shop: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64, requested: bool) -> Self {
+        Self {
+            seed,
+            requested,
+            upgrades: stream(seed, UPGRADES_STREAM),
+            pressure: stream(seed, PRESSURE_STREAM),
+            shop: stream(seed, SHOP_STREAM),
+        }
+    }
+}
+
+/// Random streams used by a single side
+#[derive(Debug, Clone, Serialize, Deserialize, Resource)]
+#[serde(bound = "")]
+pub struct SideRng<S: Side> {
+    pub spawn: ChaCha8Rng,
+    pub crit: ChaCha8Rng,
+    pub weapons: ChaCha8Rng,
+    /// Chances of weapons to apply status effects
+    pub status: ChaCha8Rng,
+    /// Loot dropped by enemies
+    pub loot: ChaCha8Rng,
+    #[serde(skip)]
+    _phantom: PhantomData<S>,
+}
+
+impl<S: Side> SideRng<S> {
+    pub fn new(seed: u64) -> Self {
+        let first_stream = UPGRADES_STREAM + 1 + S::INDEX as u64 * SIDE_STREAMS;
+        Self {
+            spawn: stream(seed, first_stream),
+            crit: stream(seed, first_stream + 1),
+            weapons: stream(seed, first_stream + 2),
+            status: stream(seed, first_stream + 3),
+            loot: stream(seed, first_stream + 4),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+fn stream(seed: u64, stream: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(stream);
+    rng
+}
+
+/// Reseeds `GameRng` for the new run
+pub fn setup(requested_seed: Res<RequestedSeed>, mut game_rng: ResMut<GameRng>) {
+    let seed = requested_seed
+        .0
+        .unwrap_or_else(|| rand::thread_rng().gen::<u64>());
+    *game_rng = GameRng::new(seed, requested_seed.0.is_some());
+}
+
+fn setup_side<S: Side>(game_rng: Res<GameRng>, mut commands: Commands) {
+    commands.insert_resource(SideRng::<S>::new(game_rng.seed));
+}
@@ -0,0 +1,609 @@
+use std::fmt::Display;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    storage::{self, StorageError},
+    GlobalState,
+};
+
+use super::{
+    abilities::{CastleAbilities, Frozen},
+    castle::{Castle, CastleWall},
+    castle_type::CastleType,
+    damage::{
+        area::{DamageArea, DamageAreaBundle},
+        projectile::{EnemyProjectile, EnemyProjectileBundle, Projectile, ProjectileBundle},
+    },
+    enemies::{
+        boss::Boss,
+        definition::EnemyDefinitions,
+        elite::EliteComponents,
+        pressure::PressureState,
+        scaling::EnemyScaling,
+        spawn::{EnemyBuffs, EnemySpawn},
+        Enemy, EnemyAttack, EnemyBundle, GlobalEnemyBuffs, SpawnStateTimer,
+    },
+    loot::{DamageBoost, Pickup, PickupBundle},
+    mode::{GameMode, RunTimer},
+    rng::{GameRng, SideRng},
+    shop::Shop,
+    simulation::{Interpolated, SimulationTick},
+    stats::RunStats,
+    status::StatusEffects,
+    upgrades::Upgrades,
+    weapons::{
+        crossbow::{Crossbow, CrossbowBuffs, DEFAULT_BOLT_SIZE},
+        molotov::{Molotov, MolotovBottle, MolotovBottleBundle, MolotovBuffs},
+        GlobalWeaponBuffs, WeaponsAssets,
+    },
+    East, GameState, North, Side, South, West,
+};
+
+/// Version of the run save format.
+/// Saves with other versions can not be continued.
+pub const SAVE_VERSION: u32 = 16;
+
+const RUN_SAVE: &str = "saves/run.ron";
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveRunEvent>()
+            .add_system(
+                save_run
+                    .run_if(on_event::<SaveRunEvent>())
+                    .in_set(OnUpdate(GameState::Paused)),
+            )
+            .add_system(
+                load_run
+                    .run_if(resource_exists::<LoadedRun>())
+                    .in_schedule(OnEnter(GameState::InGame)),
+            );
+    }
+}
+
+/// Saves current run and quits to the main menu
+pub struct SaveRunEvent;
+
+/// Run which will be restored once the game starts
+#[derive(Resource)]
+pub struct LoadedRun(pub RunSave);
+
+#[derive(Debug)]
+pub enum SaveError {
+    Storage(StorageError),
+    Incompatible(u32),
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Storage(e) => f.write_fmt(format_args!("{e}")),
+            SaveError::Incompatible(version) => f.write_fmt(format_args!(
+                "save version {version} is not compatible with version {SAVE_VERSION}"
+            )),
+        }
+    }
+}
+
+impl From<StorageError> for SaveError {
+    fn from(e: StorageError) -> Self {
+        Self::Storage(e)
+    }
+}
+
+/// Only the version of the save, so it can be
+/// checked before the rest of the save is parsed
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+/// Everything needed to continue the run
+/// from the tick it was saved at
+#[derive(Serialize, Deserialize)]
+pub struct RunSave {
+    pub version: u32,
+    pub mode: GameMode,
+    pub castle_type: CastleType,
+    tick: u64,
+    run_timer: RunTimer,
+    castle: Castle,
+    spawn_state_timer: SpawnStateTimer,
+    pressure: PressureState,
+    enemy_scaling: EnemyScaling,
+    global_enemy_buffs: GlobalEnemyBuffs,
+    global_weapon_buffs: GlobalWeaponBuffs,
+    upgrades: Upgrades,
+    abilities: CastleAbilities,
+    shop: Shop,
+    damage_boost: DamageBoost,
+    stats: RunStats,
+    rng: GameRng,
+    north: SideSave<North>,
+    south: SideSave<South>,
+    west: SideSave<West>,
+    east: SideSave<East>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+struct SideSave<S: Side> {
+    wall: CastleWall<S>,
+    crossbow: Option<Crossbow<S>>,
+    molotov: Option<Molotov<S>>,
+    crossbow_buffs: CrossbowBuffs<S>,
+    molotov_buffs: MolotovBuffs<S>,
+    enemy_buffs: EnemyBuffs<S>,
+    rng: SideRng<S>,
+    spawn: EnemySpawn<S>,
+    enemies: Vec<EnemySave<S>>,
+    projectiles: Vec<ProjectileSave<S>>,
+    enemy_projectiles: Vec<EnemyProjectileSave<S>>,
+    bottles: Vec<BottleSave<S>>,
+    areas: Vec<AreaSave<S>>,
+    pickups: Vec<PickupSave<S>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+struct EnemySave<S: Side> {
+    enemy: Enemy<S>,
+    attack: EnemyAttack<S>,
+    frozen: Option<Frozen>,
+    boss: Option<Boss>,
+    status: StatusEffects,
+    elite: Option<EliteComponents>,
+    translation: Vec3,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+struct ProjectileSave<S: Side> {
+    projectile: Projectile<S>,
+    translation: Vec3,
+    rotation: Quat,
+    linvel: Vec2,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+struct EnemyProjectileSave<S: Side> {
+    projectile: EnemyProjectile<S>,
+    translation: Vec3,
+    linvel: Vec2,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+struct BottleSave<S: Side> {
+    bottle: MolotovBottle<S>,
+    translation: Vec3,
+    rotation: Quat,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+struct AreaSave<S: Side> {
+    area: DamageArea<S>,
+    translation: Vec3,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
+struct PickupSave<S: Side> {
+    pickup: Pickup<S>,
+    translation: Vec3,
+}
+
+/// Loads saved run.
+/// Returns `Ok(None)` if there is no saved run.
+pub fn load() -> Result<Option<RunSave>, SaveError> {
+    storage::load_text(RUN_SAVE)?
+        .map(|data| parse(&data))
+        .transpose()
+}
+
+/// Parses the save if it has the current version
+fn parse(data: &str) -> Result<RunSave, SaveError> {
+    let header = ron::from_str::<SaveHeader>(data).map_err(StorageError::from)?;
+    if header.version != SAVE_VERSION {
+        return Err(SaveError::Incompatible(header.version));
+    }
+    Ok(ron::from_str(data).map_err(StorageError::from)?)
+}
+
+impl RunSave {
+    fn collect(world: &mut World) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            mode: *world.resource::<GameMode>(),
+            castle_type: *world.resource::<CastleType>(),
+            tick: world.resource::<SimulationTick>().0,
+            run_timer: world.resource::<RunTimer>().clone(),
+            castle: world.query::<&Castle>().single(world).clone(),
+            spawn_state_timer: world.resource::<SpawnStateTimer>().clone(),
+            pressure: world.resource::<PressureState>().clone(),
+            enemy_scaling: world.resource::<EnemyScaling>().clone(),
+            global_enemy_buffs: world.resource::<GlobalEnemyBuffs>().clone(),
+            global_weapon_buffs: world.resource::<GlobalWeaponBuffs>().clone(),
+            upgrades: world.resource::<Upgrades>().clone(),
+            abilities: world.resource::<CastleAbilities>().clone(),
+            shop: world.resource::<Shop>().clone(),
+            damage_boost: world.resource::<DamageBoost>().clone(),
+            stats: world.resource::<RunStats>().clone(),
+            rng: world.resource::<GameRng>().clone(),
+            north: SideSave::collect(world),
+            south: SideSave::collect(world),
+            west: SideSave::collect(world),
+            east: SideSave::collect(world),
+        }
+    }
+
+    /// Castle type has to be set before the run starts,
+    /// walls and weapons are built for it
+    fn restore(self, world: &mut World) {
+        assert_eq!(
+            *world.resource::<CastleType>(),
+            self.castle_type,
+            "run was started with another castle type than the saved one"
+        );
+        world.resource_mut::<SimulationTick>().0 = self.tick;
+        world.insert_resource(self.mode);
+        world.insert_resource(self.run_timer);
+        *world.query::<&mut Castle>().single_mut(world) = self.castle;
+        world.insert_resource(self.spawn_state_timer);
+        world.insert_resource(self.pressure);
+        world.insert_resource(self.enemy_scaling);
+        world.insert_resource(self.global_enemy_buffs);
+        world.insert_resource(self.global_weapon_buffs);
+        world.insert_resource(self.upgrades);
+        world.insert_resource(self.abilities);
+        world.insert_resource(self.shop);
+        world.insert_resource(self.damage_boost);
+        world.insert_resource(self.stats);
+        world.insert_resource(self.rng);
+        self.north.restore(world);
+        self.south.restore(world);
+        self.west.restore(world);
+        self.east.restore(world);
+    }
+}
+
+impl<S: Side> SideSave<S> {
+    fn collect(world: &mut World) -> Self {
+        // walls may not have all weapons, depending on the castle type
+        let (wall, crossbow, molotov) = world
+            .query::<(&CastleWall<S>, Option<&Crossbow<S>>, Option<&Molotov<S>>)>()
+            .single(world);
+        let (wall, crossbow, molotov) = (wall.clone(), crossbow.cloned(), molotov.cloned());
+
+        let spawn = world.query::<&EnemySpawn<S>>().single(world).clone();
+
+        // rendered transforms are interpolated, simulation
+        // continues from the transforms of the last tick
+        let enemies = world
+            .query::<(
+                Entity,
+                &Enemy<S>,
+                &EnemyAttack<S>,
+                Option<&Frozen>,
+                Option<&Boss>,
+                &StatusEffects,
+                &Transform,
+                &Interpolated,
+            )>()
+            .iter(world)
+            .map(
+                |(entity, enemy, attack, frozen, boss, status, transform, interpolated)| {
+                    EnemySave {
+                        enemy: enemy.clone(),
+                        attack: attack.clone(),
+                        frozen: frozen.cloned(),
+                        boss: boss.cloned(),
+                        status: status.clone(),
+                        elite: EliteComponents::collect(world, entity),
+                        translation: interpolated.simulated(transform).translation,
+                    }
+                },
+            )
+            .collect();
+        let projectiles = world
+            .query::<(&Projectile<S>, &Velocity, &Transform, &Interpolated)>()
+            .iter(world)
+            .map(|(projectile, velocity, transform, interpolated)| {
+                let transform = interpolated.simulated(transform);
+                ProjectileSave {
+                    projectile: projectile.clone(),
+                    translation: transform.translation,
+                    rotation: transform.rotation,
+                    linvel: velocity.linvel,
+                }
+            })
+            .collect();
+        let enemy_projectiles = world
+            .query::<(&EnemyProjectile<S>, &Velocity, &Transform, &Interpolated)>()
+            .iter(world)
+            .map(
+                |(projectile, velocity, transform, interpolated)| EnemyProjectileSave {
+                    projectile: projectile.clone(),
+                    translation: interpolated.simulated(transform).translation,
+                    linvel: velocity.linvel,
+                },
+            )
+            .collect();
+        let bottles = world
+            .query::<(&MolotovBottle<S>, &Transform, &Interpolated)>()
+            .iter(world)
+            .map(|(bottle, transform, interpolated)| {
+                let transform = interpolated.simulated(transform);
+                BottleSave {
+                    bottle: bottle.clone(),
+                    translation: transform.translation,
+                    rotation: transform.rotation,
+                }
+            })
+            .collect();
+        let areas = world
+            .query::<(&DamageArea<S>, &Transform)>()
+            .iter(world)
+            .map(|(area, transform)| AreaSave {
+                area: area.clone(),
+                translation: transform.translation,
+            })
+            .collect();
+        let pickups = world
+            .query::<(&Pickup<S>, &Transform)>()
+            .iter(world)
+            .map(|(pickup, transform)| PickupSave {
+                pickup: pickup.clone(),
+                translation: transform.translation,
+            })
+            .collect();
+
+        Self {
+            wall,
+            crossbow,
+            molotov,
+            crossbow_buffs: world.resource::<CrossbowBuffs<S>>().clone(),
+            molotov_buffs: world.resource::<MolotovBuffs<S>>().clone(),
+            enemy_buffs: world.resource::<EnemyBuffs<S>>().clone(),
+            rng: world.resource::<SideRng<S>>().clone(),
+            spawn,
+            enemies,
+            projectiles,
+            enemy_projectiles,
+            bottles,
+            areas,
+            pickups,
+        }
+    }
+
+    fn restore(self, world: &mut World) {
+        let (mut wall, crossbow, molotov) = world
+            .query::<(
+                &mut CastleWall<S>,
+                Option<&mut Crossbow<S>>,
+                Option<&mut Molotov<S>>,
+            )>()
+            .single_mut(world);
+        *wall = self.wall;
+        if let (Some(mut crossbow), Some(saved)) = (crossbow, self.crossbow) {
+            *crossbow = saved;
+        }
+        if let (Some(mut molotov), Some(saved)) = (molotov, self.molotov) {
+            *molotov = saved;
+        }
+
+        world.insert_resource(self.crossbow_buffs);
+        world.insert_resource(self.molotov_buffs);
+        world.insert_resource(self.enemy_buffs);
+        world.insert_resource(self.rng);
+
+        *world.query::<&mut EnemySpawn<S>>().single_mut(world) = self.spawn;
+
+        for enemy in self.enemies {
+            restore_enemy(world, enemy);
+        }
+
+        for projectile in self.projectiles {
+            let texture = world.resource::<WeaponsAssets>().arrow.clone();
+            world.spawn(ProjectileBundle::<S>::from_projectile(
+                texture,
+                DEFAULT_BOLT_SIZE,
+                projectile.projectile,
+                Velocity::linear(projectile.linvel),
+                Transform::from_translation(projectile.translation)
+                    .with_rotation(projectile.rotation),
+            ));
+        }
+
+        for projectile in self.enemy_projectiles {
+            restore_enemy_projectile(world, projectile);
+        }
+
+        for bottle in self.bottles {
+            let texture = world.resource::<WeaponsAssets>().molotov.clone();
+            world.spawn(MolotovBottleBundle::<S>::from_bottle(
+                texture,
+                bottle.bottle,
+                Transform::from_translation(bottle.translation).with_rotation(bottle.rotation),
+            ));
+        }
+
+        for area in self.areas {
+            let texture_atlas = world.resource::<WeaponsAssets>().fire.clone();
+            world.spawn(DamageAreaBundle::<S>::new(
+                texture_atlas,
+                area.translation,
+                area.area,
+            ));
+        }
+
+        for pickup in self.pickups {
+            world.spawn(PickupBundle::<S>::new(pickup.pickup, pickup.translation));
+        }
+    }
+}
+
+fn restore_enemy<S: Side>(world: &mut World, enemy: EnemySave<S>) {
+    let Some(definition) = world
+        .resource::<EnemyDefinitions>()
+        .get(enemy.enemy.kind)
+        .cloned()
+    else {
+        warn!(
+            "saved {:?} has no definition, it is not restored",
+            enemy.enemy.kind
+        );
+        return;
+    };
+    let mut entity = world.spawn(EnemyBundle::<S>::from_enemy(
+        &definition,
+        enemy.translation,
+        enemy.enemy,
+        enemy.attack,
+    ));
+    if let Some(frozen) = enemy.frozen {
+        entity.insert(frozen);
+    }
+    if let Some(boss) = enemy.boss {
+        entity.insert(boss);
+    }
+    entity.insert(enemy.status);
+    if let Some(elite) = enemy.elite {
+        elite.insert(&mut entity);
+    }
+}
+
+fn restore_enemy_projectile<S: Side>(world: &mut World, projectile: EnemyProjectileSave<S>) {
+    let Some(ranged) = world
+        .resource::<EnemyDefinitions>()
+        .get(projectile.projectile.kind)
+        .and_then(|definition| definition.ranged.clone())
+    else {
+        warn!(
+            "projectile of saved {:?} has no ranged definition, it is not restored",
+            projectile.projectile.kind
+        );
+        return;
+    };
+    world.spawn(EnemyProjectileBundle::<S>::new(
+        ranged.projectile_texture,
+        ranged.projectile_size,
+        projectile.projectile,
+        Velocity::linear(projectile.linvel),
+        projectile.translation,
+    ));
+}
+
+fn save_run(world: &mut World) {
+    let save = RunSave::collect(world);
+    match storage::save(RUN_SAVE, &save) {
+        Ok(()) => world
+            .resource_mut::<NextState<GlobalState>>()
+            .set(GlobalState::MainMenu),
+        Err(e) => error!("could not save the run: {e}"),
+    }
+}
+
+/// Restores saved run on top of the freshly started one.
+/// Save is removed, so the run can only be continued once.
+fn load_run(world: &mut World) {
+    if let Some(LoadedRun(save)) = world.remove_resource::<LoadedRun>() {
+        save.restore(world);
+    }
+    if let Err(e) = storage::remove(RUN_SAVE) {
+        error!("could not remove the save: {e}");
+    }
+}
+
+#[cfg(all(test, not(target_family = "wasm")))]
+mod tests {
+    use std::{path::Path, time::Duration};
+
+    use bevy::{hierarchy::HierarchyPlugin, time::TimeUpdateStrategy, transform::TransformPlugin};
+
+    use crate::{
+        game::{
+            castle::CastleAssets, enemies::schedule::WaveSchedules, rng::RequestedSeed,
+            simulation::SIMULATION_TIMESTEP, GamePlugin,
+        },
+        GameAssets,
+    };
+
+    use super::*;
+
+    /// Headless run started the same way as in `mys-sim`
+    fn started_run() -> App {
+        let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        let enemy_definitions = match EnemyDefinitions::load_from_dir(&assets.join("enemies")) {
+            Ok(enemy_definitions) => enemy_definitions,
+            Err(e) => panic!("{e}"),
+        };
+        let schedules = match WaveSchedules::load_from_dir(&assets.join("schedules")) {
+            Ok(schedules) => schedules,
+            Err(e) => panic!("{e}"),
+        };
+
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(TransformPlugin)
+            .add_plugin(HierarchyPlugin)
+            .add_plugin(
+                RapierPhysicsPlugin::<NoUserData>::default().with_default_system_setup(false),
+            )
+            .add_state::<GlobalState>()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                SIMULATION_TIMESTEP,
+            )))
+            .init_resource::<GameAssets>()
+            .init_resource::<CastleAssets>()
+            .init_resource::<WeaponsAssets>()
+            .add_plugin(GamePlugin)
+            .insert_resource(enemy_definitions)
+            .insert_resource(schedules)
+            .insert_resource(RequestedSeed(Some(0)));
+        app.world
+            .insert_resource(NextState(Some(GlobalState::InGame)));
+        for _ in 0..10 {
+            app.update();
+        }
+        app
+    }
+
+    fn to_ron(save: &RunSave) -> String {
+        match ron::to_string(save) {
+            Ok(data) => data,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    #[test]
+    fn saved_run_is_parsed() {
+        let mut app = started_run();
+        let save = RunSave::collect(&mut app.world);
+        let tick = save.tick;
+
+        match parse(&to_ron(&save)) {
+            Ok(parsed) => assert_eq!(parsed.tick, tick),
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    #[test]
+    fn save_of_another_version_is_incompatible() {
+        let mut app = started_run();
+        let mut save = RunSave::collect(&mut app.world);
+        save.version = SAVE_VERSION + 1;
+
+        assert!(matches!(
+            parse(&to_ron(&save)),
+            Err(SaveError::Incompatible(version)) if version == SAVE_VERSION + 1
+        ));
+    }
+}
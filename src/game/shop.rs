@@ -0,0 +1,379 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::GlobalState;
+
+use super::{
+    castle::{Castle, CastleWall},
+    enemies::{SpawnState, SpawnStateTimer},
+    rng::GameRng,
+    simulation::ResolveSet,
+    stats::RunStats,
+    upgrades::{
+        apply::{apply_wall_upgrade, apply_weapon_upgrade},
+        genereate_wall_upgrade, genereate_weapon_upgrade, Upgrade, UpgradeSide, WallUpgrade,
+        WeaponUpgrade,
+    },
+    weapons::{crossbow::CrossbowBuffs, molotov::MolotovBuffs},
+    East, GameState, North, Side, SideId, South, West,
+};
+
+/// Price of the first side upgrade of the run
+const UPGRADE_PRICE: u32 = 40;
+/// Every bought side upgrade makes the next one more expensive
+const UPGRADE_PRICE_GROWTH: u32 = 20;
+const REPAIR_PRICE: u32 = 30;
+/// Part of the wall max health restored by a repair
+const REPAIR_PART: f32 = 0.3;
+/// Price of the first reroll of the visit,
+/// every next one costs this much more
+const REROLL_PRICE: u32 = 15;
+
+pub struct ShopPlugin;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShopEvent>()
+            .add_system(setup.in_schedule(OnEnter(GlobalState::InGame)))
+            .add_system(
+                // level ups are handled first, shop opens once they are done
+                open_shop
+                    .in_set(ResolveSet::Shop)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(restock.in_schedule(OnEnter(GameState::Shop)))
+            .add_systems(
+                (
+                    buy_for_side::<North>,
+                    buy_for_side::<South>,
+                    buy_for_side::<West>,
+                    buy_for_side::<East>,
+                    reroll_and_leave,
+                )
+                    // all of them spend the same gold
+                    .chain()
+                    .in_set(ShopSet)
+                    .in_set(OnUpdate(GameState::Shop)),
+            );
+    }
+}
+
+/// Systems handling `ShopEvent`s
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub struct ShopSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShopItem {
+    /// Upgrade offered for the side
+    Upgrade(SideId),
+    /// Heals the wall of the side
+    Repair(SideId),
+    /// New upgrade offers for all sides
+    Reroll,
+}
+
+impl ShopItem {
+    pub fn side(&self) -> Option<SideId> {
+        match self {
+            ShopItem::Upgrade(side) | ShopItem::Repair(side) => Some(*side),
+            ShopItem::Reroll => None,
+        }
+    }
+}
+
+/// Player decision in the shop
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShopEvent {
+    Buy(ShopItem),
+    /// Closes the shop and continues the run
+    Leave,
+}
+
+/// Upgrade of a single side which is always a buff
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct ShopOffer {
+    pub wall_upgrade: Option<WallUpgrade>,
+    pub weapon_upgrade: Option<WeaponUpgrade>,
+    pub sold: bool,
+}
+
+impl ShopOffer {
+    fn generate(rng: &mut impl Rng) -> Self {
+        let (wall_upgrade, weapon_upgrade) = if rng.gen_ratio(1, 4) {
+            (Some(genereate_wall_upgrade(rng)), None)
+        } else {
+            (None, Some(genereate_weapon_upgrade(rng)))
+        };
+        Self {
+            wall_upgrade,
+            weapon_upgrade,
+            sold: false,
+        }
+    }
+
+    /// Offer as an upgrade of the side
+    pub fn upgrade(&self, side: SideId) -> Upgrade {
+        Upgrade {
+            wall_upgrade: self
+                .wall_upgrade
+                .map(|upgrade| UpgradeSide::new(side, upgrade)),
+            weapon_upgrade: self
+                .weapon_upgrade
+                .map(|upgrade| UpgradeSide::new(side, upgrade)),
+            ..default()
+        }
+    }
+}
+
+impl std::fmt::Display for ShopOffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(upgrade) = self.wall_upgrade {
+            f.write_fmt(format_args!("Wall {upgrade}"))?;
+        }
+        if let Some(upgrade) = self.weapon_upgrade {
+            f.write_fmt(format_args!("{upgrade}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Shop opened between the stages
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
+pub struct Shop {
+    /// Upgrade offers by `Side::INDEX`
+    pub offers: [ShopOffer; 4],
+    /// Stage the shop was last opened for
+    stage: SpawnState,
+    /// Stage changed, shop opens once nothing else is happening
+    pending: bool,
+    /// Side upgrades bought during the run
+    upgrades_bought: u32,
+    /// Rerolls bought during the current visit
+    rerolls: u32,
+}
+
+impl Shop {
+    pub fn price(&self, item: ShopItem) -> u32 {
+        match item {
+            ShopItem::Upgrade(_) => UPGRADE_PRICE + UPGRADE_PRICE_GROWTH * self.upgrades_bought,
+            ShopItem::Repair(_) => REPAIR_PRICE,
+            ShopItem::Reroll => REROLL_PRICE * (1 + self.rerolls),
+        }
+    }
+
+    /// Spends the gold if the item can be bought
+    fn try_buy(&mut self, item: ShopItem, gold: &mut u32) -> bool {
+        if let ShopItem::Upgrade(side) = item {
+            if self.offers[side.index()].sold {
+                return false;
+            }
+        }
+        let price = self.price(item);
+        if *gold < price {
+            return false;
+        }
+        *gold -= price;
+        match item {
+            ShopItem::Upgrade(side) => {
+                self.offers[side.index()].sold = true;
+                self.upgrades_bought += 1;
+            }
+            ShopItem::Repair(_) => {}
+            ShopItem::Reroll => self.rerolls += 1,
+        }
+        true
+    }
+
+    fn restock(&mut self, rng: &mut impl Rng) {
+        for offer in self.offers.iter_mut() {
+            *offer = ShopOffer::generate(rng);
+        }
+    }
+
+    /// Starts a new visit with new offers and cheap rerolls
+    fn open(&mut self, rng: &mut impl Rng) {
+        self.rerolls = 0;
+        self.restock(rng);
+    }
+
+    /// Repairs the wall if it is damaged and the gold is enough
+    fn try_repair<S: Side>(&mut self, wall: &mut CastleWall<S>, gold: &mut u32) -> bool {
+        // full walls do not need repairs
+        if wall.max_health <= wall.health
+            || !self.try_buy(ShopItem::Repair(SideId::of::<S>()), gold)
+        {
+            return false;
+        }
+        let heal = (wall.max_health as f32 * REPAIR_PART) as i32;
+        wall.heal(heal);
+        true
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.insert_resource(Shop::default());
+}
+
+/// Opens the shop after the stage changes,
+/// unless other state change was already requested
+fn open_shop(
+    spawn_state_timer: Res<SpawnStateTimer>,
+    mut shop: ResMut<Shop>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if spawn_state_timer.current_state != shop.stage {
+        shop.stage = spawn_state_timer.current_state;
+        shop.pending = true;
+    }
+    if shop.pending && game_state.0.is_none() {
+        shop.pending = false;
+        game_state.set(GameState::Shop);
+    }
+}
+
+fn restock(mut game_rng: ResMut<GameRng>, mut shop: ResMut<Shop>) {
+    shop.open(&mut game_rng.shop);
+}
+
+/// Upgrades and repairs of the side
+fn buy_for_side<S: Side>(
+    mut events: EventReader<ShopEvent>,
+    mut shop: ResMut<Shop>,
+    mut stats: ResMut<RunStats>,
+    mut castle: Query<&mut Castle>,
+    mut wall: Query<&mut CastleWall<S>>,
+    mut crossbow_buffs: ResMut<CrossbowBuffs<S>>,
+    mut molotov_buffs: ResMut<MolotovBuffs<S>>,
+) {
+    let mut castle = castle.single_mut();
+    let mut wall = wall.single_mut();
+    for event in events.iter() {
+        let ShopEvent::Buy(item) = *event else {
+            continue;
+        };
+        if item.side() != Some(SideId::of::<S>()) {
+            continue;
+        }
+        match item {
+            ShopItem::Upgrade(_) => {
+                // rerolls of the same frame are handled after
+                // all purchases, so the offer is still the bought one
+                let offer = shop.offers[S::INDEX];
+                if !shop.try_buy(item, &mut castle.gold) {
+                    continue;
+                }
+                if let Some(upgrade) = offer.wall_upgrade {
+                    apply_wall_upgrade(&mut wall, upgrade);
+                }
+                if let Some(upgrade) = offer.weapon_upgrade {
+                    apply_weapon_upgrade(&mut crossbow_buffs, &mut molotov_buffs, upgrade);
+                }
+                stats.add_upgrade(offer.upgrade(SideId::of::<S>()));
+            }
+            ShopItem::Repair(_) => {
+                shop.try_repair(&mut wall, &mut castle.gold);
+            }
+            ShopItem::Reroll => {}
+        }
+    }
+}
+
+fn reroll_and_leave(
+    mut events: EventReader<ShopEvent>,
+    mut game_rng: ResMut<GameRng>,
+    mut shop: ResMut<Shop>,
+    mut castle: Query<&mut Castle>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let mut castle = castle.single_mut();
+    for event in events.iter() {
+        match *event {
+            ShopEvent::Buy(ShopItem::Reroll) => {
+                if shop.try_buy(ShopItem::Reroll, &mut castle.gold) {
+                    shop.restock(&mut game_rng.shop);
+                }
+            }
+            ShopEvent::Buy(_) => {}
+            ShopEvent::Leave => game_state.set(GameState::InGame),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn stocked_shop() -> Shop {
+        let mut shop = Shop::default();
+        shop.open(&mut ChaCha8Rng::seed_from_u64(0));
+        shop
+    }
+
+    #[test]
+    fn upgrades_get_more_expensive() {
+        let mut shop = stocked_shop();
+        let mut gold = 1000;
+
+        assert!(shop.try_buy(ShopItem::Upgrade(SideId::North), &mut gold));
+        assert_eq!(gold, 1000 - UPGRADE_PRICE);
+        assert_eq!(
+            shop.price(ShopItem::Upgrade(SideId::South)),
+            UPGRADE_PRICE + UPGRADE_PRICE_GROWTH
+        );
+    }
+
+    #[test]
+    fn sold_offer_is_not_sold_again() {
+        let mut shop = stocked_shop();
+        let mut gold = 1000;
+
+        assert!(shop.try_buy(ShopItem::Upgrade(SideId::West), &mut gold));
+        let left = gold;
+        assert!(!shop.try_buy(ShopItem::Upgrade(SideId::West), &mut gold));
+        assert_eq!(gold, left);
+    }
+
+    #[test]
+    fn nothing_is_bought_without_enough_gold() {
+        let mut shop = stocked_shop();
+        let mut gold = UPGRADE_PRICE - 1;
+
+        assert!(!shop.try_buy(ShopItem::Upgrade(SideId::East), &mut gold));
+        assert_eq!(gold, UPGRADE_PRICE - 1);
+        assert!(!shop.offers[SideId::East.index()].sold);
+    }
+
+    #[test]
+    fn reroll_price_resets_on_the_next_visit() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let mut shop = stocked_shop();
+        let mut gold = 1000;
+
+        assert!(shop.try_buy(ShopItem::Reroll, &mut gold));
+        assert_eq!(shop.price(ShopItem::Reroll), 2 * REROLL_PRICE);
+        shop.restock(&mut rng);
+        assert_eq!(shop.price(ShopItem::Reroll), 2 * REROLL_PRICE);
+
+        shop.open(&mut rng);
+        assert_eq!(shop.price(ShopItem::Reroll), REROLL_PRICE);
+    }
+
+    #[test]
+    fn full_wall_is_not_repaired() {
+        let mut shop = stocked_shop();
+        let mut wall = CastleWall::<North>::new(100, 1.0);
+        let mut gold = 1000;
+
+        assert!(!shop.try_repair(&mut wall, &mut gold));
+        assert_eq!(gold, 1000);
+
+        wall.health = 50;
+        assert!(shop.try_repair(&mut wall, &mut gold));
+        assert_eq!(gold, 1000 - REPAIR_PRICE);
+        assert_eq!(wall.health, 80);
+    }
+}
@@ -0,0 +1,306 @@
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+use bevy_rapier2d::prelude::*;
+
+use crate::GlobalState;
+
+use super::GameState;
+
+/// Length of a single simulation tick in seconds
+pub const SIMULATION_TIMESTEP: f32 = 1.0 / 60.0;
+
+/// Collision group of the castle walls
+pub const WALL_GROUP: Group = Group::GROUP_1;
+/// Collision group of enemies walking to the walls
+pub const GROUND_GROUP: Group = Group::GROUP_2;
+/// Collision group of flying enemies. They only collide
+/// with each other and anti-air projectiles.
+pub const AIR_GROUP: Group = Group::GROUP_3;
+/// Collision group of the castle weapon projectiles
+pub const PROJECTILE_GROUP: Group = Group::GROUP_4;
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        let mut physics_schedule = Schedule::new();
+        physics_schedule
+            .configure_sets(
+                (
+                    PhysicsSet::SyncBackend,
+                    PhysicsSet::SyncBackendFlush,
+                    PhysicsSet::StepSimulation,
+                    PhysicsSet::Writeback,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::SyncBackend)
+                    .in_base_set(PhysicsSet::SyncBackend),
+            )
+            .add_systems(
+                RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::SyncBackendFlush)
+                    .in_base_set(PhysicsSet::SyncBackendFlush),
+            )
+            .add_systems(
+                RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::StepSimulation)
+                    .in_base_set(PhysicsSet::StepSimulation),
+            )
+            .add_systems(
+                RapierPhysicsPlugin::<NoUserData>::get_systems(PhysicsSet::Writeback)
+                    .in_base_set(PhysicsSet::Writeback),
+            );
+
+        app.insert_resource(FixedTime::new_from_secs(SIMULATION_TIMESTEP))
+            .init_resource::<SimulationTick>()
+            .init_resource::<SimulationSpeed>()
+            .init_resource::<SingleStep>()
+            .add_schedule(PhysicsSchedule, physics_schedule)
+            .edit_schedule(CoreSchedule::FixedUpdate, |schedule| {
+                schedule
+                    .configure_sets(
+                        (
+                            SimulationSet::Progress,
+                            SimulationSet::Act,
+                            SimulationSet::Damage,
+                            SimulationSet::Death,
+                            SimulationSet::Resolve,
+                            SimulationSet::Physics,
+                        )
+                            .chain(),
+                    )
+                    .configure_sets(
+                        (
+                            ResolveSet::Defeat,
+                            ResolveSet::Victory,
+                            ResolveSet::LevelUp,
+                            ResolveSet::Shop,
+                        )
+                            .chain(),
+                    )
+                    .configure_set(ResolveSet::Defeat.in_set(SimulationSet::Resolve))
+                    .configure_set(ResolveSet::Victory.in_set(SimulationSet::Resolve))
+                    .configure_set(ResolveSet::LevelUp.in_set(SimulationSet::Resolve))
+                    .configure_set(ResolveSet::Shop.in_set(SimulationSet::Resolve))
+                    .configure_set(SimulationSet::Progress.run_if(simulation_running))
+                    .configure_set(SimulationSet::Act.run_if(simulation_running))
+                    .configure_set(SimulationSet::Damage.run_if(simulation_running))
+                    .configure_set(SimulationSet::Death.run_if(simulation_running))
+                    .configure_set(SimulationSet::Resolve.run_if(simulation_running))
+                    .configure_set(SimulationSet::Physics.run_if(simulation_running));
+            })
+            .add_system(setup.in_schedule(OnEnter(GlobalState::InGame)))
+            .add_system(cleanup.in_schedule(OnExit(GlobalState::InGame)))
+            .add_system(drain_fixed_time.in_schedule(OnEnter(GameState::InGame)))
+            .add_systems(
+                (simulation_tick, restore_transforms)
+                    .in_set(SimulationSet::Progress)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (run_physics, record_transforms)
+                    .chain()
+                    .in_set(SimulationSet::Physics)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(interpolate_transforms.in_set(OnUpdate(GameState::InGame)))
+            .add_systems((update_time, single_step));
+    }
+}
+
+/// Schedule with rapier systems.
+/// Runs once every simulation tick, so physics
+/// advances in lockstep with the rest of the game.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, ScheduleLabel)]
+pub struct PhysicsSchedule;
+
+/// Order of the game logic inside of a single simulation tick.
+/// All game logic runs in `CoreSchedule::FixedUpdate`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub enum SimulationSet {
+    /// Timers, stages and spawns
+    Progress,
+    /// Movement and attacks of enemies and weapons
+    Act,
+    /// Damage events are applied
+    Damage,
+    /// Enemies without health die
+    Death,
+    /// Level ups and end of the run
+    Resolve,
+    Physics,
+}
+
+/// Systems requesting the next game state at the end of the tick.
+/// Only the first request is kept, so a tick which ends the run
+/// and levels up the castle always ends it, also in replays.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub enum ResolveSet {
+    Defeat,
+    Victory,
+    LevelUp,
+    Shop,
+}
+
+/// Number of simulation ticks since the start of the run
+#[derive(Debug, Default, Resource)]
+pub struct SimulationTick(pub u64);
+
+/// How fast the simulation runs compared to real time
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub enum SimulationSpeed {
+    #[default]
+    Normal,
+    Double,
+    Quadruple,
+}
+
+impl SimulationSpeed {
+    pub fn multiplier(&self) -> f32 {
+        match self {
+            SimulationSpeed::Normal => 1.0,
+            SimulationSpeed::Double => 2.0,
+            SimulationSpeed::Quadruple => 4.0,
+        }
+    }
+
+    /// Cycles 1x -> 2x -> 4x -> 1x
+    pub fn next(&self) -> Self {
+        match self {
+            SimulationSpeed::Normal => SimulationSpeed::Double,
+            SimulationSpeed::Double => SimulationSpeed::Quadruple,
+            SimulationSpeed::Quadruple => SimulationSpeed::Normal,
+        }
+    }
+}
+
+/// Debug mode where time is stopped and
+/// simulation only advances by requested number of ticks
+#[derive(Debug, Default, Resource)]
+pub struct SingleStep {
+    pub enabled: bool,
+    pub requested_steps: u32,
+}
+
+/// Smooths movement of the entity between simulation ticks.
+/// Simulation works with the transform of the last tick, while
+/// rendered transform is interpolated between the last two ticks.
+#[derive(Debug, Default, Component)]
+pub struct Interpolated {
+    previous: Option<Transform>,
+    current: Option<Transform>,
+}
+
+impl Interpolated {
+    /// Transform of the entity after the last simulation tick
+    pub fn simulated(&self, transform: &Transform) -> Transform {
+        self.current.unwrap_or(*transform)
+    }
+}
+
+/// Simulation runs only in game and stops on the same
+/// tick a state change is requested, so it does not depend
+/// on how many ticks happen to fit in a frame.
+pub fn simulation_running(
+    game_state: Res<State<GameState>>,
+    next_game_state: Res<NextState<GameState>>,
+) -> bool {
+    game_state.0 == GameState::InGame && next_game_state.0.is_none()
+}
+
+fn setup(mut physics: ResMut<RapierConfiguration>, mut tick: ResMut<SimulationTick>) {
+    // disable gravity because game is 2d top down
+    physics.gravity = Vec2::ZERO;
+    physics.timestep_mode = TimestepMode::Fixed {
+        dt: SIMULATION_TIMESTEP,
+        substeps: 1,
+    };
+    tick.0 = 0;
+}
+
+/// Throws away time accumulated while the simulation was stopped,
+/// otherwise it would all be simulated at once on resume
+fn drain_fixed_time(mut fixed_time: ResMut<FixedTime>) {
+    while fixed_time.expend().is_ok() {}
+}
+
+fn cleanup(mut speed: ResMut<SimulationSpeed>, mut single_step: ResMut<SingleStep>) {
+    *speed = SimulationSpeed::default();
+    *single_step = SingleStep::default();
+}
+
+pub fn simulation_tick(mut tick: ResMut<SimulationTick>) {
+    tick.0 += 1;
+}
+
+/// Puts back transforms of the last tick
+/// before the simulation continues
+fn restore_transforms(mut entities: Query<(&Interpolated, &mut Transform)>) {
+    for (interpolated, mut transform) in entities.iter_mut() {
+        if let Some(current) = interpolated.current {
+            if *transform != current {
+                *transform = current;
+            }
+        }
+    }
+}
+
+fn record_transforms(mut entities: Query<(&mut Interpolated, &Transform)>) {
+    for (mut interpolated, transform) in entities.iter_mut() {
+        interpolated.previous = Some(interpolated.current.unwrap_or(*transform));
+        interpolated.current = Some(*transform);
+    }
+}
+
+/// Moves entities to where they would be
+/// between the last two ticks
+fn interpolate_transforms(
+    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
+    mut entities: Query<(&Interpolated, &mut Transform)>,
+) {
+    // while stepping show the result of the last tick
+    let alpha = if time.is_paused() {
+        1.0
+    } else {
+        (fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32()).min(1.0)
+    };
+
+    for (interpolated, mut transform) in entities.iter_mut() {
+        if let (Some(previous), Some(current)) = (interpolated.previous, interpolated.current) {
+            transform.translation = previous.translation.lerp(current.translation, alpha);
+            transform.rotation = previous.rotation.slerp(current.rotation, alpha);
+            transform.scale = previous.scale.lerp(current.scale, alpha);
+        }
+    }
+}
+
+fn update_time(speed: Res<SimulationSpeed>, single_step: Res<SingleStep>, mut time: ResMut<Time>) {
+    if !speed.is_changed() && !single_step.is_changed() {
+        return;
+    }
+
+    time.set_relative_speed(speed.multiplier());
+    if single_step.enabled {
+        time.pause();
+    } else {
+        time.unpause();
+    }
+}
+
+/// Adds time for requested ticks, they
+/// are simulated on the next fixed update
+fn single_step(mut single_step: ResMut<SingleStep>, mut fixed_time: ResMut<FixedTime>) {
+    if !single_step.enabled || single_step.requested_steps == 0 {
+        return;
+    }
+
+    let period = fixed_time.period;
+    for _ in 0..single_step.requested_steps {
+        fixed_time.tick(period);
+    }
+    single_step.requested_steps = 0;
+}
+
+fn run_physics(world: &mut World) {
+    world.run_schedule(PhysicsSchedule);
+}
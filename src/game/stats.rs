@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::GlobalState;
+
+use super::{
+    castle::CastleWall,
+    damage::{DamageSource, EnemyDamagedEvent, WallDamagedEvent},
+    enemies::{EnemyDeathEvent, EnemyKind, SpawnStateTimer},
+    simulation::SimulationSet,
+    upgrades::{
+        apply::{ApplyUpgradeEvent, UpgradeSet},
+        Upgrade, Upgrades,
+    },
+    East, GameState, North, Side, South, West,
+};
+
+pub struct RunStatsPlugin;
+
+impl Plugin for RunStatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .add_system(setup.in_schedule(OnEnter(GlobalState::InGame)))
+            .add_systems(
+                (
+                    record_damage_dealt::<North>,
+                    record_damage_dealt::<South>,
+                    record_damage_dealt::<West>,
+                    record_damage_dealt::<East>,
+                    record_damage_taken::<North>,
+                    record_damage_taken::<South>,
+                    record_damage_taken::<West>,
+                    record_damage_taken::<East>,
+                    record_kills::<North>,
+                    record_kills::<South>,
+                    record_kills::<West>,
+                    record_kills::<East>,
+                    record_wall_health,
+                )
+                    .in_set(RecordStatsSet)
+                    .in_set(SimulationSet::Resolve)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(
+                record_upgrades
+                    .in_set(UpgradeSet::Dispatch)
+                    .in_set(OnUpdate(GameState::LevelUp)),
+            );
+    }
+}
+
+/// Systems collecting the run stats during the simulation tick
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub struct RecordStatsSet;
+
+/// Statistics of the current run collected from the game events
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
+pub struct RunStats {
+    /// Stats of the sides by `Side::INDEX`
+    pub sides: [SideStats; 4],
+    /// Stage the wall health is tracked for
+    pub stage: u32,
+    /// Largest part of max hp any wall lost during the current stage
+    pub stage_wall_damage: f32,
+    /// Smallest `stage_wall_damage` of all finished stages
+    pub best_stage_wall_damage: Option<f32>,
+    /// Upgrades in the order they were picked
+    pub upgrades: Vec<Upgrade>,
+    /// Number of times each buff was picked by its name
+    pub upgrade_picks: HashMap<String, u32>,
+}
+
+impl RunStats {
+    pub fn add_upgrade(&mut self, upgrade: Upgrade) {
+        self.upgrades.push(upgrade);
+        for name in upgrade.buff_names() {
+            *self.upgrade_picks.entry(name.to_string()).or_default() += 1;
+        }
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.sides.iter().map(SideStats::total_kills).sum()
+    }
+
+    pub fn total_crits(&self) -> u32 {
+        self.sides.iter().map(|side| side.crits).sum()
+    }
+
+    /// Part of the weapon hits which were critical
+    pub fn crit_rate(&self) -> f32 {
+        let hits = self.sides.iter().map(|side| side.hits).sum::<u32>();
+        if hits == 0 {
+            0.0
+        } else {
+            self.total_crits() as f32 / hits as f32
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SideStats {
+    pub kills: HashMap<EnemyKind, u32>,
+    pub damage_dealt: HashMap<DamageSource, u64>,
+    /// Hits which could be critical
+    pub hits: u32,
+    pub crits: u32,
+    pub damage_taken: u64,
+}
+
+impl SideStats {
+    pub fn total_kills(&self) -> u32 {
+        self.kills.values().sum()
+    }
+
+    pub fn total_damage_dealt(&self) -> u64 {
+        self.damage_dealt.values().sum()
+    }
+}
+
+fn setup(mut commands: Commands) {
+    commands.insert_resource(RunStats::default());
+}
+
+fn record_damage_dealt<S: Side>(
+    mut events: EventReader<EnemyDamagedEvent<S>>,
+    mut stats: ResMut<RunStats>,
+) {
+    let side = &mut stats.sides[S::INDEX];
+    for event in events.iter() {
+        *side.damage_dealt.entry(event.source).or_default() += event.damage.max(0) as u64;
+        if event.source.can_crit() {
+            side.hits += 1;
+        }
+        if event.was_crit {
+            side.crits += 1;
+        }
+    }
+}
+
+fn record_damage_taken<S: Side>(
+    mut events: EventReader<WallDamagedEvent<S>>,
+    mut stats: ResMut<RunStats>,
+) {
+    let side = &mut stats.sides[S::INDEX];
+    for event in events.iter() {
+        side.damage_taken += event.damage.max(0) as u64;
+    }
+}
+
+fn record_kills<S: Side>(mut events: EventReader<EnemyDeathEvent<S>>, mut stats: ResMut<RunStats>) {
+    let side = &mut stats.sides[S::INDEX];
+    for event in events.iter() {
+        *side.kills.entry(event.kind).or_default() += 1;
+    }
+}
+
+fn record_wall_health(
+    spawn_state_timer: Res<SpawnStateTimer>,
+    wall_north: Query<&CastleWall<North>>,
+    wall_south: Query<&CastleWall<South>>,
+    wall_west: Query<&CastleWall<West>>,
+    wall_east: Query<&CastleWall<East>>,
+    mut stats: ResMut<RunStats>,
+) {
+    let stage = spawn_state_timer.stage_number();
+    if stats.stage != stage {
+        // no stage was tracked before the first tick
+        if stats.stage != 0 {
+            let stage_wall_damage = stats.stage_wall_damage;
+            stats.best_stage_wall_damage = Some(
+                stats
+                    .best_stage_wall_damage
+                    .map_or(stage_wall_damage, |best| best.min(stage_wall_damage)),
+            );
+        }
+        stats.stage = stage;
+        stats.stage_wall_damage = 0.0;
+    }
+
+    let wall_damage = [
+        wall_damage(wall_north.single()),
+        wall_damage(wall_south.single()),
+        wall_damage(wall_west.single()),
+        wall_damage(wall_east.single()),
+    ]
+    .into_iter()
+    .fold(stats.stage_wall_damage, f32::max);
+    stats.stage_wall_damage = wall_damage;
+}
+
+/// Part of max hp the wall lost
+fn wall_damage<S: Side>(wall: &CastleWall<S>) -> f32 {
+    1.0 - wall.health as f32 / wall.max_health as f32
+}
+
+fn record_upgrades(
+    upgrades: Res<Upgrades>,
+    mut events: EventReader<ApplyUpgradeEvent>,
+    mut stats: ResMut<RunStats>,
+) {
+    for event in events.iter() {
+        stats.add_upgrade(upgrades.upgrades[*event as usize]);
+    }
+}
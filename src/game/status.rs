@@ -0,0 +1,422 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    abilities::Frozen,
+    damage::{DamageSource, DamageType, EnemyDamageEvent},
+    enemies::{
+        elite::{Elite, ELITE_COLOR},
+        Enemy,
+    },
+    simulation::SimulationSet,
+    East, North, Side, South, West,
+};
+
+/// Seconds between damage ticks of burning and poison
+const DAMAGE_TICK: f32 = 0.5;
+/// Slow can not remove more than this part of the speed
+const MAX_SLOW: f32 = 0.8;
+const MAX_POISON_STACKS: usize = 5;
+/// Seconds after a stun ends during which enemy can not be stunned
+const STUN_IMMUNITY: f32 = 2.0;
+
+const BURNING_COLOR: Color = Color::rgb(1.0, 0.6, 0.3);
+const SLOWED_COLOR: Color = Color::rgb(0.6, 0.6, 1.0);
+const POISONED_COLOR: Color = Color::rgb(0.5, 1.0, 0.4);
+const STUNNED_COLOR: Color = Color::rgb(1.0, 1.0, 0.4);
+
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<StatusEvent<North>>()
+            .add_event::<StatusEvent<South>>()
+            .add_event::<StatusEvent<West>>()
+            .add_event::<StatusEvent<East>>()
+            .add_systems(
+                (
+                    status_update::<North>,
+                    status_update::<South>,
+                    status_update::<West>,
+                    status_update::<East>,
+                )
+                    .in_set(SimulationSet::Act)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_systems(
+                (
+                    apply_status::<North>,
+                    apply_status::<South>,
+                    apply_status::<West>,
+                    apply_status::<East>,
+                )
+                    .in_set(SimulationSet::Damage)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            )
+            .add_system(
+                status_tint
+                    .in_set(SimulationSet::Resolve)
+                    .in_schedule(CoreSchedule::FixedUpdate),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StatusKind {
+    /// Damages over time, keeps burning after leaving the fire
+    Burning,
+    /// Reduces movement speed
+    Slowed,
+    /// Damages over time, stacks
+    Poisoned,
+    /// Stops movement and attacks
+    Stunned,
+}
+
+/// Effect applied to the enemy
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    /// Damage per second for burning and poison,
+    /// part of the speed removed for slow.
+    /// Not used by stun.
+    pub power: f32,
+    /// Seconds the effect lasts
+    pub duration: f32,
+}
+
+impl StatusEffect {
+    pub fn new(kind: StatusKind, power: f32, duration: f32) -> Self {
+        Self {
+            kind,
+            power,
+            duration,
+        }
+    }
+}
+
+/// Effect weapon applies on hit with a chance
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StatusChance {
+    pub effect: StatusEffect,
+    pub chance: f32,
+}
+
+impl StatusChance {
+    pub fn new(effect: StatusEffect, chance: f32) -> Self {
+        Self { effect, chance }
+    }
+
+    /// Effect if the chance succeeded
+    pub fn roll(&self, rng: &mut impl Rng) -> Option<StatusEffect> {
+        if self.chance <= 0.0 {
+            return None;
+        }
+        (rng.gen_range(0.0..1.0) < self.chance).then_some(self.effect)
+    }
+}
+
+/// Event to apply status effect to the enemy
+pub struct StatusEvent<S: Side> {
+    pub target: Entity,
+    pub effect: StatusEffect,
+    _phantom: PhantomData<S>,
+}
+
+impl<S: Side> StatusEvent<S> {
+    pub fn new(target: Entity, effect: StatusEffect) -> Self {
+        Self {
+            target,
+            effect,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ActiveStatus {
+    pub power: f32,
+    /// Seconds until the effect ends
+    pub remaining: f32,
+}
+
+/// Status effects on the enemy.
+/// Burning and slow keep the strongest effect and the longest duration,
+/// poison stacks up to `MAX_POISON_STACKS` and stun can not be
+/// applied again for `STUN_IMMUNITY` seconds after it ends.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Component)]
+pub struct StatusEffects {
+    pub burning: Option<ActiveStatus>,
+    pub slowed: Option<ActiveStatus>,
+    pub poisoned: Vec<ActiveStatus>,
+    pub stunned: Option<ActiveStatus>,
+    /// Seconds until enemy can be stunned again
+    stun_immunity: f32,
+    /// Seconds until the next damage tick
+    damage_timer: f32,
+    /// Damage accumulated since the last damage tick
+    burning_damage: f32,
+    poison_damage: f32,
+}
+
+impl StatusEffects {
+    pub fn apply(&mut self, effect: StatusEffect) {
+        let new = ActiveStatus {
+            power: effect.power,
+            remaining: effect.duration,
+        };
+        match effect.kind {
+            StatusKind::Burning => Self::refresh(&mut self.burning, new),
+            StatusKind::Slowed => Self::refresh(&mut self.slowed, new),
+            StatusKind::Poisoned => {
+                if self.poisoned.len() < MAX_POISON_STACKS {
+                    self.poisoned.push(new);
+                } else if let Some(oldest) = self
+                    .poisoned
+                    .iter_mut()
+                    .min_by(|a, b| a.remaining.total_cmp(&b.remaining))
+                {
+                    *oldest = new;
+                }
+            }
+            StatusKind::Stunned => {
+                if 0.0 < self.stun_immunity {
+                    return;
+                }
+                Self::refresh(&mut self.stunned, new);
+            }
+        }
+    }
+
+    fn refresh(current: &mut Option<ActiveStatus>, new: ActiveStatus) {
+        *current = Some(match current {
+            Some(current) => ActiveStatus {
+                power: current.power.max(new.power),
+                remaining: current.remaining.max(new.remaining),
+            },
+            None => new,
+        });
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        match kind {
+            StatusKind::Burning => self.burning.is_some(),
+            StatusKind::Slowed => self.slowed.is_some(),
+            StatusKind::Poisoned => !self.poisoned.is_empty(),
+            StatusKind::Stunned => self.stunned.is_some(),
+        }
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.stunned.is_some()
+    }
+
+    /// Multiplier for the enemy speed
+    pub fn speed_multiplier(&self) -> f32 {
+        self.slowed
+            .map_or(1.0, |slowed| 1.0 - slowed.power.clamp(0.0, MAX_SLOW))
+    }
+
+    /// Advances durations. Returns burning and poison
+    /// damage to deal once the damage tick is reached.
+    fn tick(&mut self, delta: f32) -> Option<(i32, i32)> {
+        if let Some(burning) = &self.burning {
+            self.burning_damage += burning.power * delta.min(burning.remaining);
+        }
+        for poison in self.poisoned.iter() {
+            self.poison_damage += poison.power * delta.min(poison.remaining);
+        }
+
+        for status in [&mut self.burning, &mut self.slowed] {
+            if status.as_mut().map_or(false, |status| {
+                status.remaining -= delta;
+                status.remaining <= 0.0
+            }) {
+                *status = None;
+            }
+        }
+        self.poisoned.retain_mut(|poison| {
+            poison.remaining -= delta;
+            0.0 < poison.remaining
+        });
+        self.stun_immunity = (self.stun_immunity - delta).max(0.0);
+        if self.stunned.as_mut().map_or(false, |stunned| {
+            stunned.remaining -= delta;
+            stunned.remaining <= 0.0
+        }) {
+            self.stunned = None;
+            self.stun_immunity = STUN_IMMUNITY;
+        }
+
+        self.damage_timer -= delta;
+        if 0.0 < self.damage_timer {
+            return None;
+        }
+        self.damage_timer += DAMAGE_TICK;
+        // fractions are kept for the next tick
+        let burning = self.burning_damage.floor();
+        let poison = self.poison_damage.floor();
+        self.burning_damage -= burning;
+        self.poison_damage -= poison;
+        Some((burning as i32, poison as i32))
+    }
+
+    /// Sprite color of the strongest looking effect
+    fn color(&self) -> Option<Color> {
+        if self.is_stunned() {
+            Some(STUNNED_COLOR)
+        } else if self.burning.is_some() {
+            Some(BURNING_COLOR)
+        } else if !self.poisoned.is_empty() {
+            Some(POISONED_COLOR)
+        } else if self.slowed.is_some() {
+            Some(SLOWED_COLOR)
+        } else {
+            None
+        }
+    }
+}
+
+fn apply_status<S: Side>(
+    mut events: EventReader<StatusEvent<S>>,
+    mut enemies: Query<&mut StatusEffects>,
+) {
+    for event in events.iter() {
+        if let Ok(mut status) = enemies.get_mut(event.target) {
+            status.apply(event.effect);
+        }
+    }
+}
+
+/// Advances status effects and deals damage over time
+fn status_update<S: Side>(
+    fixed_time: Res<FixedTime>,
+    mut enemies: Query<(Entity, &mut StatusEffects), With<Enemy<S>>>,
+    mut damage_events: EventWriter<EnemyDamageEvent<S>>,
+) {
+    let delta = fixed_time.period.as_secs_f32();
+    for (entity, mut status) in enemies.iter_mut() {
+        let Some((burning, poison)) = status.tick(delta) else {
+            continue;
+        };
+        if 0 < burning {
+            damage_events.send(EnemyDamageEvent::new(
+                entity,
+                burning,
+                DamageType::Fire,
+                false,
+                DamageSource::Burning,
+            ));
+        }
+        if 0 < poison {
+            damage_events.send(EnemyDamageEvent::new(
+                entity,
+                poison,
+                DamageType::Poison,
+                false,
+                DamageSource::Poison,
+            ));
+        }
+    }
+}
+
+/// Tints enemies by their status effects, elites without them keep the elite tint.
+/// Frozen enemies keep the freeze color.
+fn status_tint(
+    mut enemies: Query<(&StatusEffects, Option<&Elite>, &mut TextureAtlasSprite), Without<Frozen>>,
+) {
+    for (status, elite, mut sprite) in enemies.iter_mut() {
+        let color = status
+            .color()
+            .unwrap_or(elite.map_or(Color::WHITE, |_| ELITE_COLOR));
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ticks the effects for `seconds` in steps exactly
+    /// representable as floats, returns all dealt damage
+    fn total_damage(status: &mut StatusEffects, seconds: f32) -> (i32, i32) {
+        let delta = 0.125;
+        let mut total = (0, 0);
+        for _ in 0..(seconds / delta) as u32 {
+            if let Some((burning, poison)) = status.tick(delta) {
+                total.0 += burning;
+                total.1 += poison;
+            }
+        }
+        total
+    }
+
+    #[test]
+    fn burning_keeps_strongest_power_and_longest_duration() {
+        let mut status = StatusEffects::default();
+        status.apply(StatusEffect::new(StatusKind::Burning, 10.0, 1.0));
+        status.apply(StatusEffect::new(StatusKind::Burning, 4.0, 3.0));
+
+        let burning = status.burning.unwrap();
+        assert_eq!(burning.power, 10.0);
+        assert_eq!(burning.remaining, 3.0);
+    }
+
+    #[test]
+    fn poison_stacks_up_to_the_limit() {
+        let mut status = StatusEffects::default();
+        for duration in 1..=MAX_POISON_STACKS {
+            status.apply(StatusEffect::new(
+                StatusKind::Poisoned,
+                1.0,
+                duration as f32,
+            ));
+        }
+        status.apply(StatusEffect::new(StatusKind::Poisoned, 2.0, 10.0));
+
+        assert_eq!(status.poisoned.len(), MAX_POISON_STACKS);
+        // shortest stack was replaced
+        assert!(status.poisoned.iter().all(|poison| 1.0 < poison.remaining));
+        assert!(status.poisoned.iter().any(|poison| poison.power == 2.0));
+    }
+
+    #[test]
+    fn stun_can_not_be_applied_right_after_it_ends() {
+        let mut status = StatusEffects::default();
+        status.apply(StatusEffect::new(StatusKind::Stunned, 0.0, 1.0));
+        assert!(status.is_stunned());
+
+        total_damage(&mut status, 1.0);
+        assert!(!status.is_stunned());
+        status.apply(StatusEffect::new(StatusKind::Stunned, 0.0, 1.0));
+        assert!(!status.is_stunned());
+
+        total_damage(&mut status, STUN_IMMUNITY);
+        status.apply(StatusEffect::new(StatusKind::Stunned, 0.0, 1.0));
+        assert!(status.is_stunned());
+    }
+
+    #[test]
+    fn damage_over_time_deals_power_times_duration() {
+        let mut status = StatusEffects::default();
+        status.apply(StatusEffect::new(StatusKind::Burning, 10.0, 2.0));
+        status.apply(StatusEffect::new(StatusKind::Poisoned, 3.0, 1.0));
+        status.apply(StatusEffect::new(StatusKind::Poisoned, 3.0, 1.0));
+
+        assert_eq!(total_damage(&mut status, 4.0), (20, 6));
+        assert!(!status.has(StatusKind::Burning));
+        assert!(!status.has(StatusKind::Poisoned));
+    }
+
+    #[test]
+    fn slow_is_limited() {
+        let mut status = StatusEffects::default();
+        status.apply(StatusEffect::new(StatusKind::Slowed, 2.0, 1.0));
+        assert_eq!(status.speed_multiplier(), 1.0 - MAX_SLOW);
+    }
+}
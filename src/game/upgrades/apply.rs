@@ -4,8 +4,10 @@ use bevy::prelude::*;
 
 use crate::{
     game::{
+        abilities::CastleAbilities,
         castle::CastleWall,
         enemies::{spawn::EnemyBuffs, GlobalEnemyBuffs},
+        rng::{self, GameRng},
         weapons::{crossbow::CrossbowBuffs, molotov::MolotovBuffs, GlobalWeaponBuffs},
     },
     GlobalState,
@@ -29,6 +31,7 @@ impl Plugin for ApplyUpgradesPlugin {
             .add_event::<WeaponUpgradeEvent<South>>()
             .add_event::<WeaponUpgradeEvent<West>>()
             .add_event::<WeaponUpgradeEvent<East>>()
+            .add_event::<AbilityUpgradeEvent>()
             .add_event::<GlobalEnemyUpgradeEvent>()
             .add_event::<EnemyUpgradeEvent<North>>()
             .add_event::<EnemyUpgradeEvent<South>>()
@@ -36,11 +39,16 @@ impl Plugin for ApplyUpgradesPlugin {
             .add_event::<EnemyUpgradeEvent<East>>()
             .configure_set(UpgradeSet::Dispatch.before(UpgradeSet::Apply))
             .configure_set(UpgradeSet::Apply.before(UpgradeSet::Finish))
-            .add_system(setup.in_schedule(OnEnter(GlobalState::InGame)))
+            .add_system(
+                setup
+                    .after(rng::setup)
+                    .in_schedule(OnEnter(GlobalState::InGame)),
+            )
             .add_systems(
                 (
                     dispatch_wall_upgrades,
                     dispatch_weapon_upgrades,
+                    dispatch_ability_upgrades,
                     dispatch_enemy_upgrades,
                 )
                     .in_set(UpgradeSet::Dispatch)
@@ -58,6 +66,7 @@ impl Plugin for ApplyUpgradesPlugin {
                     apply_weapon_upgrades_to_side::<South>,
                     apply_weapon_upgrades_to_side::<West>,
                     apply_weapon_upgrades_to_side::<East>,
+                    apply_ability_upgrades,
                     apply_global_enemy_upgrades,
                     apply_enemy_upgrades_to_side::<North>,
                     apply_enemy_upgrades_to_side::<South>,
@@ -76,7 +85,7 @@ impl Plugin for ApplyUpgradesPlugin {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
-enum UpgradeSet {
+pub enum UpgradeSet {
     Dispatch,
     Apply,
     Finish,
@@ -91,6 +100,18 @@ pub enum ApplyUpgradeEvent {
     Fourth = 3,
 }
 
+impl ApplyUpgradeEvent {
+    pub fn from_index(index: usize) -> Option<Self> {
+        match index {
+            0 => Some(Self::First),
+            1 => Some(Self::Second),
+            2 => Some(Self::Third),
+            3 => Some(Self::Fourth),
+            _ => None,
+        }
+    }
+}
+
 pub struct FinishUpgradeEvent;
 
 #[derive(Debug, Clone, Copy)]
@@ -145,6 +166,17 @@ impl<S: Side> WeaponUpgradeEvent<S> {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct AbilityUpgradeEvent {
+    upgrade: AbilityUpgrade,
+}
+
+impl AbilityUpgradeEvent {
+    pub fn new(upgrade: AbilityUpgrade) -> Self {
+        Self { upgrade }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct GlobalEnemyUpgradeEvent {
     upgrade: GlobalEnemyUpgrade,
@@ -171,18 +203,19 @@ impl<S: Side> EnemyUpgradeEvent<S> {
     }
 }
 
-fn setup(mut commands: Commands) {
+fn setup(mut game_rng: ResMut<GameRng>, mut commands: Commands) {
     // generate new upgrades ahead of time
-    commands.insert_resource(genereate_upgrades());
+    commands.insert_resource(genereate_upgrades(&mut game_rng.upgrades));
 }
 
 fn finish_upgrade(
     finish_events: EventReader<FinishUpgradeEvent>,
+    mut game_rng: ResMut<GameRng>,
     mut upgrades: ResMut<Upgrades>,
     mut game_state: ResMut<NextState<GameState>>,
 ) {
     if !finish_events.is_empty() {
-        *upgrades = genereate_upgrades();
+        *upgrades = genereate_upgrades(&mut game_rng.upgrades);
         game_state.set(GameState::InGame);
     }
 }
@@ -255,6 +288,20 @@ fn dispatch_weapon_upgrades(
     }
 }
 
+fn dispatch_ability_upgrades(
+    upgrades: Res<Upgrades>,
+    mut apply_upgrade_events: EventReader<ApplyUpgradeEvent>,
+    mut ability_upgrade_event: EventWriter<AbilityUpgradeEvent>,
+) {
+    for event in apply_upgrade_events.iter() {
+        let upgrade_to_apply = &upgrades.upgrades[*event as usize];
+
+        if let Some(upgrade) = upgrade_to_apply.ability_upgrade {
+            ability_upgrade_event.send(AbilityUpgradeEvent::new(upgrade))
+        }
+    }
+}
+
 fn dispatch_enemy_upgrades(
     upgrades: Res<Upgrades>,
     mut apply_upgrade_events: EventReader<ApplyUpgradeEvent>,
@@ -327,14 +374,21 @@ fn apply_wall_upgrades_to_side<S: Side>(
 ) {
     let mut wall = wall.single_mut();
     for event in wall_upgrade_events.iter() {
-        match event.upgrade {
-            WallUpgrade::AdditionalMaxHp(value) => wall.add_max_hp(value),
-            WallUpgrade::Heal(value) => wall.heal(value),
-        }
+        apply_wall_upgrade(&mut wall, event.upgrade);
         finish_event.send(FinishUpgradeEvent);
     }
 }
 
+/// Applies upgrade to the wall of the side.
+/// Also used by the shop.
+pub fn apply_wall_upgrade<S: Side>(wall: &mut CastleWall<S>, upgrade: WallUpgrade) {
+    match upgrade {
+        WallUpgrade::AdditionalMaxHp(value) => wall.add_max_hp(value),
+        WallUpgrade::Heal(value) => wall.heal(value),
+        WallUpgrade::Armor(value) => wall.armor += value,
+    }
+}
+
 fn apply_global_weapon_upgrades(
     mut global_weapons_buffs: ResMut<GlobalWeaponBuffs>,
     mut global_weapon_upgrade_events: EventReader<GlobalWeaponUpgradeEvent>,
@@ -362,32 +416,67 @@ fn apply_weapon_upgrades_to_side<S: Side>(
     mut finish_event: EventWriter<FinishUpgradeEvent>,
 ) {
     for event in weapon_upgrade_events.iter() {
-        match event.upgrade {
-            WeaponUpgrade::CrossbowDamage(value) => crossbow_buffs.damage += value / 100.0,
-            WeaponUpgrade::CrossbowDamageFlat(value) => crossbow_buffs.damage_flat += value,
-            WeaponUpgrade::CrossbowCritDamage(value) => crossbow_buffs.crit_damage += value / 100.0,
-            WeaponUpgrade::CrossbowCritChance(value) => crossbow_buffs.crit_chance += value / 100.0,
-            WeaponUpgrade::CrossbowRange(value) => crossbow_buffs.range += value / 100.0,
-            WeaponUpgrade::CrossbowAttackSpeed(value) => {
-                crossbow_buffs.attack_speed += value / 100.0
-            }
-            WeaponUpgrade::MolotovDamage(value) => molotov_buffs.damage += value / 100.0,
-            WeaponUpgrade::MolotovDamageFlat(value) => molotov_buffs.damage_flat += value,
-            WeaponUpgrade::MolotovCritDamage(value) => molotov_buffs.crit_damage += value / 100.0,
-            WeaponUpgrade::MolotovCritChance(value) => molotov_buffs.crit_chance += value / 100.0,
-            WeaponUpgrade::MolotovAreaSize(value) => molotov_buffs.area_size += value / 100.0,
-            WeaponUpgrade::MolotovAttackSpeed(value) => molotov_buffs.attack_speed += value / 100.0,
-            WeaponUpgrade::MolotovAreaAttackSpeed(value) => {
-                molotov_buffs.area_attack_speed += value
-            }
-            WeaponUpgrade::MolotovAreaLifespan(value) => {
-                molotov_buffs.area_lifespan += value / 100.0
-            }
-        }
+        apply_weapon_upgrade(&mut crossbow_buffs, &mut molotov_buffs, event.upgrade);
+        finish_event.send(FinishUpgradeEvent);
+    }
+}
+
+/// Applies upgrade to the weapon buffs of the side.
+/// Also used by the shop.
+pub fn apply_weapon_upgrade<S: Side>(
+    crossbow_buffs: &mut CrossbowBuffs<S>,
+    molotov_buffs: &mut MolotovBuffs<S>,
+    upgrade: WeaponUpgrade,
+) {
+    match upgrade {
+        WeaponUpgrade::CrossbowDamage(value) => crossbow_buffs.damage += value / 100.0,
+        WeaponUpgrade::CrossbowDamageFlat(value) => crossbow_buffs.damage_flat += value,
+        WeaponUpgrade::CrossbowCritDamage(value) => crossbow_buffs.crit_damage += value / 100.0,
+        WeaponUpgrade::CrossbowCritChance(value) => crossbow_buffs.crit_chance += value / 100.0,
+        WeaponUpgrade::CrossbowRange(value) => crossbow_buffs.range += value / 100.0,
+        WeaponUpgrade::CrossbowAttackSpeed(value) => crossbow_buffs.attack_speed += value / 100.0,
+        WeaponUpgrade::CrossbowSlowChance(value) => crossbow_buffs.slow_chance += value / 100.0,
+        WeaponUpgrade::CrossbowPoisonChance(value) => crossbow_buffs.poison_chance += value / 100.0,
+        WeaponUpgrade::CrossbowStunChance(value) => crossbow_buffs.stun_chance += value / 100.0,
+        WeaponUpgrade::MolotovDamage(value) => molotov_buffs.damage += value / 100.0,
+        WeaponUpgrade::MolotovDamageFlat(value) => molotov_buffs.damage_flat += value,
+        WeaponUpgrade::MolotovCritDamage(value) => molotov_buffs.crit_damage += value / 100.0,
+        WeaponUpgrade::MolotovCritChance(value) => molotov_buffs.crit_chance += value / 100.0,
+        WeaponUpgrade::MolotovAreaSize(value) => molotov_buffs.area_size += value / 100.0,
+        WeaponUpgrade::MolotovAttackSpeed(value) => molotov_buffs.attack_speed += value / 100.0,
+        WeaponUpgrade::MolotovAreaAttackSpeed(value) => molotov_buffs.area_attack_speed += value,
+        WeaponUpgrade::MolotovAreaLifespan(value) => molotov_buffs.area_lifespan += value / 100.0,
+        WeaponUpgrade::MolotovBurnChance(value) => molotov_buffs.burn_chance += value / 100.0,
+    }
+}
+
+fn apply_ability_upgrades(
+    mut abilities: ResMut<CastleAbilities>,
+    mut ability_upgrade_events: EventReader<AbilityUpgradeEvent>,
+    mut finish_event: EventWriter<FinishUpgradeEvent>,
+) {
+    for event in ability_upgrade_events.iter() {
+        apply_ability_upgrade(&mut abilities, event.upgrade);
         finish_event.send(FinishUpgradeEvent);
     }
 }
 
+/// Applies the percentage bonus of the upgrade to the ability
+pub fn apply_ability_upgrade(abilities: &mut CastleAbilities, upgrade: AbilityUpgrade) {
+    match upgrade {
+        AbilityUpgrade::RepairPower(value) => abilities.repair.power += value / 100.0,
+        AbilityUpgrade::RepairRecharge(value) => abilities.repair.recharge += value / 100.0,
+        AbilityUpgrade::VolleyPower(value) => abilities.volley.power += value / 100.0,
+        AbilityUpgrade::VolleyRecharge(value) => abilities.volley.recharge += value / 100.0,
+        AbilityUpgrade::FreezePower(value) => abilities.freeze.power += value / 100.0,
+        AbilityUpgrade::FreezeRecharge(value) => abilities.freeze.recharge += value / 100.0,
+        AbilityUpgrade::BurningOilPower(value) => abilities.burning_oil.power += value / 100.0,
+        AbilityUpgrade::BurningOilRecharge(value) => {
+            abilities.burning_oil.recharge += value / 100.0
+        }
+    }
+}
+
 fn apply_global_enemy_upgrades(
     mut global_enemy_buffs: ResMut<GlobalEnemyBuffs>,
     mut global_enemy_upgrade_events: EventReader<GlobalEnemyUpgradeEvent>,
@@ -1,8 +1,9 @@
 use std::marker::PhantomData;
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use super::{East, GameState, North, Side, South, West};
+use super::{East, GameState, North, Side, SideId, South, West};
 
 pub mod apply;
 
@@ -25,7 +26,7 @@ macro_rules! random_upgrade {
     };
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum GlobalWallUpgrade {
     AdditionalMaxHp(i32),
     Heal(i32),
@@ -34,6 +35,13 @@ pub enum GlobalWallUpgrade {
 impl GlobalWallUpgrade {
     random_upgrade!(additional_max_hp, AdditionalMaxHp, i32, 10, 90);
     random_upgrade!(heal, Heal, i32, 20, 100);
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AdditionalMaxHp(_) => "walls max hp",
+            Self::Heal(_) => "walls heal",
+        }
+    }
 }
 
 impl std::fmt::Display for GlobalWallUpgrade {
@@ -46,15 +54,25 @@ impl std::fmt::Display for GlobalWallUpgrade {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum WallUpgrade {
     AdditionalMaxHp(i32),
     Heal(i32),
+    Armor(i32),
 }
 
 impl WallUpgrade {
     random_upgrade!(additional_max_hp, AdditionalMaxHp, i32, 20, 120);
     random_upgrade!(heal, Heal, i32, 40, 150);
+    random_upgrade!(armor, Armor, i32, 1, 5);
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AdditionalMaxHp(_) => "wall max hp",
+            Self::Heal(_) => "wall heal",
+            Self::Armor(_) => "wall armor",
+        }
+    }
 }
 
 impl std::fmt::Display for WallUpgrade {
@@ -62,12 +80,13 @@ impl std::fmt::Display for WallUpgrade {
         match self {
             Self::AdditionalMaxHp(value) => f.write_fmt(format_args!("max hx: +{value}"))?,
             Self::Heal(value) => f.write_fmt(format_args!("heal: {value}"))?,
+            Self::Armor(value) => f.write_fmt(format_args!("armor: +{value}"))?,
         }
         Ok(())
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum GlobalWeaponUpgrade {
     Damage(f32),
     DamageFlat(i32),
@@ -80,6 +99,15 @@ impl GlobalWeaponUpgrade {
     random_upgrade!(damage_flat, DamageFlat, i32, 20, 50);
     random_upgrade!(crit_dmamge, CritDamage, f32, 5.0, 20.0);
     random_upgrade!(crit_chance, CritChance, f32, 3.0, 15.0);
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Damage(_) => "weapons damage",
+            Self::DamageFlat(_) => "weapons flat damage",
+            Self::CritDamage(_) => "weapons crit damage",
+            Self::CritChance(_) => "weapons crit chance",
+        }
+    }
 }
 
 impl std::fmt::Display for GlobalWeaponUpgrade {
@@ -94,7 +122,7 @@ impl std::fmt::Display for GlobalWeaponUpgrade {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum WeaponUpgrade {
     CrossbowDamage(f32),
     CrossbowDamageFlat(i32),
@@ -102,6 +130,9 @@ pub enum WeaponUpgrade {
     CrossbowCritChance(f32),
     CrossbowRange(f32),
     CrossbowAttackSpeed(f32),
+    CrossbowSlowChance(f32),
+    CrossbowPoisonChance(f32),
+    CrossbowStunChance(f32),
 
     MolotovDamage(f32),
     MolotovDamageFlat(i32),
@@ -111,6 +142,7 @@ pub enum WeaponUpgrade {
     MolotovAttackSpeed(f32),
     MolotovAreaAttackSpeed(f32),
     MolotovAreaLifespan(f32),
+    MolotovBurnChance(f32),
 }
 
 impl WeaponUpgrade {
@@ -120,6 +152,9 @@ impl WeaponUpgrade {
     random_upgrade!(crossbow_crit_chance, CrossbowCritChance, f32, 5.0, 20.0);
     random_upgrade!(crossbow_range, CrossbowRange, f32, 10.0, 100.0);
     random_upgrade!(crossbow_attack_speed, CrossbowAttackSpeed, f32, 5.0, 50.0);
+    random_upgrade!(crossbow_slow_chance, CrossbowSlowChance, f32, 5.0, 20.0);
+    random_upgrade!(crossbow_poison_chance, CrossbowPoisonChance, f32, 5.0, 20.0);
+    random_upgrade!(crossbow_stun_chance, CrossbowStunChance, f32, 2.0, 8.0);
 
     random_upgrade!(molotov_damage, MolotovDamage, f32, 3.0, 20.0);
     random_upgrade!(molotov_damage_flat, MolotovDamageFlat, i32, 5, 50);
@@ -135,6 +170,30 @@ impl WeaponUpgrade {
         30.0
     );
     random_upgrade!(molotov_area_lifespan, MolotovAreaLifespan, f32, 10.0, 30.0);
+    random_upgrade!(molotov_burn_chance, MolotovBurnChance, f32, 10.0, 25.0);
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::CrossbowDamage(_) => "crossbow damage",
+            Self::CrossbowDamageFlat(_) => "crossbow flat damage",
+            Self::CrossbowCritDamage(_) => "crossbow crit damage",
+            Self::CrossbowCritChance(_) => "crossbow crit chance",
+            Self::CrossbowRange(_) => "crossbow range",
+            Self::CrossbowAttackSpeed(_) => "crossbow attack speed",
+            Self::CrossbowSlowChance(_) => "crossbow slow chance",
+            Self::CrossbowPoisonChance(_) => "crossbow poison chance",
+            Self::CrossbowStunChance(_) => "crossbow stun chance",
+            Self::MolotovDamage(_) => "molotov damage",
+            Self::MolotovDamageFlat(_) => "molotov flat damage",
+            Self::MolotovCritDamage(_) => "molotov crit damage",
+            Self::MolotovCritChance(_) => "molotov crit chance",
+            Self::MolotovAreaSize(_) => "molotov area size",
+            Self::MolotovAttackSpeed(_) => "molotov attack speed",
+            Self::MolotovAreaAttackSpeed(_) => "molotov area attack speed",
+            Self::MolotovAreaLifespan(_) => "molotov area lifespan",
+            Self::MolotovBurnChance(_) => "molotov burn chance",
+        }
+    }
 }
 
 #[rustfmt::skip]
@@ -147,6 +206,9 @@ impl std::fmt::Display for WeaponUpgrade {
             Self::CrossbowCritChance(value) => f.write_fmt(format_args!("crossbow crit chance: +{value:.1}%"))?,
             Self::CrossbowRange(value) => f.write_fmt(format_args!("crossbow range: +{value:.1}%"))?,
             Self::CrossbowAttackSpeed(value) => f.write_fmt(format_args!("crossbow attack speed: +{value:.1}%"))?,
+            Self::CrossbowSlowChance(value) => f.write_fmt(format_args!("crossbow slow chance: +{value:.1}%"))?,
+            Self::CrossbowPoisonChance(value) => f.write_fmt(format_args!("crossbow poison chance: +{value:.1}%"))?,
+            Self::CrossbowStunChance(value) => f.write_fmt(format_args!("crossbow stun chance: +{value:.1}%"))?,
             Self::MolotovDamage(value) => f.write_fmt(format_args!("molotov damage: +{value:.1}%"))?,
             Self::MolotovDamageFlat(value) => f.write_fmt(format_args!("molotov damage: +{value}"))?,
             Self::MolotovCritDamage(value) => f.write_fmt(format_args!("molotov crit damage: +{value:.1}%"))?,
@@ -155,12 +217,66 @@ impl std::fmt::Display for WeaponUpgrade {
             Self::MolotovAttackSpeed(value) => f.write_fmt(format_args!("molotov attack speed: +{value:.1}%"))?,
             Self::MolotovAreaAttackSpeed(value) => f.write_fmt(format_args!("molotov area attack speed: +{value:.1}%"))?,
             Self::MolotovAreaLifespan(value) => f.write_fmt(format_args!("molotov area lifespan: +{value:.1}%"))?,
+            Self::MolotovBurnChance(value) => f.write_fmt(format_args!("molotov burn chance: +{value:.1}%"))?,
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum AbilityUpgrade {
+    RepairPower(f32),
+    RepairRecharge(f32),
+    VolleyPower(f32),
+    VolleyRecharge(f32),
+    FreezePower(f32),
+    FreezeRecharge(f32),
+    BurningOilPower(f32),
+    BurningOilRecharge(f32),
+}
+
+impl AbilityUpgrade {
+    random_upgrade!(repair_power, RepairPower, f32, 10.0, 40.0);
+    random_upgrade!(repair_recharge, RepairRecharge, f32, 5.0, 25.0);
+    random_upgrade!(volley_power, VolleyPower, f32, 10.0, 40.0);
+    random_upgrade!(volley_recharge, VolleyRecharge, f32, 5.0, 25.0);
+    random_upgrade!(freeze_power, FreezePower, f32, 5.0, 25.0);
+    random_upgrade!(freeze_recharge, FreezeRecharge, f32, 5.0, 25.0);
+    random_upgrade!(burning_oil_power, BurningOilPower, f32, 10.0, 40.0);
+    random_upgrade!(burning_oil_recharge, BurningOilRecharge, f32, 5.0, 25.0);
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::RepairPower(_) => "repair heal",
+            Self::RepairRecharge(_) => "repair recharge",
+            Self::VolleyPower(_) => "volley damage",
+            Self::VolleyRecharge(_) => "volley recharge",
+            Self::FreezePower(_) => "freeze duration",
+            Self::FreezeRecharge(_) => "freeze recharge",
+            Self::BurningOilPower(_) => "burning oil damage",
+            Self::BurningOilRecharge(_) => "burning oil recharge",
+        }
+    }
+}
+
+#[rustfmt::skip]
+impl std::fmt::Display for AbilityUpgrade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RepairPower(value) => f.write_fmt(format_args!("repair heal: +{value:.1}%"))?,
+            Self::RepairRecharge(value) => f.write_fmt(format_args!("repair recharge: +{value:.1}%"))?,
+            Self::VolleyPower(value) => f.write_fmt(format_args!("volley damage: +{value:.1}%"))?,
+            Self::VolleyRecharge(value) => f.write_fmt(format_args!("volley recharge: +{value:.1}%"))?,
+            Self::FreezePower(value) => f.write_fmt(format_args!("freeze duration: +{value:.1}%"))?,
+            Self::FreezeRecharge(value) => f.write_fmt(format_args!("freeze recharge: +{value:.1}%"))?,
+            Self::BurningOilPower(value) => f.write_fmt(format_args!("burning oil damage: +{value:.1}%"))?,
+            Self::BurningOilRecharge(value) => f.write_fmt(format_args!("burning oil recharge: +{value:.1}%"))?,
         }
         Ok(())
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum GlobalEnemyUpgrade {
     Health(f32),
     Speed(f32),
@@ -190,7 +306,7 @@ impl std::fmt::Display for GlobalEnemyUpgrade {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum EnemyUpgrade {
     Health(f32),
     Speed(f32),
@@ -220,7 +336,7 @@ impl EnemyUpgrade {
     random_upgrade!(attack_speed, AttackSpeed, f32, 1.0, 10.0);
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum UpgradeSide<U> {
     North(U),
     South(U),
@@ -228,12 +344,13 @@ pub enum UpgradeSide<U> {
     East(U),
 }
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 pub struct Upgrade {
     pub global_wall_upgrade: Option<GlobalWallUpgrade>,
     pub wall_upgrade: Option<UpgradeSide<WallUpgrade>>,
     pub global_weapon_upgrade: Option<GlobalWeaponUpgrade>,
     pub weapon_upgrade: Option<UpgradeSide<WeaponUpgrade>>,
+    pub ability_upgrade: Option<AbilityUpgrade>,
     pub global_enemy_upgrade: Option<GlobalEnemyUpgrade>,
     pub enemy_upgrade: Option<UpgradeSide<EnemyUpgrade>>,
 }
@@ -241,6 +358,7 @@ pub struct Upgrade {
 pub struct GlobalBuffs {
     pub wall_upgrade: Option<GlobalWallUpgrade>,
     pub weapon_upgrade: Option<GlobalWeaponUpgrade>,
+    pub ability_upgrade: Option<AbilityUpgrade>,
 }
 
 impl std::fmt::Display for GlobalBuffs {
@@ -251,6 +369,9 @@ impl std::fmt::Display for GlobalBuffs {
         if let Some(upgrade) = self.weapon_upgrade {
             f.write_fmt(format_args!("{upgrade}\n"))?;
         }
+        if let Some(upgrade) = self.ability_upgrade {
+            f.write_fmt(format_args!("{upgrade}\n"))?;
+        }
         Ok(())
     }
 }
@@ -331,10 +452,45 @@ macro_rules! get_side {
     };
 }
 
+impl<U> UpgradeSide<U> {
+    pub fn new(side: SideId, upgrade: U) -> Self {
+        match side {
+            SideId::North => UpgradeSide::North(upgrade),
+            SideId::South => UpgradeSide::South(upgrade),
+            SideId::West => UpgradeSide::West(upgrade),
+            SideId::East => UpgradeSide::East(upgrade),
+        }
+    }
+
+    pub fn upgrade(&self) -> &U {
+        match self {
+            UpgradeSide::North(upgrade)
+            | UpgradeSide::South(upgrade)
+            | UpgradeSide::West(upgrade)
+            | UpgradeSide::East(upgrade) => upgrade,
+        }
+    }
+}
+
 impl Upgrade {
+    /// Names of all buffs of the upgrade regardless of the side
+    pub fn buff_names(&self) -> Vec<&'static str> {
+        [
+            self.global_wall_upgrade.map(|upgrade| upgrade.name()),
+            self.wall_upgrade.map(|upgrade| upgrade.upgrade().name()),
+            self.global_weapon_upgrade.map(|upgrade| upgrade.name()),
+            self.weapon_upgrade.map(|upgrade| upgrade.upgrade().name()),
+            self.ability_upgrade.map(|upgrade| upgrade.name()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
     pub fn has_global_upgrades(&self) -> bool {
         self.global_wall_upgrade.is_some()
             || self.global_weapon_upgrade.is_some()
+            || self.ability_upgrade.is_some()
             || self.global_enemy_upgrade.is_some()
     }
 
@@ -343,6 +499,7 @@ impl Upgrade {
             GlobalBuffs {
                 wall_upgrade: self.global_wall_upgrade,
                 weapon_upgrade: self.global_weapon_upgrade,
+                ability_upgrade: self.ability_upgrade,
             },
             GlobalDebuffs {
                 enemy_upgrade: self.global_enemy_upgrade,
@@ -462,6 +619,10 @@ impl std::fmt::Display for Upgrade {
             }
         }
 
+        if let Some(upgrade) = self.ability_upgrade {
+            f.write_fmt(format_args!("Castle abilities:\n\t{upgrade}\n"))?;
+        }
+
         if let Some(upgrade) = self.global_enemy_upgrade {
             f.write_fmt(format_args!("Global enemies:\n\t{upgrade}\n"))?;
         }
@@ -485,19 +646,18 @@ impl std::fmt::Display for Upgrade {
     }
 }
 
-#[derive(Debug, Default, Resource)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
 pub struct Upgrades {
     pub upgrades: [Upgrade; 4],
 }
 
-pub fn genereate_upgrades() -> Upgrades {
-    let mut rng = rand::thread_rng();
+pub fn genereate_upgrades(rng: &mut impl rand::Rng) -> Upgrades {
     Upgrades {
         upgrades: [
-            genereate_upgrade(&mut rng),
-            genereate_upgrade(&mut rng),
-            genereate_upgrade(&mut rng),
-            genereate_upgrade(&mut rng),
+            genereate_upgrade(rng),
+            genereate_upgrade(rng),
+            genereate_upgrade(rng),
+            genereate_upgrade(rng),
         ],
     }
 }
@@ -514,11 +674,7 @@ pub fn genereate_upgrade(mut rng: &mut impl rand::Rng) -> Upgrade {
             (Some(upgrade), None, false)
         } else {
             // one side wall
-            let upgrade = match rng.gen_range(0..2) {
-                0 => WallUpgrade::additional_max_hp(&mut rng),
-                1 => WallUpgrade::heal(&mut rng),
-                _ => unreachable!(),
-            };
+            let upgrade = genereate_wall_upgrade(rng);
 
             let upgrade = match rng.gen_range(0..4) {
                 0 => UpgradeSide::North(upgrade),
@@ -546,24 +702,7 @@ pub fn genereate_upgrade(mut rng: &mut impl rand::Rng) -> Upgrade {
             (Some(upgrade), None)
         } else {
             // one side wall
-            let upgrade = match rng.gen_range(0..20) {
-                0 => WeaponUpgrade::crossbow_damage(&mut rng),
-                1 => WeaponUpgrade::crossbow_damage_flat(&mut rng),
-                2 => WeaponUpgrade::crossbow_crit_damage(&mut rng),
-                3 => WeaponUpgrade::crossbow_crit_chance(&mut rng),
-                4 => WeaponUpgrade::crossbow_range(&mut rng),
-                5 | 6 | 7 | 8 => WeaponUpgrade::crossbow_attack_speed(&mut rng),
-
-                9 => WeaponUpgrade::molotov_damage(&mut rng),
-                10 => WeaponUpgrade::molotov_damage_flat(&mut rng),
-                11 => WeaponUpgrade::molotov_crit_damage(&mut rng),
-                12 => WeaponUpgrade::molotov_crit_chance(&mut rng),
-                13 => WeaponUpgrade::molotov_area_size(&mut rng),
-                14 | 15 | 16 | 17 => WeaponUpgrade::molotov_attack_speed(&mut rng),
-                18 => WeaponUpgrade::molotov_area_attack_speed(&mut rng),
-                19 => WeaponUpgrade::molotov_area_lifespan(&mut rng),
-                _ => unreachable!(),
-            };
+            let upgrade = genereate_weapon_upgrade(rng);
 
             let upgrade = match rng.gen_range(0..4) {
                 0 => UpgradeSide::North(upgrade),
@@ -578,6 +717,24 @@ pub fn genereate_upgrade(mut rng: &mut impl rand::Rng) -> Upgrade {
         (None, None)
     };
 
+    // ability
+    let ability_upgrade = if rng.gen_ratio(1, 10) {
+        let upgrade = match rng.gen_range(0..8) {
+            0 => AbilityUpgrade::repair_power(&mut rng),
+            1 => AbilityUpgrade::repair_recharge(&mut rng),
+            2 => AbilityUpgrade::volley_power(&mut rng),
+            3 => AbilityUpgrade::volley_recharge(&mut rng),
+            4 => AbilityUpgrade::freeze_power(&mut rng),
+            5 => AbilityUpgrade::freeze_recharge(&mut rng),
+            6 => AbilityUpgrade::burning_oil_power(&mut rng),
+            7 => AbilityUpgrade::burning_oil_recharge(&mut rng),
+            _ => unreachable!(),
+        };
+        Some(upgrade)
+    } else {
+        None
+    };
+
     // enemy
     let (global_enemy_upgrade, enemy_upgrade) = if rng.gen_ratio(99, 100) {
         if rng.gen_ratio(3, 10) {
@@ -619,7 +776,45 @@ pub fn genereate_upgrade(mut rng: &mut impl rand::Rng) -> Upgrade {
         wall_upgrade,
         global_weapon_upgrade,
         weapon_upgrade,
+        ability_upgrade,
         global_enemy_upgrade,
         enemy_upgrade,
     }
 }
+
+/// Upgrade of a single wall
+pub fn genereate_wall_upgrade(mut rng: &mut impl rand::Rng) -> WallUpgrade {
+    match rng.gen_range(0..3) {
+        0 => WallUpgrade::additional_max_hp(&mut rng),
+        1 => WallUpgrade::heal(&mut rng),
+        2 => WallUpgrade::armor(&mut rng),
+        _ => unreachable!(),
+    }
+}
+
+/// Upgrade of the weapons of a single side
+pub fn genereate_weapon_upgrade(mut rng: &mut impl rand::Rng) -> WeaponUpgrade {
+    match rng.gen_range(0..24) {
+        0 => WeaponUpgrade::crossbow_damage(&mut rng),
+        1 => WeaponUpgrade::crossbow_damage_flat(&mut rng),
+        2 => WeaponUpgrade::crossbow_crit_damage(&mut rng),
+        3 => WeaponUpgrade::crossbow_crit_chance(&mut rng),
+        4 => WeaponUpgrade::crossbow_range(&mut rng),
+        5 | 6 | 7 | 8 => WeaponUpgrade::crossbow_attack_speed(&mut rng),
+
+        9 => WeaponUpgrade::molotov_damage(&mut rng),
+        10 => WeaponUpgrade::molotov_damage_flat(&mut rng),
+        11 => WeaponUpgrade::molotov_crit_damage(&mut rng),
+        12 => WeaponUpgrade::molotov_crit_chance(&mut rng),
+        13 => WeaponUpgrade::molotov_area_size(&mut rng),
+        14 | 15 | 16 | 17 => WeaponUpgrade::molotov_attack_speed(&mut rng),
+        18 => WeaponUpgrade::molotov_area_attack_speed(&mut rng),
+        19 => WeaponUpgrade::molotov_area_lifespan(&mut rng),
+
+        20 => WeaponUpgrade::crossbow_slow_chance(&mut rng),
+        21 => WeaponUpgrade::crossbow_poison_chance(&mut rng),
+        22 => WeaponUpgrade::crossbow_stun_chance(&mut rng),
+        23 => WeaponUpgrade::molotov_burn_chance(&mut rng),
+        _ => unreachable!(),
+    }
+}
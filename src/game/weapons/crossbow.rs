@@ -1,26 +1,40 @@
 use std::marker::PhantomData;
 
 use bevy::prelude::*;
-use bevy_kira_audio::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     game::{
-        damage::projectile::ProjectileBundle, enemies::Enemy, East, GameState, North, Side, South,
-        West,
+        damage::projectile::{EnemyProjectile, ProjectileBundle},
+        enemies::{Enemy, Movement},
+        loot::DamageBoost,
+        simulation::SimulationSet,
+        status::{StatusChance, StatusEffect, StatusKind},
+        East, North, Side, South, West,
     },
     utils::remove_all_with,
-    GameAssets, GameSettings, GlobalState,
+    GlobalState,
 };
 
-use super::{GlobalWeaponBuffs, WeaponsAssets};
+use super::{GlobalWeaponBuffs, WeaponSoundEvent, WeaponsAssets};
 
-const DEFAULT_BOLT_SIZE: f32 = 3.0;
+pub const DEFAULT_BOLT_SIZE: f32 = 3.0;
 
 const DEFAULT_CROSSBOW_DAMAGE: i32 = 20;
 const DEFAULT_CROSSBOW_CRIT_DAMAGE: f32 = 2.0;
 const DEFAULT_CROSSBOW_CRIT_CHANCE: f32 = 0.05;
 const DEFAULT_CROSSBOW_RANGE: f32 = 400.0;
 const DEFAULT_CROSSBOW_ATTACK_SPEED: f32 = 1.0;
+const DEFAULT_CROSSBOW_ANTI_AIR: bool = true;
+
+/// Part of the speed removed by slowing bolts
+const CROSSBOW_SLOW_POWER: f32 = 0.4;
+const CROSSBOW_SLOW_DURATION: f32 = 2.0;
+/// Poison damage per second as a part of bolt damage
+const CROSSBOW_POISON_POWER: f32 = 0.25;
+const CROSSBOW_POISON_DURATION: f32 = 4.0;
+const CROSSBOW_STUN_DURATION: f32 = 0.5;
 
 /// Offsets arrow spawn point in the enemy direction
 const DEFAULT_BOLT_SPAWN_OFFSET: f32 = 30.0;
@@ -37,7 +51,8 @@ impl Plugin for CrossbowPlugin {
                     crossbow_attack::<West>,
                     crossbow_attack::<East>,
                 )
-                    .in_set(OnUpdate(GameState::InGame)),
+                    .in_set(SimulationSet::Act)
+                    .in_schedule(CoreSchedule::FixedUpdate),
             )
             .add_system(remove_all_with::<CrossbowMarker>.in_schedule(OnExit(GlobalState::InGame)));
     }
@@ -46,7 +61,8 @@ impl Plugin for CrossbowPlugin {
 #[derive(Component)]
 pub struct CrossbowMarker;
 
-#[derive(Debug, Default, Resource)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
+#[serde(bound = "")]
 pub struct CrossbowBuffs<S: Side> {
     pub damage: f32,
     pub damage_flat: i32,
@@ -54,16 +70,27 @@ pub struct CrossbowBuffs<S: Side> {
     pub crit_chance: f32,
     pub range: f32,
     pub attack_speed: f32,
+    pub slow_chance: f32,
+    pub poison_chance: f32,
+    pub stun_chance: f32,
+    #[serde(skip)]
     _phantom: PhantomData<S>,
 }
 
-#[derive(Component)]
+#[derive(Clone, Serialize, Deserialize, Component)]
+#[serde(bound = "")]
 pub struct Crossbow<S: Side> {
     damage: i32,
     range: f32,
     crit_damage: f32,
     crit_chance: f32,
+    /// Can shoot flying enemies
+    anti_air: bool,
+    slow_chance: f32,
+    poison_chance: f32,
+    stun_chance: f32,
     attack_timer: Timer,
+    #[serde(skip)]
     _phantom: PhantomData<S>,
 }
 
@@ -83,6 +110,27 @@ impl<S: Side> std::fmt::Display for Crossbow<S> {
             "attack speed {:.1}/s\n",
             self.attack_timer.duration().as_secs_f32()
         ))?;
+        if self.anti_air {
+            f.write_str("anti-air\n")?;
+        }
+        if 0.0 < self.slow_chance {
+            f.write_fmt(format_args!(
+                "slow chance {:.1}%\n",
+                self.slow_chance * 100.0
+            ))?;
+        }
+        if 0.0 < self.poison_chance {
+            f.write_fmt(format_args!(
+                "poison chance {:.1}%\n",
+                self.poison_chance * 100.0
+            ))?;
+        }
+        if 0.0 < self.stun_chance {
+            f.write_fmt(format_args!(
+                "stun chance {:.1}%\n",
+                self.stun_chance * 100.0
+            ))?;
+        }
         Ok(())
     }
 }
@@ -94,6 +142,10 @@ impl<S: Side> Default for Crossbow<S> {
             range: DEFAULT_CROSSBOW_RANGE,
             crit_damage: DEFAULT_CROSSBOW_CRIT_DAMAGE,
             crit_chance: DEFAULT_CROSSBOW_CRIT_CHANCE,
+            anti_air: DEFAULT_CROSSBOW_ANTI_AIR,
+            slow_chance: 0.0,
+            poison_chance: 0.0,
+            stun_chance: 0.0,
             attack_timer: Timer::from_seconds(
                 1.0 / DEFAULT_CROSSBOW_ATTACK_SPEED,
                 TimerMode::Repeating,
@@ -121,6 +173,10 @@ impl<S: Side> Crossbow<S> {
             crit_chance: self.crit_chance
                 + crossbow_buffs.crit_chance
                 + global_weapons_buffs.crit_chance,
+            anti_air: self.anti_air,
+            slow_chance: self.slow_chance + crossbow_buffs.slow_chance,
+            poison_chance: self.poison_chance + crossbow_buffs.poison_chance,
+            stun_chance: self.stun_chance + crossbow_buffs.stun_chance,
             attack_timer: Timer::from_seconds(
                 DEFAULT_CROSSBOW_ATTACK_SPEED * (1.0 + crossbow_buffs.attack_speed),
                 TimerMode::Repeating,
@@ -153,19 +209,19 @@ fn setup(mut commands: Commands) {
 }
 
 fn crossbow_attack<S: Side>(
-    time: Res<Time>,
-    audio: Res<Audio>,
-    game_assets: Res<GameAssets>,
-    game_settings: Res<GameSettings>,
+    fixed_time: Res<FixedTime>,
     weapon_assets: Res<WeaponsAssets>,
     crossbow_buffs: Res<CrossbowBuffs<S>>,
     global_weapons_buffs: Res<GlobalWeaponBuffs>,
-    enemies: Query<&Transform, With<Enemy<S>>>,
+    damage_boost: Res<DamageBoost>,
+    enemies: Query<(&Transform, &Movement), With<Enemy<S>>>,
+    enemy_projectiles: Query<(&Transform, &Velocity, &EnemyProjectile<S>)>,
     mut commands: Commands,
+    mut sound_events: EventWriter<WeaponSoundEvent>,
     mut crossbows: Query<(&Transform, &mut Crossbow<S>)>,
 ) {
     for (transform, mut crossbow) in crossbows.iter_mut() {
-        if !crossbow.attack_timer.tick(time.delta()).finished() {
+        if !crossbow.attack_timer.tick(fixed_time.period).finished() {
             continue;
         }
 
@@ -173,10 +229,26 @@ fn crossbow_attack<S: Side>(
 
         crossbow.attack_timer = Timer::from_seconds(1.0 / attack_speed, TimerMode::Repeating);
 
+        // bolt reaches the target in one attack period,
+        // so moving projectiles are aimed ahead of them
+        let anti_air = crossbow.anti_air;
+        let targets = enemies
+            .iter()
+            .filter(|(_, movement)| anti_air || **movement != Movement::Flying)
+            .map(|(enemy_transform, _)| enemy_transform.translation.truncate())
+            .chain(
+                enemy_projectiles
+                    .iter()
+                    .filter(|(_, _, projectile)| projectile.interceptable)
+                    .map(|(projectile_transform, velocity, _)| {
+                        projectile_transform.translation.truncate() + velocity.linvel / attack_speed
+                    }),
+            );
+
         let mut enemy_vec = Vec2::default();
         let mut min_range = crossbow.range;
-        for enemy_transform in enemies.iter() {
-            let vec = (enemy_transform.translation - transform.translation).truncate();
+        for target in targets {
+            let vec = target - transform.translation.truncate();
             let distance = vec.length();
             if distance < min_range {
                 min_range = distance;
@@ -201,16 +273,42 @@ fn crossbow_attack<S: Side>(
         let arrow_direction = Vec2::NEG_X;
         projectile_transform.rotate_z(-direction.angle_between(arrow_direction));
 
-        let damage =
-            ((crossbow.damage + crossbow_buffs.damage_flat + global_weapons_buffs.damage_flat)
-                as f32
-                * (1.0 + crossbow_buffs.damage + global_weapons_buffs.damage)) as i32;
+        let damage = ((crossbow.damage
+            + crossbow_buffs.damage_flat
+            + global_weapons_buffs.damage_flat) as f32
+            * (1.0 + crossbow_buffs.damage + global_weapons_buffs.damage + damage_boost.damage()))
+            as i32;
         let crit_chance =
             crossbow.crit_chance + crossbow_buffs.crit_chance + global_weapons_buffs.crit_chance;
         let crit_damage = (damage as f32
             * (crossbow.crit_damage
                 + crossbow_buffs.crit_damage
                 + global_weapons_buffs.crit_damage)) as i32;
+        let effects = [
+            StatusChance::new(
+                StatusEffect::new(
+                    StatusKind::Slowed,
+                    CROSSBOW_SLOW_POWER,
+                    CROSSBOW_SLOW_DURATION,
+                ),
+                crossbow.slow_chance + crossbow_buffs.slow_chance,
+            ),
+            StatusChance::new(
+                StatusEffect::new(
+                    StatusKind::Poisoned,
+                    damage as f32 * CROSSBOW_POISON_POWER,
+                    CROSSBOW_POISON_DURATION,
+                ),
+                crossbow.poison_chance + crossbow_buffs.poison_chance,
+            ),
+            StatusChance::new(
+                StatusEffect::new(StatusKind::Stunned, 0.0, CROSSBOW_STUN_DURATION),
+                crossbow.stun_chance + crossbow_buffs.stun_chance,
+            ),
+        ]
+        .into_iter()
+        .filter(|effect| 0.0 < effect.chance)
+        .collect();
 
         commands.spawn(ProjectileBundle::<S>::new(
             weapon_assets.arrow.clone(),
@@ -218,13 +316,13 @@ fn crossbow_attack<S: Side>(
             damage,
             crit_damage,
             crit_chance,
+            crossbow.anti_air,
+            effects,
             arrow_speed,
             direction,
             projectile_transform,
         ));
 
-        audio
-            .play(game_assets.crossbow_shoot.clone())
-            .with_volume(game_settings.sound_volume);
+        sound_events.send(WeaponSoundEvent::CrossbowShoot);
     }
 }
@@ -1,8 +1,11 @@
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::GlobalState;
 
+use super::castle_type::CastleType;
+
 pub struct WeaponsPlugin;
 
 pub mod crossbow;
@@ -10,25 +13,32 @@ pub mod molotov;
 
 impl Plugin for WeaponsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_collection_to_loading_state::<_, WeaponsAssets>(GlobalState::AssetLoading)
+        app.add_event::<WeaponSoundEvent>()
             .add_system(setup.in_schedule(OnEnter(GlobalState::InGame)))
             .add_plugin(crossbow::CrossbowPlugin)
             .add_plugin(molotov::MolotovPlugin);
     }
 }
 
-#[derive(AssetCollection, Resource)]
-struct WeaponsAssets {
+#[derive(Default, AssetCollection, Resource)]
+pub struct WeaponsAssets {
     #[asset(path = "sprites/arrow.png")]
-    arrow: Handle<Image>,
+    pub arrow: Handle<Image>,
     #[asset(path = "sprites/molotov.png")]
-    molotov: Handle<Image>,
+    pub molotov: Handle<Image>,
     #[asset(texture_atlas(tile_size_x = 32.0, tile_size_y = 32.0, columns = 3, rows = 1,))]
     #[asset(path = "sprites/fire.png")]
     pub fire: Handle<TextureAtlas>,
 }
 
-#[derive(Debug, Default, Resource)]
+/// Event to play weapon sound
+#[derive(Debug, Clone, Copy)]
+pub enum WeaponSoundEvent {
+    CrossbowShoot,
+    MolotovExplosion,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
 pub struct GlobalWeaponBuffs {
     pub damage: f32,
     pub damage_flat: i32,
@@ -36,6 +46,6 @@ pub struct GlobalWeaponBuffs {
     pub crit_chance: f32,
 }
 
-fn setup(mut commands: Commands) {
-    commands.insert_resource(GlobalWeaponBuffs::default());
+fn setup(castle_type: Res<CastleType>, mut commands: Commands) {
+    commands.insert_resource(castle_type.definition().weapon_buffs);
 }
@@ -1,20 +1,27 @@
 use std::marker::PhantomData;
 
 use bevy::prelude::*;
-use bevy_kira_audio::prelude::*;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     game::{
         castle::CastleWall,
-        damage::area::{DamageArea, DamageAreaBundle},
-        East, GameState, North, Side, South, West,
+        damage::{
+            area::{DamageArea, DamageAreaBundle},
+            DamageSource, DamageType,
+        },
+        loot::DamageBoost,
+        rng::SideRng,
+        simulation::{Interpolated, SimulationSet},
+        status::{StatusChance, StatusEffect, StatusKind},
+        East, North, Side, South, West,
     },
     utils::remove_all_with,
-    GameAssets, GameSettings, GlobalState,
+    GlobalState,
 };
 
-use super::{GlobalWeaponBuffs, WeaponsAssets};
+use super::{GlobalWeaponBuffs, WeaponSoundEvent, WeaponsAssets};
 
 const DEFAULT_AREA_SIZE: f32 = 20.0;
 const DEFAULT_AREA_DAMAGE: i32 = 10;
@@ -26,8 +33,11 @@ const DEFAULT_MOLOTOV_RANGE: f32 = 300.0;
 const DEFAULT_MOLOTOV_ATTACK_SPEED: f32 = 0.3;
 const DEFAULT_MOLOTOV_BOTTLE_IN_FLIGHT_TIME: f32 = 2.0;
 const DEFAULT_MOLOTOV_BOTTLE_IN_FLIGHT_ROTATION: f32 = std::f32::consts::PI * 5.0;
+const DEFAULT_MOLOTOV_BURN_CHANCE: f32 = 0.5;
 
-const MOLOTOV_SFX_MULTIPLIER: f64 = 0.2;
+/// Burning damage per second as a part of area damage
+const MOLOTOV_BURN_POWER: f32 = 0.5;
+const MOLOTOV_BURN_DURATION: f32 = 3.0;
 
 pub struct MolotovPlugin;
 
@@ -45,7 +55,8 @@ impl Plugin for MolotovPlugin {
                     molotov_bottle_update::<West>,
                     molotov_bottle_update::<East>,
                 )
-                    .in_set(OnUpdate(GameState::InGame)),
+                    .in_set(SimulationSet::Act)
+                    .in_schedule(CoreSchedule::FixedUpdate),
             )
             .add_system(remove_all_with::<MolotovMarker>.in_schedule(OnExit(GlobalState::InGame)));
     }
@@ -54,7 +65,8 @@ impl Plugin for MolotovPlugin {
 #[derive(Component)]
 pub struct MolotovMarker;
 
-#[derive(Default, Resource)]
+#[derive(Default, Clone, Serialize, Deserialize, Resource)]
+#[serde(bound = "")]
 pub struct MolotovBuffs<S: Side> {
     pub damage: f32,
     pub damage_flat: i32,
@@ -64,17 +76,22 @@ pub struct MolotovBuffs<S: Side> {
     pub attack_speed: f32,
     pub area_attack_speed: f32,
     pub area_lifespan: f32,
+    pub burn_chance: f32,
+    #[serde(skip)]
     _phatom: PhantomData<S>,
 }
 
-#[derive(Component)]
+#[derive(Clone, Serialize, Deserialize, Component)]
+#[serde(bound = "")]
 pub struct Molotov<S: Side> {
     damage: i32,
     range: f32,
     area_size: f32,
     area_attack_speed: f32,
     area_lifespan: f32,
+    burn_chance: f32,
     attack_timer: Timer,
+    #[serde(skip)]
     _phantom: PhantomData<S>,
 }
 
@@ -88,6 +105,10 @@ impl<S: Side> std::fmt::Display for Molotov<S> {
             self.area_attack_speed
         ))?;
         f.write_fmt(format_args!("area lifespan {:.1}s\n", self.area_lifespan))?;
+        f.write_fmt(format_args!(
+            "burn chance {:.1}%\n",
+            self.burn_chance * 100.0
+        ))?;
         f.write_fmt(format_args!(
             "attack speed {:.1}/s\n",
             self.attack_timer.duration().as_secs_f32()
@@ -104,13 +125,15 @@ impl<S: Side> Default for Molotov<S> {
             area_size: DEFAULT_AREA_SIZE,
             area_attack_speed: 1.0 / DEFAULT_AREA_ATTACK_SPEED,
             area_lifespan: DEFAULT_AREA_LIFESPAN,
+            burn_chance: DEFAULT_MOLOTOV_BURN_CHANCE,
             attack_timer: Timer::from_seconds(DEFAULT_MOLOTOV_ATTACK_SPEED, TimerMode::Repeating),
             _phantom: PhantomData,
         }
     }
 }
 
-#[derive(Component)]
+#[derive(Clone, Serialize, Deserialize, Component)]
+#[serde(bound = "")]
 pub struct MolotovBottle<S: Side> {
     area: DamageArea<S>,
     rotation: f32,
@@ -123,6 +146,7 @@ pub struct MolotovBottleBundle<S: Side> {
     #[bundle]
     sprite: SpriteBundle,
     bottle: MolotovBottle<S>,
+    interpolated: Interpolated,
     marker: MolotovMarker,
 }
 
@@ -135,21 +159,13 @@ impl<S: Side> MolotovBottleBundle<S> {
         crit_chance: f32,
         attack_speed: f32,
         lifespan: f32,
+        effects: Vec<StatusChance>,
         area_position: Vec3,
         initial_position: Vec3,
     ) -> Self {
-        Self {
-            sprite: SpriteBundle {
-                sprite: Sprite {
-                    // Flip on East and North
-                    flip_y: S::DIRECTION.x < 0.0 || S::DIRECTION.y > 0.0,
-                    ..default()
-                },
-                texture,
-                transform: Transform::from_translation(initial_position),
-                ..default()
-            },
-            bottle: MolotovBottle {
+        Self::from_bottle(
+            texture,
+            MolotovBottle {
                 area: DamageArea::new(
                     area_size,
                     damage,
@@ -157,11 +173,37 @@ impl<S: Side> MolotovBottleBundle<S> {
                     crit_chance,
                     attack_speed,
                     lifespan,
-                ),
+                    DamageSource::Molotov,
+                    DamageType::Fire,
+                )
+                .with_effects(effects),
                 rotation: 0.0,
                 initial_position,
                 target_position: area_position,
             },
+            Transform::from_translation(initial_position),
+        )
+    }
+
+    /// Creates bundle for the bottle already in flight
+    pub fn from_bottle(
+        texture: Handle<Image>,
+        bottle: MolotovBottle<S>,
+        transform: Transform,
+    ) -> Self {
+        Self {
+            sprite: SpriteBundle {
+                sprite: Sprite {
+                    // Flip on East and North
+                    flip_y: S::DIRECTION.x < 0.0 || S::DIRECTION.y > 0.0,
+                    ..default()
+                },
+                texture,
+                transform,
+                ..default()
+            },
+            bottle,
+            interpolated: Interpolated::default(),
             marker: MolotovMarker,
         }
     }
@@ -182,6 +224,7 @@ impl<S: Side> Molotov<S> {
             area_size: self.area_size * (1.0 + molotov_buffs.area_size),
             area_attack_speed: self.area_attack_speed * (1.0 + molotov_buffs.area_attack_speed),
             area_lifespan: self.area_lifespan * (1.0 + molotov_buffs.area_lifespan),
+            burn_chance: self.burn_chance + molotov_buffs.burn_chance,
             attack_timer: Timer::from_seconds(
                 DEFAULT_MOLOTOV_ATTACK_SPEED * (1.0 + molotov_buffs.attack_speed),
                 TimerMode::Repeating,
@@ -214,15 +257,17 @@ fn setup(mut commands: Commands) {
 }
 
 fn molotov_attack<S: Side>(
-    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
     weapon_assets: Res<WeaponsAssets>,
     molotov_buffs: Res<MolotovBuffs<S>>,
     global_weapons_buffs: Res<GlobalWeaponBuffs>,
+    damage_boost: Res<DamageBoost>,
+    mut rng: ResMut<SideRng<S>>,
     mut commands: Commands,
     mut molotovs: Query<(&Transform, &CastleWall<S>, &mut Molotov<S>)>,
 ) {
     for (transform, wall, mut molotov) in molotovs.iter_mut() {
-        if !molotov.attack_timer.tick(time.delta()).finished() {
+        if !molotov.attack_timer.tick(fixed_time.period).finished() {
             continue;
         }
 
@@ -231,11 +276,12 @@ fn molotov_attack<S: Side>(
             TimerMode::Repeating,
         );
 
-        let mut rng = rand::thread_rng();
         // each side is 60 degrees in size.
         // S::direction gives a line directly at the center of the side
-        let angle = rng.gen_range(-30.0..30.0);
-        let distance = rng.gen_range(DEFAULT_MOLOTOV_MIN_RANGE..molotov.range);
+        let angle = rng.weapons.gen_range(-30.0..30.0);
+        let distance = rng
+            .weapons
+            .gen_range(DEFAULT_MOLOTOV_MIN_RANGE..molotov.range);
 
         // convert angle to radians
         let direction = Vec2::from_angle(angle / 360.0 * std::f32::consts::PI).rotate(S::DIRECTION);
@@ -246,9 +292,11 @@ fn molotov_attack<S: Side>(
         let mut area_position = transform.translation;
         area_position += (direction * (distance + wall.half_thickness)).extend(0.0);
 
-        let damage =
-            ((molotov.damage + molotov_buffs.damage_flat + global_weapons_buffs.damage_flat) as f32
-                * (1.0 + molotov_buffs.damage + global_weapons_buffs.damage)) as i32;
+        let damage = ((molotov.damage
+            + molotov_buffs.damage_flat
+            + global_weapons_buffs.damage_flat) as f32
+            * (1.0 + molotov_buffs.damage + global_weapons_buffs.damage + damage_boost.damage()))
+            as i32;
         let area_size = molotov.area_size * (1.0 + molotov_buffs.area_size);
         let area_attack_speed = molotov.area_attack_speed * (1.0 + molotov_buffs.area_attack_speed);
         let area_lifespan = DEFAULT_AREA_LIFESPAN * (1.0 + molotov_buffs.area_lifespan);
@@ -256,6 +304,14 @@ fn molotov_attack<S: Side>(
         let crit_damage = (damage as f32
             * (1.0 + molotov_buffs.crit_damage + global_weapons_buffs.crit_damage))
             as i32;
+        let burn = StatusChance::new(
+            StatusEffect::new(
+                StatusKind::Burning,
+                damage as f32 * MOLOTOV_BURN_POWER,
+                MOLOTOV_BURN_DURATION,
+            ),
+            molotov.burn_chance + molotov_buffs.burn_chance,
+        );
 
         commands.spawn(MolotovBottleBundle::<S>::new(
             weapon_assets.molotov.clone(),
@@ -265,6 +321,7 @@ fn molotov_attack<S: Side>(
             crit_chance,
             area_attack_speed,
             area_lifespan,
+            vec![burn],
             area_position,
             initial_position,
         ));
@@ -272,12 +329,10 @@ fn molotov_attack<S: Side>(
 }
 
 fn molotov_bottle_update<S: Side>(
-    time: Res<Time>,
-    audio: Res<Audio>,
-    game_assets: Res<GameAssets>,
-    game_settings: Res<GameSettings>,
+    fixed_time: Res<FixedTime>,
     weapon_assets: Res<WeaponsAssets>,
     mut commands: Commands,
+    mut sound_events: EventWriter<WeaponSoundEvent>,
     mut bottles: Query<(Entity, &mut MolotovBottle<S>, &mut Transform)>,
 ) {
     for (entity, mut bottle, mut transform) in bottles.iter_mut() {
@@ -285,7 +340,7 @@ fn molotov_bottle_update<S: Side>(
 
         let distance = direction.length();
         let speed = distance / DEFAULT_MOLOTOV_BOTTLE_IN_FLIGHT_TIME;
-        transform.translation += direction.normalize() * speed * time.delta().as_secs_f32();
+        transform.translation += direction.normalize() * speed * fixed_time.period.as_secs_f32();
 
         let progression = (bottle.initial_position - transform.translation).length() / distance;
         let rotation = progression * DEFAULT_MOLOTOV_BOTTLE_IN_FLIGHT_ROTATION;
@@ -308,9 +363,7 @@ fn molotov_bottle_update<S: Side>(
                 bottle.area.clone(),
             ));
 
-            audio
-                .play(game_assets.explosion.clone())
-                .with_volume(game_settings.sound_volume * MOLOTOV_SFX_MULTIPLIER);
+            sound_events.send(WeaponSoundEvent::MolotovExplosion);
         }
     }
 }
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{castle_type::CastleType, mode::GameMode},
+    storage::{self, StoredResource, VersionedFile},
+};
+
+/// Version of the high scores file.
+/// High scores with other versions are replaced with new ones.
+pub const HIGH_SCORES_VERSION: u32 = 1;
+
+/// Number of runs kept in every table
+pub const MAX_HIGH_SCORES: usize = 10;
+
+/// Longest name the player can enter
+pub const MAX_NAME_LENGTH: usize = 12;
+
+const HIGH_SCORES_FILE: &str = "high_scores.ron";
+
+/// Points for every second survived
+const TIME_POINTS: u64 = 10;
+/// Points for every castle level
+const LEVEL_POINTS: u64 = 100;
+/// Points for every killed enemy
+const KILL_POINTS: u64 = 1;
+
+/// Saves high scores every time they change
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            storage::save_resource::<HighScores>
+                .run_if(resource_exists_and_changed::<HighScores>()),
+        );
+    }
+}
+
+/// Runs are compared only with the runs of the same mode.
+/// Runs with a requested seed get their own table for that seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    pub mode: GameMode,
+    pub seed: Option<u64>,
+}
+
+impl std::fmt::Display for HighScoreTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.seed {
+            Some(seed) => f.write_fmt(format_args!("{:?} (seed {seed})", self.mode)),
+            None => f.write_fmt(format_args!("{:?}", self.mode)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u64,
+    /// Survival time in seconds
+    pub time: f32,
+    pub level: u32,
+    pub kills: u32,
+    pub castle_type: CastleType,
+    /// Seconds since the unix epoch
+    pub date: u64,
+}
+
+impl HighScore {
+    pub fn new(name: String, time: f32, level: u32, kills: u32, castle_type: CastleType) -> Self {
+        Self {
+            name,
+            score: score(time, level, kills),
+            time,
+            level,
+            kills,
+            castle_type,
+            date: unix_time(),
+        }
+    }
+
+    /// Date of the run as `YYYY-MM-DD`
+    pub fn date_label(&self) -> String {
+        let (year, month, day) = civil_from_days((self.date / 86400) as i64);
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+}
+
+/// Score of the run:
+/// `10 * seconds survived + 100 * castle level + kills`
+pub fn score(time: f32, level: u32, kills: u32) -> u64 {
+    time as u64 * TIME_POINTS + level as u64 * LEVEL_POINTS + kills as u64 * KILL_POINTS
+}
+
+/// Best runs of every table
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct HighScores {
+    pub tables: HashMap<HighScoreTable, Vec<HighScore>>,
+    /// Name entered the last time, offered for the next run
+    pub last_name: String,
+}
+
+impl HighScores {
+    /// Adds the run to the table.
+    /// Returns the place of the run if it made it into the table.
+    pub fn insert(&mut self, table: HighScoreTable, high_score: HighScore) -> Option<usize> {
+        self.last_name = high_score.name.clone();
+
+        let scores = self.tables.entry(table).or_default();
+        let place = scores
+            .iter()
+            .position(|other| other.score < high_score.score)
+            .unwrap_or(scores.len());
+        if MAX_HIGH_SCORES <= place {
+            return None;
+        }
+        scores.insert(place, high_score);
+        scores.truncate(MAX_HIGH_SCORES);
+        Some(place)
+    }
+
+    /// All tables with at least one run, normal mode first
+    pub fn sorted_tables(&self) -> Vec<HighScoreTable> {
+        let mut tables = self.tables.keys().copied().collect::<Vec<_>>();
+        tables.sort_by_key(|table| (table.mode == GameMode::Infinite, table.seed));
+        tables
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HighScoresFile {
+    version: u32,
+    high_scores: HighScores,
+}
+
+impl VersionedFile for HighScoresFile {
+    const NAME: &'static str = HIGH_SCORES_FILE;
+    const VERSION: u32 = HIGH_SCORES_VERSION;
+}
+
+impl StoredResource for HighScores {
+    type File = HighScoresFile;
+
+    fn to_file(&self) -> HighScoresFile {
+        HighScoresFile {
+            version: HIGH_SCORES_VERSION,
+            high_scores: self.clone(),
+        }
+    }
+}
+
+/// Loads high scores saved by the previous sessions.
+/// Starts new ones if there are none or they can not be read.
+pub fn load() -> HighScores {
+    storage::load_versioned::<HighScoresFile>()
+        .map_or_else(HighScores::default, |file| file.high_scores)
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// `SystemTime` is not available in the browser
+#[cfg(target_family = "wasm")]
+fn unix_time() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
+/// Converts days since the unix epoch into (year, month, day)
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,48 @@
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_kira_audio::AudioSource;
+
+pub mod achievements;
+pub mod audio;
+pub mod game;
+pub mod high_scores;
+pub mod profile;
+pub mod settings;
+pub mod storage;
+pub mod ui;
+pub mod utils;
+
+pub use settings::GameSettings;
+use utils::IntoState;
+
+pub const GAME_NAME: &str = "Mind your sides";
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash, States)]
+pub enum GlobalState {
+    #[default]
+    AssetLoading,
+    /// Init resources that depend on assets
+    /// i.e UI resources
+    Initialization,
+    MainMenu,
+    InGame,
+}
+impl_into_state!(GlobalState);
+
+#[derive(Default, AssetCollection, Resource)]
+pub struct GameAssets {
+    #[asset(path = "fonts/ae-systematic-tt-brk.ae-systematic-tt-brk.ttf")]
+    font: Handle<Font>,
+    #[asset(path = "sfx/background.wav")]
+    background: Handle<AudioSource>,
+    #[asset(path = "sfx/main_menu.wav")]
+    main_menu: Handle<AudioSource>,
+    #[asset(path = "sfx/crossbow_shoot.wav")]
+    crossbow_shoot: Handle<AudioSource>,
+    #[asset(path = "sfx/explosion.wav")]
+    explosion: Handle<AudioSource>,
+    #[asset(path = "sfx/click.wav")]
+    click: Handle<AudioSource>,
+}
@@ -1,20 +1,30 @@
-#![allow(clippy::too_many_arguments, clippy::type_complexity)]
-
 use bevy::{
     prelude::*,
     window::{PresentMode, WindowMode, WindowResolution},
 };
 use bevy_asset_loader::prelude::*;
-use bevy_kira_audio::{AudioPlugin, AudioSource};
+use bevy_kira_audio::AudioPlugin;
 use bevy_rapier2d::prelude::*;
 
-mod game;
-mod ui;
-mod utils;
-
-use utils::IntoState;
-
-const GAME_NAME: &str = "Mind your sides";
+use mind_your_sides::{
+    achievements::{AchievementAssets, AchievementsPlugin},
+    audio::GameAudioPlugin,
+    game::{
+        castle::CastleAssets,
+        enemies::{
+            definition::EnemyDefinitionsPlugin, schedule::WaveSchedulesPlugin, EnemyAssets,
+            ScheduleAssets,
+        },
+        replay::ReplayPlugin,
+        weapons::WeaponsAssets,
+        GamePlugin,
+    },
+    high_scores::{self, HighScoresPlugin},
+    profile::{self, PlayerProfilePlugin},
+    settings::{self, GameSettingsPlugin},
+    ui::UiPlugin,
+    GameAssets, GlobalState, GAME_NAME,
+};
 
 fn main() {
     let mut app = App::new();
@@ -23,6 +33,11 @@ fn main() {
         .add_plugins(
             DefaultPlugins
                 .set(ImagePlugin::default_nearest())
+                // reload changed asset files in development builds
+                .set(AssetPlugin {
+                    watch_for_changes: cfg!(feature = "dev"),
+                    ..default()
+                })
                 .set(WindowPlugin {
                     primary_window: Some(Window {
                         title: GAME_NAME.to_string(),
@@ -33,77 +48,46 @@ fn main() {
                     ..default()
                 }),
         )
-        .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+        // physics systems are run by the game simulation
+        .add_plugin(RapierPhysicsPlugin::<NoUserData>::default().with_default_system_setup(false))
+        .add_plugin(EnemyDefinitionsPlugin)
+        .add_plugin(WaveSchedulesPlugin)
         .add_loading_state(
             LoadingState::new(GlobalState::AssetLoading)
                 .continue_to_state(GlobalState::Initialization),
         )
         .add_collection_to_loading_state::<_, GameAssets>(GlobalState::AssetLoading)
+        .add_collection_to_loading_state::<_, CastleAssets>(GlobalState::AssetLoading)
+        .add_collection_to_loading_state::<_, EnemyAssets>(GlobalState::AssetLoading)
+        .add_collection_to_loading_state::<_, ScheduleAssets>(GlobalState::AssetLoading)
+        .add_collection_to_loading_state::<_, WeaponsAssets>(GlobalState::AssetLoading)
+        .add_collection_to_loading_state::<_, AchievementAssets>(GlobalState::AssetLoading)
         .add_plugin(AudioPlugin)
-        .add_plugin(game::GamePlugin)
-        .add_plugin(ui::UiPlugin)
+        .add_plugin(GameAudioPlugin)
+        .add_plugin(GamePlugin)
+        .add_plugin(ReplayPlugin)
+        .add_plugin(GameSettingsPlugin)
+        .add_plugin(PlayerProfilePlugin)
+        .add_plugin(HighScoresPlugin)
+        .add_plugin(AchievementsPlugin)
+        .add_plugin(UiPlugin)
         .add_system(setup.in_set(OnUpdate(GlobalState::Initialization)));
     app.run();
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Eq, Hash, States)]
-pub enum GlobalState {
-    #[default]
-    AssetLoading,
-    /// Init resources that depend on assets
-    /// i.e UI resources
-    Initialization,
-    MainMenu,
-    InGame,
-}
-impl_into_state!(GlobalState);
-
-#[derive(AssetCollection, Resource)]
-pub struct GameAssets {
-    #[asset(path = "fonts/ae-systematic-tt-brk.ae-systematic-tt-brk.ttf")]
-    font: Handle<Font>,
-    #[asset(path = "sfx/background.wav")]
-    background: Handle<AudioSource>,
-    #[asset(path = "sfx/main_menu.wav")]
-    main_menu: Handle<AudioSource>,
-    #[asset(path = "sfx/crossbow_shoot.wav")]
-    crossbow_shoot: Handle<AudioSource>,
-    #[asset(path = "sfx/explosion.wav")]
-    explosion: Handle<AudioSource>,
-}
-
-#[derive(Resource)]
-pub struct GameSettings {
-    window_mode: WindowMode,
-    sound_volume: f64,
-}
-
-impl Default for GameSettings {
-    fn default() -> Self {
-        Self {
-            window_mode: WindowMode::Windowed,
-            sound_volume: 0.6,
-        }
-    }
-}
-
 /// Used to create initial global config
 /// and then changes state to `GlobalState::MainMenu`
 fn setup(
     mut commands: Commands,
-    mut physics: ResMut<RapierConfiguration>,
     mut windows: Query<&mut Window>,
     mut global_state: ResMut<NextState<GlobalState>>,
 ) {
-    // disable gravity because game is 2d top down
-    physics.gravity = Vec2::ZERO;
-
     let mut camera_bundle = Camera2dBundle::default();
     // make everything smaller
     camera_bundle.projection.scale = 1.8;
     commands.spawn(camera_bundle);
 
-    let game_settings = GameSettings::default();
+    let game_settings = settings::load();
 
     for mut window in windows.iter_mut() {
         window.present_mode = PresentMode::AutoVsync;
@@ -111,6 +95,8 @@ fn setup(
     }
 
     commands.insert_resource(game_settings);
+    commands.insert_resource(profile::load());
+    commands.insert_resource(high_scores::load());
 
     global_state.set(GlobalState::MainMenu);
 }
@@ -0,0 +1,170 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{
+        castle::{Castle, CastleWall},
+        damage::DamageSource,
+        enemies::{EnemyKind, SpawnStateTimer},
+        mode::{GameMode, RunTimer},
+        replay::ReplayPlayback,
+        stats::RunStats,
+        East, GameState, North, Side, South, West,
+    },
+    storage::{self, StoredResource, VersionedFile},
+};
+
+/// Version of the profile file.
+/// Profiles with other versions are replaced with a new one.
+pub const PROFILE_VERSION: u32 = 2;
+
+const PROFILE_FILE: &str = "profile.ron";
+
+/// Keeps player progress across the sessions
+pub struct PlayerProfilePlugin;
+
+impl Plugin for PlayerProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            record_run
+                .run_if(not(resource_exists::<ReplayPlayback>()))
+                .in_schedule(OnEnter(GameState::GameOver)),
+        )
+        .add_system(
+            record_run
+                .run_if(not(resource_exists::<ReplayPlayback>()))
+                .in_schedule(OnEnter(GameState::Victory)),
+        )
+        .add_system(
+            storage::save_resource::<PlayerProfile>
+                .run_if(resource_exists_and_changed::<PlayerProfile>()),
+        );
+    }
+}
+
+/// Best results and lifetime statistics of the player over all runs
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct PlayerProfile {
+    pub runs: u32,
+    pub victories: u32,
+    pub best_stage: u32,
+    pub best_level: u32,
+    /// Longest survival time in seconds
+    pub best_times: HashMap<GameMode, f32>,
+    pub kills: HashMap<EnemyKind, u64>,
+    pub weapon_damage: HashMap<DamageSource, u64>,
+    pub crits: u64,
+    /// Damage dealt on the sides by `Side::INDEX`
+    pub side_damage: [u64; 4],
+    /// Number of times each buff was picked by its name
+    pub upgrade_picks: HashMap<String, u32>,
+    /// Number of times each wall fell by `Side::INDEX`
+    pub fallen_walls: [u32; 4],
+    /// Ids of the unlocked achievements
+    pub achievements: HashSet<String>,
+}
+
+impl PlayerProfile {
+    /// Most picked buff and number of times it was picked
+    pub fn favorite_upgrade(&self) -> Option<(&str, u32)> {
+        self.upgrade_picks
+            .iter()
+            .max_by(|(a_name, a), (b_name, b)| a.cmp(b).then_with(|| b_name.cmp(a_name)))
+            .map(|(name, picks)| (name.as_str(), *picks))
+    }
+
+    /// Index of the wall that fell the most times
+    pub fn weakest_wall(&self) -> Option<usize> {
+        self.fallen_walls
+            .iter()
+            .enumerate()
+            .filter(|(_, fallen)| 0 < **fallen)
+            .max_by(|(a_index, a), (b_index, b)| a.cmp(b).then_with(|| b_index.cmp(a_index)))
+            .map(|(index, _)| index)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ProfileFile {
+    version: u32,
+    profile: PlayerProfile,
+}
+
+impl VersionedFile for ProfileFile {
+    const NAME: &'static str = PROFILE_FILE;
+    const VERSION: u32 = PROFILE_VERSION;
+}
+
+impl StoredResource for PlayerProfile {
+    type File = ProfileFile;
+
+    fn to_file(&self) -> ProfileFile {
+        ProfileFile {
+            version: PROFILE_VERSION,
+            profile: self.clone(),
+        }
+    }
+}
+
+/// Loads profile saved by the previous sessions.
+/// Starts a new one if there is none or it can not be read.
+pub fn load() -> PlayerProfile {
+    storage::load_versioned::<ProfileFile>()
+        .map_or_else(PlayerProfile::default, |file| file.profile)
+}
+
+/// Replayed runs are not recorded,
+/// they were already recorded when played
+pub fn record_run(
+    game_state: Res<State<GameState>>,
+    game_mode: Res<GameMode>,
+    run_timer: Res<RunTimer>,
+    run_stats: Res<RunStats>,
+    spawn_state_timer: Res<SpawnStateTimer>,
+    castle: Query<&Castle>,
+    wall_north: Query<&CastleWall<North>>,
+    wall_south: Query<&CastleWall<South>>,
+    wall_west: Query<&CastleWall<West>>,
+    wall_east: Query<&CastleWall<East>>,
+    mut profile: ResMut<PlayerProfile>,
+) {
+    let castle = castle.single();
+
+    profile.runs += 1;
+    if game_state.0 == GameState::Victory {
+        profile.victories += 1;
+    }
+    profile.best_stage = profile.best_stage.max(spawn_state_timer.stage_number());
+    profile.best_level = profile.best_level.max(castle.level);
+
+    let best_time = profile.best_times.entry(*game_mode).or_default();
+    *best_time = best_time.max(run_timer.time_passed);
+
+    for (index, side) in run_stats.sides.iter().enumerate() {
+        for (kind, kills) in side.kills.iter() {
+            *profile.kills.entry(*kind).or_default() += *kills as u64;
+        }
+        for (source, damage) in side.damage_dealt.iter() {
+            *profile.weapon_damage.entry(*source).or_default() += damage;
+        }
+        profile.side_damage[index] += side.total_damage_dealt();
+        profile.crits += side.crits as u64;
+    }
+    for (name, picks) in run_stats.upgrade_picks.iter() {
+        *profile.upgrade_picks.entry(name.clone()).or_default() += picks;
+    }
+
+    record_fallen_wall(&mut profile, wall_north.single());
+    record_fallen_wall(&mut profile, wall_south.single());
+    record_fallen_wall(&mut profile, wall_west.single());
+    record_fallen_wall(&mut profile, wall_east.single());
+}
+
+fn record_fallen_wall<S: Side>(profile: &mut PlayerProfile, wall: &CastleWall<S>) {
+    if wall.health <= 0 {
+        profile.fallen_walls[S::INDEX] += 1;
+    }
+}
@@ -0,0 +1,127 @@
+use bevy::{prelude::*, window::WindowMode};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{self, Dir, StoredResource, VersionedFile};
+
+/// Version of the settings file.
+/// Settings with other versions are replaced with defaults.
+pub const SETTINGS_VERSION: u32 = 1;
+
+const SETTINGS_FILE: &str = "settings.ron";
+
+/// Saves settings every time they change
+pub struct GameSettingsPlugin;
+
+impl Plugin for GameSettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            storage::save_resource::<GameSettings>
+                .run_if(resource_exists_and_changed::<GameSettings>()),
+        );
+    }
+}
+
+/// Settings missing in the file keep their default values,
+/// so new settings can be added without a new version
+#[derive(Clone, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct GameSettings {
+    pub window_mode: WindowMode,
+    pub music_volume: f64,
+    pub weapons_volume: f64,
+    pub enemies_volume: f64,
+    pub ui_volume: f64,
+    pub muted: bool,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            window_mode: WindowMode::Windowed,
+            music_volume: 0.6,
+            weapons_volume: 0.6,
+            enemies_volume: 0.6,
+            ui_volume: 0.6,
+            muted: false,
+        }
+    }
+}
+
+impl GameSettings {
+    /// Volume sounds of the channel are played with
+    pub fn volume(&self, channel: SoundChannel) -> f64 {
+        if self.muted {
+            0.0
+        } else {
+            self.channel_volume(channel)
+        }
+    }
+
+    /// Volume of the channel set by the player
+    pub fn channel_volume(&self, channel: SoundChannel) -> f64 {
+        match channel {
+            SoundChannel::Music => self.music_volume,
+            SoundChannel::Weapons => self.weapons_volume,
+            SoundChannel::Enemies => self.enemies_volume,
+            SoundChannel::Ui => self.ui_volume,
+        }
+    }
+
+    pub fn change_volume(&mut self, channel: SoundChannel, delta: f64) {
+        let volume = match channel {
+            SoundChannel::Music => &mut self.music_volume,
+            SoundChannel::Weapons => &mut self.weapons_volume,
+            SoundChannel::Enemies => &mut self.enemies_volume,
+            SoundChannel::Ui => &mut self.ui_volume,
+        };
+        *volume = (*volume + delta).clamp(0.0, 1.0);
+    }
+}
+
+/// Group of sounds with its own volume
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundChannel {
+    Music,
+    Weapons,
+    Enemies,
+    Ui,
+}
+
+impl SoundChannel {
+    pub const ALL: [SoundChannel; 4] = [
+        SoundChannel::Music,
+        SoundChannel::Weapons,
+        SoundChannel::Enemies,
+        SoundChannel::Ui,
+    ];
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SettingsFile {
+    version: u32,
+    settings: GameSettings,
+}
+
+impl VersionedFile for SettingsFile {
+    const NAME: &'static str = SETTINGS_FILE;
+    const VERSION: u32 = SETTINGS_VERSION;
+    const DIR: Dir = Dir::Config;
+}
+
+impl StoredResource for GameSettings {
+    type File = SettingsFile;
+
+    fn to_file(&self) -> SettingsFile {
+        SettingsFile {
+            version: SETTINGS_VERSION,
+            settings: self.clone(),
+        }
+    }
+}
+
+/// Loads settings saved by the previous launch.
+/// Falls back to defaults if there are none or they can not be read.
+pub fn load() -> GameSettings {
+    storage::load_versioned::<SettingsFile>()
+        .map_or_else(GameSettings::default, |file| file.settings)
+}
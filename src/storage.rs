@@ -0,0 +1,227 @@
+use std::fmt::Display;
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+#[cfg(not(target_family = "wasm"))]
+const QUALIFIER: &str = "";
+#[cfg(not(target_family = "wasm"))]
+const ORGANIZATION: &str = "";
+const APPLICATION: &str = "mind_your_sides";
+
+#[derive(Debug)]
+pub enum StorageError {
+    NoDataDir,
+    Io(std::io::Error),
+    Serialize(ron::Error),
+    Deserialize(ron::error::SpannedError),
+    /// Browser refused to access local storage
+    Browser(String),
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::NoDataDir => f.write_str("could not find data directory"),
+            StorageError::Io(e) => f.write_fmt(format_args!("io error: {e}")),
+            StorageError::Serialize(e) => f.write_fmt(format_args!("serialization error: {e}")),
+            StorageError::Deserialize(e) => f.write_fmt(format_args!("deserialization error: {e}")),
+            StorageError::Browser(e) => f.write_fmt(format_args!("browser storage error: {e}")),
+        }
+    }
+}
+
+impl From<std::io::Error> for StorageError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ron::Error> for StorageError {
+    fn from(e: ron::Error) -> Self {
+        Self::Serialize(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for StorageError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        Self::Deserialize(e)
+    }
+}
+
+/// Where the value is kept
+#[derive(Debug, Clone, Copy)]
+pub enum Dir {
+    /// Saves, replays, the profile and high scores
+    Data,
+    /// Settings
+    Config,
+}
+
+/// Saves `value` under the `name` in the game data directory
+pub fn save<T: Serialize>(name: &str, value: &T) -> Result<(), StorageError> {
+    save_in(Dir::Data, name, value)
+}
+
+/// Loads text saved under the `name`, so it can be parsed
+/// in parts. Returns `Ok(None)` if nothing was saved yet.
+pub fn load_text(name: &str) -> Result<Option<String>, StorageError> {
+    read(Dir::Data, name)
+}
+
+/// Removes value saved under the `name`.
+/// Does nothing if nothing was saved.
+pub fn remove(name: &str) -> Result<(), StorageError> {
+    delete(Dir::Data, name)
+}
+
+/// File which format has a version.
+/// Files saved with other versions are not loaded.
+pub trait VersionedFile: Serialize + DeserializeOwned {
+    /// Name the file is saved under
+    const NAME: &'static str;
+    const VERSION: u32;
+    const DIR: Dir = Dir::Data;
+}
+
+/// Resource saved to a versioned file every time it changes
+pub trait StoredResource: Resource {
+    type File: VersionedFile;
+
+    fn to_file(&self) -> Self::File;
+}
+
+/// Only the version of the file, so it can be
+/// checked before the rest is deserialized
+#[derive(Deserialize)]
+struct VersionHeader {
+    version: u32,
+}
+
+/// Loads the versioned file.
+/// Returns `None` if nothing was saved yet, it can not
+/// be read or it was saved with another version.
+pub fn load_versioned<T: VersionedFile>() -> Option<T> {
+    let loaded = match load_from::<VersionHeader>(T::DIR, T::NAME) {
+        Ok(Some(header)) if header.version != T::VERSION => {
+            warn!(
+                "{} version {} is not supported, expected {}",
+                T::NAME,
+                header.version,
+                T::VERSION
+            );
+            return None;
+        }
+        Ok(Some(_)) => load_from(T::DIR, T::NAME),
+        Ok(None) => Ok(None),
+        Err(e) => Err(e),
+    };
+    match loaded {
+        Ok(file) => file,
+        Err(e) => {
+            warn!("could not load {}: {e}", T::NAME);
+            None
+        }
+    }
+}
+
+pub fn save_versioned<T: VersionedFile>(file: &T) {
+    if let Err(e) = save_in(T::DIR, T::NAME, file) {
+        error!("could not save {}: {e}", T::NAME);
+    }
+}
+
+/// Saves the resource after it changes
+pub fn save_resource<R: StoredResource>(resource: Res<R>) {
+    // resource was just loaded
+    if resource.is_added() {
+        return;
+    }
+    save_versioned(&resource.to_file());
+}
+
+fn save_in<T: Serialize>(dir: Dir, name: &str, value: &T) -> Result<(), StorageError> {
+    let data = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())?;
+    write(dir, name, &data)
+}
+
+fn load_from<T: DeserializeOwned>(dir: Dir, name: &str) -> Result<Option<T>, StorageError> {
+    match read(dir, name)? {
+        Some(data) => Ok(Some(ron::from_str(&data)?)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn path(dir: Dir, name: &str) -> Result<std::path::PathBuf, StorageError> {
+    let dirs = directories::ProjectDirs::from(QUALIFIER, ORGANIZATION, APPLICATION)
+        .ok_or(StorageError::NoDataDir)?;
+    let dir = match dir {
+        Dir::Data => dirs.data_dir(),
+        Dir::Config => dirs.config_dir(),
+    };
+    Ok(dir.join(name))
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn write(dir: Dir, name: &str, data: &str) -> Result<(), StorageError> {
+    let path = path(dir, name)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, data)?;
+    Ok(())
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn read(dir: Dir, name: &str) -> Result<Option<String>, StorageError> {
+    match std::fs::read_to_string(path(dir, name)?) {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn delete(dir: Dir, name: &str) -> Result<(), StorageError> {
+    match std::fs::remove_file(path(dir, name)?) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// On the web everything is kept in the local storage of the browser,
+/// so all directories are the same
+#[cfg(target_family = "wasm")]
+fn local_storage() -> Result<web_sys::Storage, StorageError> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or(StorageError::NoDataDir)
+}
+
+#[cfg(target_family = "wasm")]
+fn key(_dir: Dir, name: &str) -> String {
+    format!("{APPLICATION}/{name}")
+}
+
+#[cfg(target_family = "wasm")]
+fn write(dir: Dir, name: &str, data: &str) -> Result<(), StorageError> {
+    local_storage()?
+        .set_item(&key(dir, name), data)
+        .map_err(|e| StorageError::Browser(format!("{e:?}")))
+}
+
+#[cfg(target_family = "wasm")]
+fn read(dir: Dir, name: &str) -> Result<Option<String>, StorageError> {
+    local_storage()?
+        .get_item(&key(dir, name))
+        .map_err(|e| StorageError::Browser(format!("{e:?}")))
+}
+
+#[cfg(target_family = "wasm")]
+fn delete(dir: Dir, name: &str) -> Result<(), StorageError> {
+    local_storage()?
+        .remove_item(&key(dir, name))
+        .map_err(|e| StorageError::Browser(format!("{e:?}")))
+}
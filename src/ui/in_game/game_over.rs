@@ -1,12 +1,16 @@
 use bevy::prelude::*;
 
 use crate::{
+    game::{castle::Castle, mode::RunTimer, replay::ReplayPlayback, rng::GameRng, stats::RunStats},
     ui::{spawn_button, UiConfig},
     utils::remove_all_with,
     GlobalState,
 };
 
-use super::{hud::HUDMarker, UiInGameState};
+use super::{
+    high_score_entry::spawn_high_score_entry, hud::HUDMarker, run_summary::spawn_run_summary,
+    UiInGameState,
+};
 
 pub struct GameOverPlugin;
 
@@ -29,11 +33,20 @@ enum GameOverButton {
     MainMenu,
 }
 
-fn setup(config: Res<UiConfig>, hud: Query<Entity, With<HUDMarker>>, mut commands: Commands) {
+fn setup(
+    config: Res<UiConfig>,
+    game_rng: Res<GameRng>,
+    run_stats: Res<RunStats>,
+    run_timer: Res<RunTimer>,
+    castle: Query<&Castle>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    hud: Query<Entity, With<HUDMarker>>,
+    mut commands: Commands,
+) {
     let game_over = commands
         .spawn((
             NodeBundle {
-                style: config.menu_style.clone(),
+                style: config.summary_style.clone(),
                 background_color: config.panels_background.into(),
                 ..default()
             },
@@ -47,6 +60,12 @@ fn setup(config: Res<UiConfig>, hud: Query<Entity, With<HUDMarker>>, mut command
                 })
                 .with_style(config.title_style.clone()),
             );
+            builder.spawn(TextBundle::from_section(
+                format!("Seed: {}", game_rng.seed),
+                config.text_style.clone(),
+            ));
+            spawn_run_summary(builder, &config, &run_stats, &run_timer, castle.single());
+            spawn_high_score_entry(builder, &config, replay_playback.is_some());
         })
         .with_children(|builder| {
             spawn_button(builder, &config, GameOverButton::Restart);
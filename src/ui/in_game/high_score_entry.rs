@@ -0,0 +1,176 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        castle::Castle,
+        castle_type::CastleType,
+        mode::{GameMode, RunTimer},
+        replay::ReplayPlayback,
+        rng::GameRng,
+        stats::RunStats,
+    },
+    high_scores::{HighScore, HighScoreTable, HighScores, MAX_NAME_LENGTH},
+    ui::{spawn_button, UiConfig},
+};
+
+use super::UiInGameState;
+
+pub struct HighScoreEntryPlugin;
+
+impl Plugin for HighScoreEntryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(UiInGameState::GameOver)))
+            .add_system(setup.in_schedule(OnEnter(UiInGameState::Victory)))
+            .add_systems(
+                (name_input, update_name_text, button_system)
+                    .distributive_run_if(not(resource_exists::<ReplayPlayback>()))
+                    .in_set(OnUpdate(UiInGameState::GameOver)),
+            )
+            .add_systems(
+                (name_input, update_name_text, button_system)
+                    .distributive_run_if(not(resource_exists::<ReplayPlayback>()))
+                    .in_set(OnUpdate(UiInGameState::Victory)),
+            );
+    }
+}
+
+/// Name typed for the finished run
+#[derive(Debug, Default, Resource)]
+struct EnteredName {
+    name: String,
+    /// Run was already added to the high scores
+    submitted: bool,
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+struct NameText;
+
+#[derive(Debug, Clone, Copy, Component)]
+struct HighScoreResultText;
+
+#[derive(Debug, Clone, Copy, Component)]
+enum HighScoreButton {
+    SaveScore,
+}
+
+/// Spawns name input for the finished run.
+/// Replayed runs are not added to the high scores.
+pub fn spawn_high_score_entry(builder: &mut ChildBuilder, config: &UiConfig, is_replay: bool) {
+    if is_replay {
+        return;
+    }
+
+    builder.spawn((
+        TextBundle::from_section("", config.text_style.clone()),
+        NameText,
+    ));
+    spawn_button(builder, config, HighScoreButton::SaveScore);
+    builder.spawn((
+        TextBundle::from_section("", config.text_style.clone()),
+        HighScoreResultText,
+    ));
+}
+
+fn setup(mut commands: Commands, high_scores: Res<HighScores>) {
+    commands.insert_resource(EnteredName {
+        name: high_scores.last_name.clone(),
+        submitted: false,
+    });
+}
+
+/// Typing adds characters to the name.
+/// Backspace removes the last one.
+fn name_input(
+    keyboard: Res<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut entered_name: ResMut<EnteredName>,
+) {
+    if entered_name.submitted {
+        characters.clear();
+        return;
+    }
+
+    for character in characters.iter() {
+        if (character.char.is_alphanumeric() || character.char == ' ')
+            && entered_name.name.chars().count() < MAX_NAME_LENGTH
+        {
+            entered_name.name.push(character.char);
+        }
+    }
+    if keyboard.just_pressed(KeyCode::Back) {
+        entered_name.name.pop();
+    }
+}
+
+fn update_name_text(
+    entered_name: Res<EnteredName>,
+    mut name_text: Query<&mut Text, With<NameText>>,
+) {
+    if !entered_name.is_changed() {
+        return;
+    }
+
+    for mut text in name_text.iter_mut() {
+        text.sections[0].value = format!("Name: {}_", entered_name.name);
+    }
+}
+
+fn button_system(
+    config: Res<UiConfig>,
+    game_mode: Res<GameMode>,
+    castle_type: Res<CastleType>,
+    game_rng: Res<GameRng>,
+    run_timer: Res<RunTimer>,
+    run_stats: Res<RunStats>,
+    castle: Query<&Castle>,
+    mut entered_name: ResMut<EnteredName>,
+    mut high_scores: ResMut<HighScores>,
+    mut result_text: Query<&mut Text, With<HighScoreResultText>>,
+    mut interaction_query: Query<
+        (&HighScoreButton, &Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (button, interaction, mut color) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *color = config.button_color_pressed.into();
+                match button {
+                    HighScoreButton::SaveScore => {
+                        let name = entered_name.name.trim();
+                        if entered_name.submitted || name.is_empty() {
+                            continue;
+                        }
+
+                        let table = HighScoreTable {
+                            mode: *game_mode,
+                            seed: game_rng.requested.then_some(game_rng.seed),
+                        };
+                        let high_score = HighScore::new(
+                            name.to_string(),
+                            run_timer.time_passed,
+                            castle.single().level,
+                            run_stats.total_kills(),
+                            *castle_type,
+                        );
+                        let score = high_score.score;
+                        let result = match high_scores.insert(table, high_score) {
+                            Some(place) => format!("Score {score}: place {} in {table}", place + 1),
+                            None => format!("Score {score}: not in the top of {table}"),
+                        };
+                        for mut text in result_text.iter_mut() {
+                            text.sections[0].value = result.clone();
+                        }
+                        entered_name.submitted = true;
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                *color = config.button_color_hover.into();
+            }
+            Interaction::None => {
+                *color = config.button_color_normal.into();
+            }
+        }
+    }
+}
@@ -3,14 +3,29 @@ use std::marker::PhantomData;
 use bevy::prelude::*;
 
 use crate::{
-    game::{castle::Castle, Side},
-    game::{castle::CastleWall, enemies::SpawnState, East, GameState, North, South, West},
+    game::{
+        abilities::{Ability, CastleAbilities},
+        castle::Castle,
+        mode::{GameMode, RunTimer},
+        simulation::SimulationSpeed,
+        Side,
+    },
+    game::{
+        castle::CastleWall,
+        enemies::{
+            boss::Boss,
+            pressure::{PressureState, Threat},
+            SpawnStateTimer,
+        },
+        loot::DamageBoost,
+        East, GameState, North, SideId, South, West,
+    },
     ui::{spawn_button, UiConfig},
     utils::remove_all_with,
     GlobalState,
 };
 
-use super::UiInGameState;
+use super::{SelectedTarget, UiInGameState, ABILITY_BINDS};
 
 pub struct HUDPlugin;
 
@@ -23,10 +38,16 @@ impl Plugin for HUDPlugin {
                     update_spawn_state,
                     update_castle_level,
                     update_castle_exp,
+                    update_castle_core,
+                    update_castle_gold,
                     update_castle_wall_hp::<North>,
                     update_castle_wall_hp::<South>,
                     update_castle_wall_hp::<West>,
                     update_castle_wall_hp::<East>,
+                    update_ability_target,
+                    update_abilities,
+                    update_threat,
+                    update_boss_bar,
                 )
                     .in_set(OnUpdate(UiInGameState::InGame)),
             )
@@ -35,12 +56,6 @@ impl Plugin for HUDPlugin {
     }
 }
 
-#[derive(Resource)]
-pub struct HUDTimer {
-    start_time: f32,
-    time_passed: f32,
-}
-
 #[derive(Debug, Clone, Copy, Component)]
 pub struct HUDMarker;
 
@@ -56,11 +71,33 @@ struct CastleLevelText;
 #[derive(Debug, Clone, Copy, Component)]
 struct CastleExpText;
 
+#[derive(Debug, Clone, Copy, Component)]
+struct CastleCoreText;
+
+#[derive(Debug, Clone, Copy, Component)]
+struct CastleGoldText;
+
 #[derive(Debug, Default, Clone, Copy, Component)]
 struct CastleWallHpText<S: Side> {
     _phantom: PhantomData<S>,
 }
 
+#[derive(Debug, Clone, Copy, Component)]
+struct AbilityTargetText;
+
+#[derive(Debug, Clone, Copy, Component)]
+struct AbilityText(Ability);
+
+/// Shown while any boss is alive
+#[derive(Debug, Clone, Copy, Component)]
+struct BossBar;
+
+#[derive(Debug, Clone, Copy, Component)]
+struct BossNameText;
+
+#[derive(Debug, Clone, Copy, Component)]
+struct BossHealthFill;
+
 #[derive(Debug, Clone, Copy, Component)]
 enum HUDButton {
     StatsNorth,
@@ -70,11 +107,19 @@ enum HUDButton {
     Pause,
 }
 
-fn setup(time: Res<Time>, config: Res<UiConfig>, mut commands: Commands) {
-    commands.insert_resource(HUDTimer {
-        start_time: time.elapsed().as_secs_f32(),
-        time_passed: time.elapsed().as_secs_f32(),
-    });
+impl HUDButton {
+    fn side(&self) -> Option<SideId> {
+        match self {
+            HUDButton::StatsNorth => Some(SideId::North),
+            HUDButton::StatsSouth => Some(SideId::South),
+            HUDButton::StatsWest => Some(SideId::West),
+            HUDButton::StatsEast => Some(SideId::East),
+            HUDButton::Pause => None,
+        }
+    }
+}
+
+fn setup(config: Res<UiConfig>, mut commands: Commands) {
     // root node
     commands
         .spawn((
@@ -136,6 +181,14 @@ fn setup(time: Res<Time>, config: Res<UiConfig>, mut commands: Commands) {
                                 TextBundle::from_section("Exp: ", config.text_style.clone()),
                                 CastleExpText,
                             ));
+                            parent.spawn((
+                                TextBundle::from_section("Core: ", config.text_style.clone()),
+                                CastleCoreText,
+                            ));
+                            parent.spawn((
+                                TextBundle::from_section("Gold: ", config.text_style.clone()),
+                                CastleGoldText,
+                            ));
                         });
 
                     // North info
@@ -205,6 +258,31 @@ fn setup(time: Res<Time>, config: Res<UiConfig>, mut commands: Commands) {
                 .with_children(|parent| {
                     spawn_button(parent, &config, HUDButton::Pause);
 
+                    // Abilities
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: config.panels_background.into(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section("Target: ", config.text_style.clone()),
+                                AbilityTargetText,
+                            ));
+                            for ability in Ability::ALL {
+                                parent.spawn((
+                                    TextBundle::from_section("", config.text_style.clone()),
+                                    AbilityText(ability),
+                                ));
+                            }
+                        });
+
                     // West info
                     parent
                         .spawn(NodeBundle {
@@ -253,6 +331,58 @@ fn setup(time: Res<Time>, config: Res<UiConfig>, mut commands: Commands) {
                             spawn_button(parent, &config, HUDButton::StatsEast);
                         });
                 });
+
+            // boss health bar at the bottom of the battlefield
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            position: UiRect {
+                                bottom: Val::Px(20.0),
+                                left: Val::Percent(30.0),
+                                ..default()
+                            },
+                            size: Size::width(Val::Percent(40.0)),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            padding: UiRect::all(Val::Px(10.0)),
+                            ..default()
+                        },
+                        background_color: config.panels_background.into(),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    BossBar,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section("", config.debuff_text_style.clone()),
+                        BossNameText,
+                    ));
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(100.0), Val::Px(20.0)),
+                                ..default()
+                            },
+                            background_color: config.button_color_normal.into(),
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn((
+                                NodeBundle {
+                                    style: Style {
+                                        size: Size::height(Val::Percent(100.0)),
+                                        ..default()
+                                    },
+                                    background_color: Color::CRIMSON.into(),
+                                    ..default()
+                                },
+                                BossHealthFill,
+                            ));
+                        });
+                });
         });
 }
 
@@ -287,21 +417,31 @@ fn button_system(
 }
 
 fn update_time(
-    time: Res<Time>,
-    mut hud_timer: ResMut<HUDTimer>,
+    run_timer: Res<RunTimer>,
+    speed: Res<SimulationSpeed>,
     mut time_text: Query<&mut Text, With<TimeText>>,
 ) {
-    hud_timer.time_passed += time.delta_seconds();
     let mut text = time_text.single_mut();
-    text.sections[0].value = format!("Time: {:.1}", hud_timer.time_passed - hud_timer.start_time,);
+    text.sections[0].value = match *speed {
+        SimulationSpeed::Normal => format!("Time: {:.1}", run_timer.time_passed),
+        _ => format!(
+            "Time: {:.1} (x{})",
+            run_timer.time_passed,
+            speed.multiplier()
+        ),
+    };
 }
 
 fn update_spawn_state(
-    spawn_state: Res<State<SpawnState>>,
+    game_mode: Res<GameMode>,
+    spawn_state_timer: Res<SpawnStateTimer>,
     mut spawn_state_text: Query<&mut Text, With<SpawnStateText>>,
 ) {
     let mut text = spawn_state_text.single_mut();
-    text.sections[0].value = format!("Stage: {}", spawn_state.0);
+    text.sections[0].value = match *game_mode {
+        GameMode::Normal => format!("Stage: {}", spawn_state_timer.current_state),
+        GameMode::Infinite => format!("Stage: {}", spawn_state_timer.stage_number()),
+    };
 }
 
 fn update_castle_level(
@@ -319,11 +459,131 @@ fn update_castle_exp(castle: Query<&Castle>, mut exp_text: Query<&mut Text, With
     exp_text.sections[0].value = format!("Exp: {}/{}", castle.exp, castle.next_level_exp);
 }
 
+fn update_castle_core(
+    castle: Query<&Castle>,
+    mut core_text: Query<&mut Text, With<CastleCoreText>>,
+) {
+    let castle = castle.single();
+    let mut core_text = core_text.single_mut();
+    core_text.sections[0].value =
+        format!("Core: {}/{}", castle.core_health, castle.core_max_health);
+}
+
+/// Shows the damage boost from pickups while it lasts
+fn update_castle_gold(
+    castle: Query<&Castle>,
+    damage_boost: Res<DamageBoost>,
+    mut gold_text: Query<&mut Text, With<CastleGoldText>>,
+) {
+    let castle = castle.single();
+    let mut gold_text = gold_text.single_mut();
+    gold_text.sections[0].value = if 0.0 < damage_boost.remaining {
+        format!(
+            "Gold: {} Boost: {:.0}",
+            castle.gold,
+            damage_boost.remaining.ceil()
+        )
+    } else {
+        format!("Gold: {}", castle.gold)
+    };
+}
+
 fn update_castle_wall_hp<S: Side>(
     wall: Query<&CastleWall<S>>,
     mut hp_text: Query<&mut Text, With<CastleWallHpText<S>>>,
 ) {
     let wall = wall.single();
     let mut hp_text = hp_text.single_mut();
-    hp_text.sections[0].value = format!("Hp: {}/{}", wall.health, wall.max_health);
+    hp_text.sections[0].value = if 0 < wall.armor {
+        format!(
+            "Hp: {}/{} Armor: {}",
+            wall.health, wall.max_health, wall.armor
+        )
+    } else {
+        format!("Hp: {}/{}", wall.health, wall.max_health)
+    };
+}
+
+fn update_ability_target(
+    target: Res<SelectedTarget>,
+    mut target_text: Query<&mut Text, With<AbilityTargetText>>,
+) {
+    let mut target_text = target_text.single_mut();
+    target_text.sections[0].value = format!("Target: {:?}", target.0);
+}
+
+fn update_abilities(
+    abilities: Res<CastleAbilities>,
+    mut ability_texts: Query<(&AbilityText, &mut Text)>,
+) {
+    for (AbilityText(ability), mut text) in ability_texts.iter_mut() {
+        let key = ABILITY_BINDS
+            .iter()
+            .position(|(_, _, bind)| bind == ability)
+            .map_or(0, |index| index + 1);
+        let state = abilities.get(*ability);
+        text.sections[0].value = if state.is_ready() {
+            format!("[{key}] {ability:?}: ready")
+        } else {
+            format!(
+                "[{key}] {ability:?}: {:.1}",
+                state.cooldown.remaining_secs()
+            )
+        };
+    }
+}
+
+/// Shows spawn pressure of the side
+/// on its stats button below the label
+fn update_threat(
+    config: Res<UiConfig>,
+    pressure: Res<PressureState>,
+    buttons: Query<(&HUDButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (button, children) in buttons.iter() {
+        let Some(side) = button.side() else {
+            continue;
+        };
+        let (label, style) = match pressure.threat(side) {
+            Threat::Quiet => ("\nquiet", config.buff_text_style.clone()),
+            Threat::Normal => ("", config.text_style.clone()),
+            Threat::High => ("\npressure", config.debuff_text_style.clone()),
+            Threat::Extreme => ("\ndanger", config.debuff_text_style.clone()),
+        };
+        for child in children.iter() {
+            let Ok(mut text) = texts.get_mut(*child) else {
+                continue;
+            };
+            if text.sections.len() < 2 {
+                text.sections.push(TextSection::new(label, style));
+            } else if text.sections[1].value != label {
+                text.sections[1] = TextSection::new(label, style);
+            }
+        }
+    }
+}
+
+/// Shows health of the most damaged boss
+/// and the number of bosses alive
+fn update_boss_bar(
+    bosses: Query<&Boss>,
+    mut bar: Query<&mut Visibility, With<BossBar>>,
+    mut name_text: Query<&mut Text, With<BossNameText>>,
+    mut fill: Query<&mut Style, With<BossHealthFill>>,
+) {
+    let mut visibility = bar.single_mut();
+    let Some(boss) = bosses.iter().min_by(|a, b| a.health.total_cmp(&b.health)) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+
+    let count = bosses.iter().count();
+    name_text.single_mut().sections[0].value = if 1 < count {
+        format!("{} x{count}", boss.name)
+    } else {
+        boss.name.clone()
+    };
+    fill.single_mut().size.width = Val::Percent(boss.health * 100.0);
 }
@@ -1,4 +1,5 @@
-use crate::game::upgrades::apply::ApplyUpgradeEvent;
+use crate::game::replay::ReplayPlayback;
+use crate::game::upgrades::apply::{ApplyUpgradeEvent, UpgradeSet};
 use crate::game::upgrades::{Upgrade, Upgrades};
 use crate::ui::in_game::UiInGameState;
 use crate::ui::UiConfig;
@@ -13,7 +14,12 @@ pub struct LevelUpPlugin;
 impl Plugin for LevelUpPlugin {
     fn build(&self, app: &mut App) {
         app.add_system(setup.in_schedule(OnEnter(UiInGameState::LevelUp)))
-            .add_system(button_system.in_set(OnUpdate(UiInGameState::LevelUp)))
+            .add_system(
+                button_system
+                    .run_if(not(resource_exists::<ReplayPlayback>()))
+                    .before(UpgradeSet::Dispatch)
+                    .in_set(OnUpdate(UiInGameState::LevelUp)),
+            )
             .add_system(
                 remove_all_with::<LevelUpMarker>.in_schedule(OnExit(UiInGameState::LevelUp)),
             );
@@ -1,22 +1,64 @@
 use bevy::prelude::*;
 
+#[cfg(feature = "dev")]
+use crate::game::simulation::SingleStep;
 use crate::{
-    game::{East, GameState, North, South, West},
+    game::{
+        abilities::{Ability, AbilityRequest, AbilityRequests, AbilityTarget, CastleAbilities},
+        replay::ReplayPlayback,
+        simulation::SimulationSpeed,
+        East, GameState, North, South, West,
+    },
     impl_into_state,
     utils::{set_state, IntoState},
 };
 
 mod game_over;
+mod high_score_entry;
 mod hud;
 mod level_up;
 mod pause;
+mod run_summary;
+mod shop;
 mod side_stats;
+mod toast;
+mod victory;
+
+/// Keys and gamepad buttons which choose the side for abilities
+const TARGET_BINDS: [(KeyCode, GamepadButtonType, AbilityTarget); 4] = [
+    (KeyCode::Up, GamepadButtonType::DPadUp, AbilityTarget::North),
+    (
+        KeyCode::Down,
+        GamepadButtonType::DPadDown,
+        AbilityTarget::South,
+    ),
+    (
+        KeyCode::Left,
+        GamepadButtonType::DPadLeft,
+        AbilityTarget::West,
+    ),
+    (
+        KeyCode::Right,
+        GamepadButtonType::DPadRight,
+        AbilityTarget::East,
+    ),
+];
+
+/// Keys and gamepad buttons which use abilities,
+/// in the same order as `Ability::ALL`
+const ABILITY_BINDS: [(KeyCode, GamepadButtonType, Ability); 4] = [
+    (KeyCode::Key1, GamepadButtonType::South, Ability::Repair),
+    (KeyCode::Key2, GamepadButtonType::East, Ability::Volley),
+    (KeyCode::Key3, GamepadButtonType::West, Ability::Freeze),
+    (KeyCode::Key4, GamepadButtonType::North, Ability::BurningOil),
+];
 
 pub struct UiInGamePlugin;
 
 impl Plugin for UiInGamePlugin {
     fn build(&self, app: &mut App) {
         app.add_state::<UiInGameState>()
+            .init_resource::<SelectedTarget>()
             .add_system(
                 set_state::<UiInGameState, { UiInGameState::InGame as u8 }>
                     .in_schedule(OnEnter(GameState::InGame)),
@@ -29,6 +71,10 @@ impl Plugin for UiInGamePlugin {
                 set_state::<UiInGameState, { UiInGameState::LevelUp as u8 }>
                     .in_schedule(OnEnter(GameState::LevelUp)),
             )
+            .add_system(
+                set_state::<UiInGameState, { UiInGameState::Shop as u8 }>
+                    .in_schedule(OnEnter(GameState::Shop)),
+            )
             .add_system(
                 set_state::<UiInGameState, { UiInGameState::Pause as u8 }>
                     .in_schedule(OnEnter(GameState::Paused)),
@@ -37,6 +83,10 @@ impl Plugin for UiInGamePlugin {
                 set_state::<UiInGameState, { UiInGameState::GameOver as u8 }>
                     .in_schedule(OnEnter(GameState::GameOver)),
             )
+            .add_system(
+                set_state::<UiInGameState, { UiInGameState::Victory as u8 }>
+                    .in_schedule(OnEnter(GameState::Victory)),
+            )
             .add_system(
                 set_state::<UiInGameState, { UiInGameState::StatsNorth as u8 }>
                     .in_schedule(OnEnter(GameState::StatsNorth)),
@@ -53,14 +103,29 @@ impl Plugin for UiInGamePlugin {
                 set_state::<UiInGameState, { UiInGameState::StatsEast as u8 }>
                     .in_schedule(OnEnter(GameState::StatsEast)),
             )
+            .add_system(in_game_key_input.in_set(OnUpdate(GameState::InGame)))
+            .add_system(
+                ability_input
+                    .run_if(not(resource_exists::<ReplayPlayback>()))
+                    .in_set(OnUpdate(GameState::InGame)),
+            )
+            .add_system(speed_input.in_set(OnUpdate(GameState::InGame)))
             .add_plugin(hud::HUDPlugin)
             .add_plugin(level_up::LevelUpPlugin)
+            .add_plugin(shop::ShopPanelPlugin)
             .add_plugin(pause::PausePlugin)
             .add_plugin(game_over::GameOverPlugin)
+            .add_plugin(high_score_entry::HighScoreEntryPlugin)
+            .add_plugin(victory::VictoryPlugin)
+            .add_plugin(toast::ToastPlugin)
             .add_plugin(side_stats::StatsPlugin::<North>::default())
             .add_plugin(side_stats::StatsPlugin::<South>::default())
             .add_plugin(side_stats::StatsPlugin::<West>::default())
             .add_plugin(side_stats::StatsPlugin::<East>::default());
+
+        // debugging only, players would freeze the game by accident
+        #[cfg(feature = "dev")]
+        app.add_system(simulation_controls.in_set(OnUpdate(GameState::InGame)));
     }
 }
 
@@ -71,10 +136,72 @@ enum UiInGameState {
     InGame,
     Pause,
     GameOver,
+    Victory,
     LevelUp,
+    Shop,
     StatsNorth,
     StatsSouth,
     StatsWest,
     StatsEast,
 }
 impl_into_state!(UiInGameState);
+
+/// Side the abilities are used on
+#[derive(Debug, Default, Resource)]
+struct SelectedTarget(AbilityTarget);
+
+fn in_game_key_input(keyboard: Res<Input<KeyCode>>, mut game_state: ResMut<NextState<GameState>>) {
+    if keyboard.pressed(KeyCode::Escape) {
+        game_state.set(GameState::Paused);
+    }
+}
+
+/// F1 changes game speed, also used to fast forward replays
+fn speed_input(keyboard: Res<Input<KeyCode>>, mut speed: ResMut<SimulationSpeed>) {
+    if keyboard.just_pressed(KeyCode::F1) {
+        *speed = speed.next();
+    }
+}
+
+/// F2 toggles single step mode, F3 advances it by one tick
+#[cfg(feature = "dev")]
+fn simulation_controls(keyboard: Res<Input<KeyCode>>, mut single_step: ResMut<SingleStep>) {
+    if keyboard.just_pressed(KeyCode::F2) {
+        single_step.enabled = !single_step.enabled;
+    }
+    if keyboard.just_pressed(KeyCode::F3) && single_step.enabled {
+        single_step.requested_steps += 1;
+    }
+}
+
+/// Arrows or d-pad choose the side, 1-4 or
+/// face buttons use abilities on it
+fn ability_input(
+    keyboard: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    abilities: Res<CastleAbilities>,
+    mut target: ResMut<SelectedTarget>,
+    mut requests: ResMut<AbilityRequests>,
+) {
+    let just_pressed = |key: KeyCode, button_type: GamepadButtonType| {
+        keyboard.just_pressed(key)
+            || gamepads.iter().any(|gamepad| {
+                gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type))
+            })
+    };
+
+    for (key, button_type, side) in TARGET_BINDS {
+        if just_pressed(key, button_type) {
+            target.0 = side;
+        }
+    }
+    for (key, button_type, ability) in ABILITY_BINDS {
+        if just_pressed(key, button_type) && abilities.get(ability).is_ready() {
+            requests.0.push(AbilityRequest {
+                ability,
+                target: target.0,
+            });
+        }
+    }
+}
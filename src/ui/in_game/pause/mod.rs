@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 
 use crate::{
-    game::GameState,
+    game::{rng::GameRng, save::SaveRunEvent, GameState},
     impl_into_state,
     ui::{spawn_button, UiConfig},
     utils::{remove_all_with, set_state, IntoState},
@@ -46,12 +46,18 @@ impl_into_state!(UiPauseState);
 
 #[derive(Debug, Clone, Copy, Component)]
 enum PauseButton {
+    SaveAndQuit,
     MainMenu,
     Settings,
     Back,
 }
 
-fn setup(config: Res<UiConfig>, hud: Query<Entity, With<HUDMarker>>, mut commands: Commands) {
+fn setup(
+    config: Res<UiConfig>,
+    game_rng: Res<GameRng>,
+    hud: Query<Entity, With<HUDMarker>>,
+    mut commands: Commands,
+) {
     let pause = commands
         .spawn((
             NodeBundle {
@@ -62,6 +68,11 @@ fn setup(config: Res<UiConfig>, hud: Query<Entity, With<HUDMarker>>, mut command
             PauseMarker,
         ))
         .with_children(|builder| {
+            builder.spawn(TextBundle::from_section(
+                format!("Seed: {}", game_rng.seed),
+                config.text_style.clone(),
+            ));
+            spawn_button(builder, &config, PauseButton::SaveAndQuit);
             spawn_button(builder, &config, PauseButton::MainMenu);
             spawn_button(builder, &config, PauseButton::Settings);
             spawn_button(builder, &config, PauseButton::Back);
@@ -81,12 +92,14 @@ fn button_system(
         (&PauseButton, &Interaction, &mut BackgroundColor),
         (Changed<Interaction>, With<Button>),
     >,
+    mut save_run_event: EventWriter<SaveRunEvent>,
 ) {
     for (button, interaction, mut color) in interaction_query.iter_mut() {
         match *interaction {
             Interaction::Clicked => {
                 *color = style.button_color_pressed.into();
                 match button {
+                    PauseButton::SaveAndQuit => save_run_event.send(SaveRunEvent),
                     PauseButton::MainMenu => {
                         global_state.set(GlobalState::MainMenu);
                     }
@@ -1,5 +1,4 @@
 use bevy::{prelude::*, window::WindowMode};
-use bevy_kira_audio::prelude::*;
 
 use crate::{
     ui::{in_game::hud::HUDMarker, main_menu::settings::*, UiConfig},
@@ -37,7 +36,6 @@ fn setup(
 
 fn button_system(
     config: Res<UiConfig>,
-    audio: ResMut<Audio>,
     mut windows: Query<&mut Window>,
     mut game_settings: ResMut<GameSettings>,
     mut pause_state: ResMut<NextState<UiPauseState>>,
@@ -59,17 +57,17 @@ fn button_system(
                         game_settings.window_mode = WindowMode::Windowed;
                         windows.single_mut().mode = WindowMode::Windowed;
                     }
-                    SettingsButton::VolumeUp => {
-                        game_settings.sound_volume += 0.05;
-                        audio.set_volume(game_settings.sound_volume);
-                    }
-                    SettingsButton::VolumeDown => {
-                        game_settings.sound_volume -= 0.05;
-                        audio.set_volume(game_settings.sound_volume);
+                    SettingsButton::Mute => {
+                        game_settings.muted = !game_settings.muted;
                     }
                     SettingsButton::Back => {
                         pause_state.set(UiPauseState::Pause);
                     }
+                    volume_button => {
+                        if let Some((channel, delta)) = volume_button.volume_change() {
+                            game_settings.change_volume(channel, delta);
+                        }
+                    }
                 }
             }
             Interaction::Hovered => {
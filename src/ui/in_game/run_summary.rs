@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        castle::Castle,
+        damage::DamageSource,
+        enemies::EnemyKind,
+        mode::RunTimer,
+        stats::{RunStats, SideStats},
+    },
+    ui::UiConfig,
+};
+
+/// Names of the sides by `Side::INDEX`
+const SIDE_NAMES: [&str; 4] = ["North", "South", "West", "East"];
+
+const UPGRADE_FONT_SIZE: f32 = 16.0;
+
+/// Spawns summary of the finished run
+pub fn spawn_run_summary(
+    builder: &mut ChildBuilder,
+    config: &UiConfig,
+    run_stats: &RunStats,
+    run_timer: &RunTimer,
+    castle: &Castle,
+) {
+    let time = run_timer.time_passed as u32;
+    builder.spawn(TextBundle::from_section(
+        format!(
+            "Time: {:02}:{:02} Level: {} Kills: {} Crit rate: {:.1}%",
+            time / 60,
+            time % 60,
+            castle.level,
+            run_stats.total_kills(),
+            run_stats.crit_rate() * 100.0
+        ),
+        config.text_style.clone(),
+    ));
+
+    // sides
+    builder
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                justify_content: JustifyContent::SpaceEvenly,
+                align_items: AlignItems::FlexStart,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|builder| {
+            for (name, side) in SIDE_NAMES.iter().zip(run_stats.sides.iter()) {
+                builder
+                    .spawn(NodeBundle {
+                        style: Style {
+                            margin: UiRect::horizontal(Val::Px(10.0)),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|builder| {
+                        builder.spawn(TextBundle::from_section(*name, config.text_style.clone()));
+                        builder.spawn(TextBundle::from_section(
+                            side_dealt(side),
+                            config.buff_text_style.clone(),
+                        ));
+                        builder.spawn(TextBundle::from_section(
+                            format!("Damage taken: {}", side.damage_taken),
+                            config.debuff_text_style.clone(),
+                        ));
+                    });
+            }
+        });
+
+    // upgrades
+    builder.spawn(TextBundle::from_section(
+        "Upgrades:",
+        config.text_style.clone(),
+    ));
+    let upgrade_text_style = TextStyle {
+        font_size: UPGRADE_FONT_SIZE,
+        ..config.text_style.clone()
+    };
+    builder
+        .spawn(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                flex_wrap: FlexWrap::Wrap,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|builder| {
+            for (i, upgrade) in run_stats.upgrades.iter().enumerate() {
+                builder.spawn(
+                    TextBundle::from_section(
+                        format!("{}. {upgrade}", i + 1),
+                        upgrade_text_style.clone(),
+                    )
+                    .with_style(Style {
+                        margin: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    }),
+                );
+            }
+        });
+}
+
+/// Kills and damage dealt on the side
+fn side_dealt(side: &SideStats) -> String {
+    let mut text = format!("Kills: {}\n", side.total_kills());
+    for kind in EnemyKind::ALL {
+        if let Some(kills) = side.kills.get(&kind) {
+            text.push_str(&format!("\t{kind:?}: {kills}\n"));
+        }
+    }
+    for source in DamageSource::ALL {
+        let damage = side.damage_dealt.get(&source).unwrap_or(&0);
+        text.push_str(&format!("{source:?}: {damage}\n"));
+    }
+    text
+}
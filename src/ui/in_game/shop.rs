@@ -0,0 +1,224 @@
+use crate::game::castle::Castle;
+use crate::game::replay::ReplayPlayback;
+use crate::game::shop::{Shop, ShopEvent, ShopItem, ShopSet};
+use crate::game::SideId;
+use crate::ui::in_game::UiInGameState;
+use crate::ui::UiConfig;
+use crate::utils::remove_all_with;
+
+use bevy::prelude::*;
+
+use super::hud::HUDMarker;
+
+pub struct ShopPanelPlugin;
+
+impl Plugin for ShopPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(UiInGameState::Shop)))
+            .add_system(update_shop.in_set(OnUpdate(UiInGameState::Shop)))
+            .add_system(
+                button_system
+                    .run_if(not(resource_exists::<ReplayPlayback>()))
+                    .before(ShopSet)
+                    .in_set(OnUpdate(UiInGameState::Shop)),
+            )
+            .add_system(remove_all_with::<ShopMarker>.in_schedule(OnExit(UiInGameState::Shop)));
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+struct ShopMarker;
+
+#[derive(Debug, Clone, Copy, Component)]
+struct GoldText;
+
+/// Offer of the side
+#[derive(Debug, Clone, Copy, Component)]
+struct OfferText(SideId);
+
+#[derive(Debug, Clone, Copy, Component)]
+struct ShopButton(ShopEvent);
+
+/// Label of the button, shows the price
+#[derive(Debug, Clone, Copy, Component)]
+struct ShopButtonText(ShopEvent);
+
+fn setup(ui_config: Res<UiConfig>, hud: Query<Entity, With<HUDMarker>>, mut commands: Commands) {
+    let shop = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            ShopMarker,
+        ))
+        .with_children(|builder| {
+            builder.spawn(
+                TextBundle::from_section("Shop", ui_config.title_text_style.clone()).with_style(
+                    Style {
+                        margin: UiRect::all(Val::Percent(2.0)),
+                        ..default()
+                    },
+                ),
+            );
+            builder.spawn((
+                TextBundle::from_section("Gold: ", ui_config.text_style.clone()),
+                GoldText,
+            ));
+            // one column for every side
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Percent(1.0)),
+                        flex_direction: FlexDirection::Row,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    for side in SideId::ALL {
+                        builder
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    margin: UiRect::all(Val::Percent(1.0)),
+                                    flex_direction: FlexDirection::Column,
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: ui_config.panels_background.into(),
+                                ..default()
+                            })
+                            .with_children(|builder| {
+                                builder.spawn(TextBundle::from_section(
+                                    format!("{side:?}"),
+                                    ui_config.text_style.clone(),
+                                ));
+                                builder.spawn((
+                                    TextBundle::from_section("", ui_config.buff_text_style.clone()),
+                                    OfferText(side),
+                                ));
+                                spawn_shop_button(
+                                    builder,
+                                    &ui_config,
+                                    ShopEvent::Buy(ShopItem::Upgrade(side)),
+                                );
+                                spawn_shop_button(
+                                    builder,
+                                    &ui_config,
+                                    ShopEvent::Buy(ShopItem::Repair(side)),
+                                );
+                            });
+                    }
+                });
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    spawn_shop_button(builder, &ui_config, ShopEvent::Buy(ShopItem::Reroll));
+                    spawn_shop_button(builder, &ui_config, ShopEvent::Leave);
+                });
+        })
+        .id();
+
+    let hud = hud.single();
+    commands.entity(hud).insert_children(1, &[shop]);
+}
+
+fn spawn_shop_button(builder: &mut ChildBuilder, style: &UiConfig, event: ShopEvent) {
+    builder
+        .spawn((
+            ButtonBundle {
+                style: style.button_style.clone(),
+                background_color: style.button_color_normal.into(),
+                ..default()
+            },
+            ShopButton(event),
+        ))
+        .with_children(|builder| {
+            builder.spawn((
+                TextBundle::from_section("", style.text_style.clone()),
+                ShopButtonText(event),
+            ));
+        });
+}
+
+/// Keeps gold, offers and prices up to date after purchases
+fn update_shop(
+    shop: Res<Shop>,
+    castle: Query<&Castle>,
+    mut gold_text: Query<&mut Text, (With<GoldText>, Without<OfferText>)>,
+    mut offer_texts: Query<(&OfferText, &mut Text), Without<ShopButtonText>>,
+    mut button_texts: Query<(&ShopButtonText, &mut Text), Without<GoldText>>,
+) {
+    let castle = castle.single();
+    set_text(
+        &mut gold_text.single_mut(),
+        format!("Gold: {}", castle.gold),
+    );
+    for (offer, mut text) in offer_texts.iter_mut() {
+        let offer = &shop.offers[offer.0.index()];
+        let value = if offer.sold {
+            "Sold".to_string()
+        } else {
+            format!("{offer}")
+        };
+        set_text(&mut text, value);
+    }
+    for (button, mut text) in button_texts.iter_mut() {
+        let value = match button.0 {
+            ShopEvent::Buy(item @ ShopItem::Upgrade(_)) => format!("Buy: {}", shop.price(item)),
+            ShopEvent::Buy(item @ ShopItem::Repair(_)) => {
+                format!("Repair: {}", shop.price(item))
+            }
+            ShopEvent::Buy(item @ ShopItem::Reroll) => format!("Reroll: {}", shop.price(item)),
+            ShopEvent::Leave => "Continue".to_string(),
+        };
+        set_text(&mut text, value);
+    }
+}
+
+/// Only changes the text if needed, so the layout is not recomputed every frame
+fn set_text(text: &mut Mut<Text>, value: String) {
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
+fn button_system(
+    config: Res<UiConfig>,
+    mut shop_events: EventWriter<ShopEvent>,
+    mut interaction_query: Query<
+        (&ShopButton, &Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (button, interaction, mut color) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *color = config.button_color_pressed.into();
+                shop_events.send(button.0);
+            }
+            Interaction::Hovered => {
+                *color = config.button_color_hover.into();
+            }
+            Interaction::None => {
+                *color = config.button_color_normal.into();
+            }
+        }
+    }
+}
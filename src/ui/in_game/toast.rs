@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+
+use crate::{
+    achievements::AchievementUnlockedEvent,
+    game::enemies::{
+        boss::BossSpawnedEvent,
+        pressure::{PressureWarning, PressureWarningEvent},
+    },
+    ui::UiConfig,
+    utils::remove_all_with,
+    GlobalState,
+};
+
+const TOAST_LIFESPAN: f32 = 4.0;
+const TOAST_GAP: f32 = 70.0;
+
+/// Shows unlocked achievements, spawn pressure
+/// warnings and boss arrivals on top of everything
+pub struct ToastPlugin;
+
+impl Plugin for ToastPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            (
+                spawn_achievement_toasts,
+                spawn_pressure_toasts,
+                spawn_boss_toasts,
+                toast_update,
+            )
+                .in_set(OnUpdate(GlobalState::InGame)),
+        )
+        .add_system(remove_all_with::<Toast>.in_schedule(OnExit(GlobalState::InGame)));
+    }
+}
+
+#[derive(Debug, Clone, Component)]
+struct Toast {
+    lifespan: Timer,
+}
+
+fn spawn_achievement_toasts(
+    config: Res<UiConfig>,
+    toasts: Query<&Toast>,
+    mut commands: Commands,
+    mut unlocked_events: EventReader<AchievementUnlockedEvent>,
+) {
+    for (i, event) in unlocked_events.iter().enumerate() {
+        let definition = &event.0;
+        spawn_toast(
+            &mut commands,
+            &config,
+            toasts.iter().count() + i,
+            vec![
+                TextSection::new("Achievement unlocked: ", config.text_style.clone()),
+                TextSection::new(definition.name.clone(), config.buff_text_style.clone()),
+            ],
+        );
+    }
+}
+
+fn spawn_pressure_toasts(
+    config: Res<UiConfig>,
+    toasts: Query<&Toast>,
+    mut commands: Commands,
+    mut warning_events: EventReader<PressureWarningEvent>,
+) {
+    for (i, event) in warning_events.iter().enumerate() {
+        let message = match event.warning {
+            PressureWarning::Focus => format!("Enemies gather at the {:?} wall", event.side),
+            PressureWarning::Flank => format!("Flank attack on the {:?} wall!", event.side),
+        };
+        spawn_toast(
+            &mut commands,
+            &config,
+            toasts.iter().count() + i,
+            vec![TextSection::new(message, config.debuff_text_style.clone())],
+        );
+    }
+}
+
+fn spawn_boss_toasts(
+    config: Res<UiConfig>,
+    toasts: Query<&Toast>,
+    mut commands: Commands,
+    mut spawned_events: EventReader<BossSpawnedEvent>,
+) {
+    for (i, event) in spawned_events.iter().enumerate() {
+        spawn_toast(
+            &mut commands,
+            &config,
+            toasts.iter().count() + i,
+            vec![
+                TextSection::new(event.name.clone(), config.debuff_text_style.clone()),
+                TextSection::new(
+                    format!(" approaches the {:?} wall!", event.side),
+                    config.text_style.clone(),
+                ),
+            ],
+        );
+    }
+}
+
+/// `index` is the number of toasts already shown,
+/// new toasts are stacked below them
+fn spawn_toast(
+    commands: &mut Commands,
+    config: &UiConfig,
+    index: usize,
+    sections: Vec<TextSection>,
+) {
+    let offset = index as f32 * TOAST_GAP;
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(20.0 + offset),
+                        left: Val::Percent(30.0),
+                        ..default()
+                    },
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                background_color: config.panels_background.into(),
+                ..default()
+            },
+            Toast {
+                lifespan: Timer::from_seconds(TOAST_LIFESPAN, TimerMode::Once),
+            },
+        ))
+        .with_children(|builder| {
+            builder.spawn(TextBundle::from_sections(sections));
+        });
+}
+
+fn toast_update(time: Res<Time>, mut commands: Commands, mut toasts: Query<(Entity, &mut Toast)>) {
+    for (entity, mut toast) in toasts.iter_mut() {
+        if toast.lifespan.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
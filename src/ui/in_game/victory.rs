@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{castle::Castle, mode::RunTimer, replay::ReplayPlayback, rng::GameRng, stats::RunStats},
+    ui::{spawn_button, UiConfig},
+    utils::remove_all_with,
+    GlobalState,
+};
+
+use super::{
+    high_score_entry::spawn_high_score_entry, hud::HUDMarker, run_summary::spawn_run_summary,
+    UiInGameState,
+};
+
+pub struct VictoryPlugin;
+
+impl Plugin for VictoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(UiInGameState::Victory)))
+            .add_system(button_system.in_set(OnUpdate(UiInGameState::Victory)))
+            .add_system(
+                remove_all_with::<VictoryMarker>.in_schedule(OnExit(UiInGameState::Victory)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+struct VictoryMarker;
+
+#[derive(Debug, Clone, Copy, Component)]
+enum VictoryButton {
+    Restart,
+    MainMenu,
+}
+
+fn setup(
+    config: Res<UiConfig>,
+    game_rng: Res<GameRng>,
+    run_stats: Res<RunStats>,
+    run_timer: Res<RunTimer>,
+    castle: Query<&Castle>,
+    replay_playback: Option<Res<ReplayPlayback>>,
+    hud: Query<Entity, With<HUDMarker>>,
+    mut commands: Commands,
+) {
+    let victory = commands
+        .spawn((
+            NodeBundle {
+                style: config.summary_style.clone(),
+                background_color: config.panels_background.into(),
+                ..default()
+            },
+            VictoryMarker,
+        ))
+        .with_children(|builder| {
+            builder.spawn(
+                (TextBundle {
+                    text: Text::from_section("Victory", config.title_text_style.clone()),
+                    ..default()
+                })
+                .with_style(config.title_style.clone()),
+            );
+            builder.spawn(TextBundle::from_section(
+                format!("Seed: {}", game_rng.seed),
+                config.text_style.clone(),
+            ));
+            spawn_run_summary(builder, &config, &run_stats, &run_timer, castle.single());
+            spawn_high_score_entry(builder, &config, replay_playback.is_some());
+        })
+        .with_children(|builder| {
+            spawn_button(builder, &config, VictoryButton::Restart);
+            spawn_button(builder, &config, VictoryButton::MainMenu);
+        })
+        .id();
+
+    let hud = hud.single();
+    commands.entity(hud).insert_children(1, &[victory]);
+}
+
+fn button_system(
+    style: Res<UiConfig>,
+    mut global_state: ResMut<NextState<GlobalState>>,
+    mut interaction_query: Query<
+        (&VictoryButton, &Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (button, interaction, mut color) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *color = style.button_color_pressed.into();
+                match button {
+                    VictoryButton::Restart => {
+                        global_state.set(GlobalState::InGame);
+                    }
+                    VictoryButton::MainMenu => {
+                        global_state.set(GlobalState::MainMenu);
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                *color = style.button_color_hover.into();
+            }
+            Interaction::None => {
+                *color = style.button_color_normal.into();
+            }
+        }
+    }
+}
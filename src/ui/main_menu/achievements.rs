@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+
+use crate::{
+    achievements::{AchievementContext, AchievementDefinitions},
+    profile::PlayerProfile,
+    utils::remove_all_with,
+};
+
+use super::{spawn_button, UiConfig, UiMainMenuState};
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(UiMainMenuState::Achievements)))
+            .add_system(button_system.in_set(OnUpdate(UiMainMenuState::Achievements)))
+            .add_system(
+                remove_all_with::<AchievementsMarker>
+                    .in_schedule(OnExit(UiMainMenuState::Achievements)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+struct AchievementsMarker;
+
+#[derive(Debug, Clone, Copy, Component)]
+enum AchievementsButton {
+    Back,
+}
+
+fn setup(
+    mut commands: Commands,
+    config: Res<UiConfig>,
+    definitions: Res<AchievementDefinitions>,
+    profile: Res<PlayerProfile>,
+) {
+    let context = AchievementContext {
+        profile: &profile,
+        run: None,
+        won: false,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: config.stats_style.clone(),
+                background_color: config.panels_background.into(),
+                ..default()
+            },
+            AchievementsMarker,
+        ))
+        .with_children(|builder| {
+            builder.spawn(
+                (TextBundle {
+                    text: Text::from_section("Achievements", config.title_text_style.clone()),
+                    ..default()
+                })
+                .with_style(config.title_style.clone()),
+            );
+            for definition in definitions.achievements.iter() {
+                let (label, style) = if profile.achievements.contains(&definition.id) {
+                    (
+                        format!("{} - {} (done)", definition.name, definition.description),
+                        config.buff_text_style.clone(),
+                    )
+                } else {
+                    let (current, required) = definition.condition.progress(&context);
+                    (
+                        format!(
+                            "{} - {} ({}/{required})",
+                            definition.name,
+                            definition.description,
+                            current.min(required)
+                        ),
+                        config.debuff_text_style.clone(),
+                    )
+                };
+                builder.spawn(TextBundle::from_section(label, style));
+            }
+        })
+        .with_children(|builder| {
+            spawn_button(builder, &config, AchievementsButton::Back);
+        });
+}
+
+fn button_system(
+    config: Res<UiConfig>,
+    mut main_menu_state: ResMut<NextState<UiMainMenuState>>,
+    mut interaction_query: Query<
+        (&AchievementsButton, &Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (button, interaction, mut color) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *color = config.button_color_pressed.into();
+                match button {
+                    AchievementsButton::Back => {
+                        main_menu_state.set(UiMainMenuState::TitleScreen);
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                *color = config.button_color_hover.into();
+            }
+            Interaction::None => {
+                *color = config.button_color_normal.into();
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+
+use crate::{high_scores::HighScores, utils::remove_all_with};
+
+use super::{spawn_button, UiConfig, UiMainMenuState};
+
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectedTable>()
+            .add_system(setup.in_schedule(OnEnter(UiMainMenuState::HighScores)))
+            .add_systems(
+                (button_system, update_table_text).in_set(OnUpdate(UiMainMenuState::HighScores)),
+            )
+            .add_system(
+                remove_all_with::<HighScoresMarker>
+                    .in_schedule(OnExit(UiMainMenuState::HighScores)),
+            );
+    }
+}
+
+/// Index of the shown table in `HighScores::sorted_tables`
+#[derive(Debug, Default, Resource)]
+struct SelectedTable(usize);
+
+#[derive(Debug, Clone, Copy, Component)]
+struct HighScoresMarker;
+
+#[derive(Debug, Clone, Copy, Component)]
+struct TableText;
+
+#[derive(Debug, Clone, Copy, Component)]
+enum HighScoresButton {
+    PreviousTable,
+    NextTable,
+    Back,
+}
+
+fn setup(
+    mut commands: Commands,
+    config: Res<UiConfig>,
+    high_scores: Res<HighScores>,
+    mut selected_table: ResMut<SelectedTable>,
+) {
+    selected_table.0 = 0;
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: config.stats_style.clone(),
+                background_color: config.panels_background.into(),
+                ..default()
+            },
+            HighScoresMarker,
+        ))
+        .with_children(|builder| {
+            builder.spawn(
+                (TextBundle {
+                    text: Text::from_section("High scores", config.title_text_style.clone()),
+                    ..default()
+                })
+                .with_style(config.title_style.clone()),
+            );
+            builder.spawn((
+                TextBundle::from_section(
+                    table_label(&high_scores, 0),
+                    config.buff_text_style.clone(),
+                ),
+                TableText,
+            ));
+        })
+        .with_children(|builder| {
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    spawn_button(builder, &config, HighScoresButton::PreviousTable);
+                    spawn_button(builder, &config, HighScoresButton::NextTable);
+                });
+            spawn_button(builder, &config, HighScoresButton::Back);
+        });
+}
+
+fn button_system(
+    config: Res<UiConfig>,
+    high_scores: Res<HighScores>,
+    mut selected_table: ResMut<SelectedTable>,
+    mut main_menu_state: ResMut<NextState<UiMainMenuState>>,
+    mut interaction_query: Query<
+        (&HighScoresButton, &Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    let tables = high_scores.tables.len().max(1);
+    for (button, interaction, mut color) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *color = config.button_color_pressed.into();
+                match button {
+                    HighScoresButton::PreviousTable => {
+                        selected_table.0 = (selected_table.0 + tables - 1) % tables;
+                    }
+                    HighScoresButton::NextTable => {
+                        selected_table.0 = (selected_table.0 + 1) % tables;
+                    }
+                    HighScoresButton::Back => {
+                        main_menu_state.set(UiMainMenuState::TitleScreen);
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                *color = config.button_color_hover.into();
+            }
+            Interaction::None => {
+                *color = config.button_color_normal.into();
+            }
+        }
+    }
+}
+
+fn update_table_text(
+    high_scores: Res<HighScores>,
+    selected_table: Res<SelectedTable>,
+    mut table_text: Query<&mut Text, With<TableText>>,
+) {
+    if !selected_table.is_changed() {
+        return;
+    }
+
+    let mut text = table_text.single_mut();
+    text.sections[0].value = table_label(&high_scores, selected_table.0);
+}
+
+fn table_label(high_scores: &HighScores, index: usize) -> String {
+    let tables = high_scores.sorted_tables();
+    let Some(table) = tables.get(index) else {
+        return "No runs yet".to_string();
+    };
+
+    let mut label = format!("{table}\n");
+    for (place, high_score) in high_scores.tables[table].iter().enumerate() {
+        let time = high_score.time as u32;
+        label.push_str(&format!(
+            "{}. {} {} - {:02}:{:02} lvl {} {:?} {}\n",
+            place + 1,
+            high_score.name,
+            high_score.score,
+            time / 60,
+            time % 60,
+            high_score.level,
+            high_score.castle_type,
+            high_score.date_label(),
+        ));
+    }
+    label
+}
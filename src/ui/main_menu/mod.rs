@@ -8,6 +8,10 @@ use crate::{
 
 use super::{spawn_button, UiConfig};
 
+pub mod achievements;
+pub mod high_scores;
+pub mod mode_select;
+pub mod records;
 pub mod settings;
 pub mod title_screen;
 
@@ -24,6 +28,10 @@ impl Plugin for UiMainMenuPlugin {
                 set_state::<UiMainMenuState, { UiMainMenuState::Disabled as u8 }>
                     .in_schedule(OnEnter(GlobalState::InGame)),
             )
+            .add_plugin(achievements::AchievementsPlugin)
+            .add_plugin(high_scores::HighScoresPlugin)
+            .add_plugin(mode_select::ModeSelectPlugin)
+            .add_plugin(records::RecordsPlugin)
             .add_plugin(settings::SettingsPlugin)
             .add_plugin(title_screen::TitleScreenPlugin);
     }
@@ -34,6 +42,10 @@ enum UiMainMenuState {
     #[default]
     Disabled,
     TitleScreen,
+    ModeSelect,
     Settings,
+    Records,
+    HighScores,
+    Achievements,
 }
 impl_into_state!(UiMainMenuState);
@@ -0,0 +1,219 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{
+        castle_type::CastleType,
+        mode::GameMode,
+        replay::{load_last_replay, ReplayPlayback},
+        rng::RequestedSeed,
+    },
+    profile::PlayerProfile,
+    utils::remove_all_with,
+    GlobalState,
+};
+
+use super::{spawn_button, UiConfig, UiMainMenuState};
+
+pub struct ModeSelectPlugin;
+
+impl Plugin for ModeSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(UiMainMenuState::ModeSelect)))
+            .add_systems(
+                (
+                    button_system,
+                    seed_input,
+                    update_seed_text,
+                    update_castle_text,
+                )
+                    .in_set(OnUpdate(UiMainMenuState::ModeSelect)),
+            )
+            .add_system(
+                remove_all_with::<ModeSelectMarker>
+                    .in_schedule(OnExit(UiMainMenuState::ModeSelect)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+struct ModeSelectMarker;
+
+#[derive(Debug, Clone, Copy, Component)]
+struct SeedText;
+
+#[derive(Debug, Clone, Copy, Component)]
+struct CastleText;
+
+#[derive(Debug, Clone, Copy, Component)]
+enum ModeSelectButton {
+    PreviousCastle,
+    NextCastle,
+    Normal,
+    Infinite,
+    Replay,
+    Back,
+}
+
+fn setup(
+    mut commands: Commands,
+    config: Res<UiConfig>,
+    requested_seed: Res<RequestedSeed>,
+    castle_type: Res<CastleType>,
+    profile: Res<PlayerProfile>,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: config.menu_style.clone(),
+                background_color: config.panels_background.into(),
+                ..default()
+            },
+            ModeSelectMarker,
+        ))
+        .with_children(|builder| {
+            builder.spawn(
+                (TextBundle {
+                    text: Text::from_section("Select mode", config.title_text_style.clone()),
+                    ..default()
+                })
+                .with_style(config.title_style.clone()),
+            );
+            builder.spawn((
+                TextBundle::from_section(seed_label(&requested_seed), config.text_style.clone()),
+                SeedText,
+            ));
+            builder.spawn((
+                TextBundle::from_section(
+                    castle_label(*castle_type, &profile),
+                    config.text_style.clone(),
+                ),
+                CastleText,
+            ));
+        })
+        .with_children(|builder| {
+            spawn_button(builder, &config, ModeSelectButton::PreviousCastle);
+            spawn_button(builder, &config, ModeSelectButton::NextCastle);
+            spawn_button(builder, &config, ModeSelectButton::Normal);
+            spawn_button(builder, &config, ModeSelectButton::Infinite);
+            spawn_button(builder, &config, ModeSelectButton::Replay);
+            spawn_button(builder, &config, ModeSelectButton::Back);
+        });
+}
+
+fn button_system(
+    config: Res<UiConfig>,
+    mut commands: Commands,
+    profile: Res<PlayerProfile>,
+    mut game_mode: ResMut<GameMode>,
+    mut castle_type: ResMut<CastleType>,
+    mut requested_seed: ResMut<RequestedSeed>,
+    mut main_menu_state: ResMut<NextState<UiMainMenuState>>,
+    mut global_state: ResMut<NextState<GlobalState>>,
+    mut interaction_query: Query<
+        (&ModeSelectButton, &Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (button, interaction, mut color) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *color = config.button_color_pressed.into();
+                match button {
+                    ModeSelectButton::PreviousCastle => {
+                        *castle_type = castle_type.previous();
+                    }
+                    ModeSelectButton::NextCastle => {
+                        *castle_type = castle_type.next();
+                    }
+                    ModeSelectButton::Normal => {
+                        if castle_type.is_unlocked(&profile) {
+                            *game_mode = GameMode::Normal;
+                            global_state.set(GlobalState::InGame);
+                        }
+                    }
+                    ModeSelectButton::Infinite => {
+                        if castle_type.is_unlocked(&profile) {
+                            *game_mode = GameMode::Infinite;
+                            global_state.set(GlobalState::InGame);
+                        }
+                    }
+                    ModeSelectButton::Replay => {
+                        if let Some(replay) = load_last_replay() {
+                            *game_mode = replay.mode;
+                            *castle_type = replay.castle_type;
+                            let previous_seed = requested_seed.0.replace(replay.seed);
+                            commands.insert_resource(ReplayPlayback::new(replay, previous_seed));
+                            global_state.set(GlobalState::InGame);
+                        }
+                    }
+                    ModeSelectButton::Back => {
+                        main_menu_state.set(UiMainMenuState::TitleScreen);
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                *color = config.button_color_hover.into();
+            }
+            Interaction::None => {
+                *color = config.button_color_normal.into();
+            }
+        }
+    }
+}
+
+/// Typing digits sets the seed for the next run.
+/// Backspace removes last digit, empty seed means random one.
+fn seed_input(
+    keyboard: Res<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut requested_seed: ResMut<RequestedSeed>,
+) {
+    for character in characters.iter() {
+        if let Some(digit) = character.char.to_digit(10) {
+            let seed = requested_seed.0.unwrap_or(0);
+            requested_seed.0 = Some(seed.saturating_mul(10).saturating_add(digit as u64));
+        }
+    }
+    if keyboard.just_pressed(KeyCode::Back) {
+        requested_seed.0 = requested_seed
+            .0
+            .map(|seed| seed / 10)
+            .filter(|seed| *seed != 0);
+    }
+}
+
+fn update_seed_text(
+    requested_seed: Res<RequestedSeed>,
+    mut seed_text: Query<&mut Text, With<SeedText>>,
+) {
+    let mut text = seed_text.single_mut();
+    text.sections[0].value = seed_label(&requested_seed);
+}
+
+fn seed_label(requested_seed: &RequestedSeed) -> String {
+    match requested_seed.0 {
+        Some(seed) => format!("Seed: {seed}"),
+        None => "Seed: random".to_string(),
+    }
+}
+
+fn update_castle_text(
+    castle_type: Res<CastleType>,
+    profile: Res<PlayerProfile>,
+    mut castle_text: Query<&mut Text, With<CastleText>>,
+) {
+    let mut text = castle_text.single_mut();
+    text.sections[0].value = castle_label(*castle_type, &profile);
+}
+
+fn castle_label(castle_type: CastleType, profile: &PlayerProfile) -> String {
+    if !castle_type.is_unlocked(profile) {
+        return format!("Castle: {castle_type:?} (locked: {})", castle_type.unlock());
+    }
+
+    let definition = castle_type.definition();
+    match definition.perk {
+        Some(perk) => format!("Castle: {castle_type:?} ({perk})"),
+        None => format!("Castle: {castle_type:?}"),
+    }
+}
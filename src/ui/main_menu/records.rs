@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+
+use crate::{
+    game::{damage::DamageSource, enemies::EnemyKind, mode::GameMode},
+    profile::PlayerProfile,
+    utils::remove_all_with,
+};
+
+use super::{spawn_button, UiConfig, UiMainMenuState};
+
+/// Names of the walls by `Side::INDEX`
+const WALL_NAMES: [&str; 4] = ["North", "South", "West", "East"];
+
+pub struct RecordsPlugin;
+
+impl Plugin for RecordsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(setup.in_schedule(OnEnter(UiMainMenuState::Records)))
+            .add_system(button_system.in_set(OnUpdate(UiMainMenuState::Records)))
+            .add_system(
+                remove_all_with::<RecordsMarker>.in_schedule(OnExit(UiMainMenuState::Records)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, Component)]
+struct RecordsMarker;
+
+#[derive(Debug, Clone, Copy, Component)]
+enum RecordsButton {
+    Back,
+}
+
+fn setup(mut commands: Commands, config: Res<UiConfig>, profile: Res<PlayerProfile>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: config.menu_style.clone(),
+                background_color: config.panels_background.into(),
+                ..default()
+            },
+            RecordsMarker,
+        ))
+        .with_children(|builder| {
+            builder.spawn(
+                (TextBundle {
+                    text: Text::from_section("Records", config.title_text_style.clone()),
+                    ..default()
+                })
+                .with_style(config.title_style.clone()),
+            );
+            for line in records(&profile) {
+                builder.spawn(TextBundle::from_section(line, config.text_style.clone()));
+            }
+        })
+        .with_children(|builder| {
+            spawn_button(builder, &config, RecordsButton::Back);
+        });
+}
+
+fn button_system(
+    config: Res<UiConfig>,
+    mut main_menu_state: ResMut<NextState<UiMainMenuState>>,
+    mut interaction_query: Query<
+        (&RecordsButton, &Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (button, interaction, mut color) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                *color = config.button_color_pressed.into();
+                match button {
+                    RecordsButton::Back => {
+                        main_menu_state.set(UiMainMenuState::TitleScreen);
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                *color = config.button_color_hover.into();
+            }
+            Interaction::None => {
+                *color = config.button_color_normal.into();
+            }
+        }
+    }
+}
+
+fn records(profile: &PlayerProfile) -> Vec<String> {
+    let mut lines = vec![
+        format!("Runs: {} (victories: {})", profile.runs, profile.victories),
+        format!("Highest level: {}", profile.best_level),
+        format!("Furthest stage: {}", profile.best_stage),
+    ];
+
+    for mode in [GameMode::Normal, GameMode::Infinite] {
+        let time = profile.best_times.get(&mode).copied().unwrap_or_default();
+        lines.push(format!(
+            "Best time {mode:?}: {:02}:{:02}",
+            time as u32 / 60,
+            time as u32 % 60
+        ));
+    }
+
+    let kills = EnemyKind::ALL
+        .iter()
+        .map(|kind| format!("{kind:?} {}", profile.kills.get(kind).unwrap_or(&0)))
+        .collect::<Vec<_>>();
+    lines.push(format!("Kills: {}", kills.join(", ")));
+
+    let weapon_damage = DamageSource::ALL
+        .iter()
+        .map(|source| {
+            format!(
+                "{source:?} {}",
+                profile.weapon_damage.get(source).unwrap_or(&0)
+            )
+        })
+        .collect::<Vec<_>>();
+    lines.push(format!("Damage: {}", weapon_damage.join(", ")));
+
+    let side_damage = WALL_NAMES
+        .iter()
+        .zip(profile.side_damage.iter())
+        .map(|(name, damage)| format!("{name} {damage}"))
+        .collect::<Vec<_>>();
+    lines.push(format!("Damage by side: {}", side_damage.join(", ")));
+
+    match profile.favorite_upgrade() {
+        Some((name, picks)) => lines.push(format!("Most picked upgrade: {name} ({picks})")),
+        None => lines.push("Most picked upgrade: none".to_string()),
+    }
+    match profile.weakest_wall() {
+        Some(index) => lines.push(format!(
+            "Most fallen wall: {} ({})",
+            WALL_NAMES[index], profile.fallen_walls[index]
+        )),
+        None => lines.push("Most fallen wall: none".to_string()),
+    }
+
+    lines
+}
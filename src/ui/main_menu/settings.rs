@@ -1,8 +1,12 @@
 use bevy::{prelude::*, window::WindowMode};
-use bevy_kira_audio::prelude::*;
 
 use super::{spawn_button, UiConfig, UiMainMenuState};
-use crate::{utils::remove_all_with, GameSettings};
+use crate::{
+    settings::{GameSettings, SoundChannel},
+    utils::remove_all_with,
+};
+
+const VOLUME_STEP: f64 = 0.05;
 
 pub struct SettingsPlugin;
 
@@ -26,17 +30,53 @@ pub struct SettingsMarker;
 pub struct WindowModeText;
 
 #[derive(Debug, Clone, Copy, Component)]
-pub struct VolumeText;
+pub struct VolumeText(SoundChannel);
+
+#[derive(Debug, Clone, Copy, Component)]
+pub struct MuteText;
 
 #[derive(Debug, Clone, Copy, Component)]
 pub enum SettingsButton {
     FullScreen,
     Windowed,
-    VolumeUp,
-    VolumeDown,
+    Mute,
+    MusicDown,
+    MusicUp,
+    WeaponsDown,
+    WeaponsUp,
+    EnemiesDown,
+    EnemiesUp,
+    UiDown,
+    UiUp,
     Back,
 }
 
+impl SettingsButton {
+    fn volume_buttons(channel: SoundChannel) -> (Self, Self) {
+        match channel {
+            SoundChannel::Music => (Self::MusicDown, Self::MusicUp),
+            SoundChannel::Weapons => (Self::WeaponsDown, Self::WeaponsUp),
+            SoundChannel::Enemies => (Self::EnemiesDown, Self::EnemiesUp),
+            SoundChannel::Ui => (Self::UiDown, Self::UiUp),
+        }
+    }
+
+    /// Channel and volume change of the volume button
+    pub fn volume_change(&self) -> Option<(SoundChannel, f64)> {
+        match self {
+            Self::MusicDown => Some((SoundChannel::Music, -VOLUME_STEP)),
+            Self::MusicUp => Some((SoundChannel::Music, VOLUME_STEP)),
+            Self::WeaponsDown => Some((SoundChannel::Weapons, -VOLUME_STEP)),
+            Self::WeaponsUp => Some((SoundChannel::Weapons, VOLUME_STEP)),
+            Self::EnemiesDown => Some((SoundChannel::Enemies, -VOLUME_STEP)),
+            Self::EnemiesUp => Some((SoundChannel::Enemies, VOLUME_STEP)),
+            Self::UiDown => Some((SoundChannel::Ui, -VOLUME_STEP)),
+            Self::UiUp => Some((SoundChannel::Ui, VOLUME_STEP)),
+            _ => None,
+        }
+    }
+}
+
 fn setup(config: Res<UiConfig>, game_settings: Res<GameSettings>, mut commands: Commands) {
     let _ = spawn_layout(&config, &game_settings, &mut commands);
 }
@@ -112,34 +152,49 @@ pub fn spawn_layout(
                     ..default()
                 },))
                 .with_children(|builder| {
-                    // Current window mode
                     builder.spawn((
                         TextBundle {
                             text: Text::from_section(
-                                format!("Volume: {:.2}", game_settings.sound_volume),
+                                mute_label(game_settings),
                                 config.text_style.clone(),
                             ),
                             ..default()
                         },
-                        VolumeText,
+                        MuteText,
                     ));
+                    spawn_button(builder, config, SettingsButton::Mute);
 
-                    // Volume controls
-                    builder
-                        .spawn((NodeBundle {
-                            style: Style {
-                                flex_direction: FlexDirection::Row,
-                                justify_content: JustifyContent::Center,
-                                align_items: AlignItems::Center,
+                    for channel in SoundChannel::ALL {
+                        // Current volume
+                        builder.spawn((
+                            TextBundle {
+                                text: Text::from_section(
+                                    volume_label(game_settings, channel),
+                                    config.text_style.clone(),
+                                ),
                                 ..default()
                             },
-                            background_color: config.panels_background.into(),
-                            ..default()
-                        },))
-                        .with_children(|builder| {
-                            spawn_button(builder, config, SettingsButton::VolumeDown);
-                            spawn_button(builder, config, SettingsButton::VolumeUp);
-                        });
+                            VolumeText(channel),
+                        ));
+
+                        // Volume controls
+                        let (down, up) = SettingsButton::volume_buttons(channel);
+                        builder
+                            .spawn((NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Row,
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: config.panels_background.into(),
+                                ..default()
+                            },))
+                            .with_children(|builder| {
+                                spawn_button(builder, config, down);
+                                spawn_button(builder, config, up);
+                            });
+                    }
                 });
 
             spawn_button(builder, config, SettingsButton::Back);
@@ -149,7 +204,6 @@ pub fn spawn_layout(
 
 fn button_system(
     style: Res<UiConfig>,
-    audio: ResMut<Audio>,
     mut windows: Query<&mut Window>,
     mut game_settings: ResMut<GameSettings>,
     mut main_menu_state: ResMut<NextState<UiMainMenuState>>,
@@ -171,17 +225,17 @@ fn button_system(
                         game_settings.window_mode = WindowMode::Windowed;
                         windows.single_mut().mode = WindowMode::Windowed;
                     }
-                    SettingsButton::VolumeUp => {
-                        game_settings.sound_volume += 0.05;
-                        audio.set_volume(game_settings.sound_volume);
-                    }
-                    SettingsButton::VolumeDown => {
-                        game_settings.sound_volume -= 0.05;
-                        audio.set_volume(game_settings.sound_volume);
+                    SettingsButton::Mute => {
+                        game_settings.muted = !game_settings.muted;
                     }
                     SettingsButton::Back => {
                         main_menu_state.set(UiMainMenuState::TitleScreen);
                     }
+                    volume_button => {
+                        if let Some((channel, delta)) = volume_button.volume_change() {
+                            game_settings.change_volume(channel, delta);
+                        }
+                    }
                 }
             }
             Interaction::Hovered => {
@@ -204,8 +258,26 @@ pub fn update_window_mode(
 
 pub fn update_volume_value(
     game_settings: Res<GameSettings>,
-    mut volume_text: Query<&mut Text, With<VolumeText>>,
+    mut mute_text: Query<&mut Text, With<MuteText>>,
+    mut volume_texts: Query<(&mut Text, &VolumeText), Without<MuteText>>,
 ) {
-    let mut text = volume_text.single_mut();
-    text.sections[0].value = format!("Volume: {:.2}", game_settings.sound_volume);
+    mute_text.single_mut().sections[0].value = mute_label(&game_settings);
+    for (mut text, VolumeText(channel)) in volume_texts.iter_mut() {
+        text.sections[0].value = volume_label(&game_settings, *channel);
+    }
+}
+
+fn mute_label(game_settings: &GameSettings) -> String {
+    if game_settings.muted {
+        "Sound: muted".to_string()
+    } else {
+        "Sound: on".to_string()
+    }
+}
+
+fn volume_label(game_settings: &GameSettings, channel: SoundChannel) -> String {
+    format!(
+        "{channel:?} volume: {:.2}",
+        game_settings.channel_volume(channel)
+    )
 }
@@ -1,6 +1,14 @@
 use bevy::{app::AppExit, prelude::*};
 
-use crate::{utils::remove_all_with, GlobalState, GAME_NAME};
+use crate::{
+    game::{
+        castle_type::CastleType,
+        mode::GameMode,
+        save::{self, LoadedRun},
+    },
+    utils::remove_all_with,
+    GlobalState, GAME_NAME,
+};
 
 use super::{spawn_button, UiConfig, UiMainMenuState};
 
@@ -20,10 +28,18 @@ impl Plugin for TitleScreenPlugin {
 #[derive(Debug, Clone, Copy, Component)]
 struct TitleScreenMarker;
 
+/// Shows why the run could not be continued
+#[derive(Debug, Clone, Copy, Component)]
+struct ContinueErrorText;
+
 #[derive(Debug, Clone, Copy, Component)]
 enum TitleScreenButton {
+    Continue,
     Start,
     Settings,
+    Records,
+    HighScores,
+    Achievements,
     Exit,
 }
 
@@ -31,7 +47,7 @@ fn setup(mut commands: Commands, config: Res<UiConfig>) {
     commands
         .spawn((
             NodeBundle {
-                style: config.menu_style.clone(),
+                style: config.stats_style.clone(),
                 background_color: config.panels_background.into(),
                 ..default()
             },
@@ -45,18 +61,43 @@ fn setup(mut commands: Commands, config: Res<UiConfig>) {
                 })
                 .with_style(config.title_style.clone()),
             );
+            builder.spawn((
+                TextBundle::from_section("", config.text_style.clone()),
+                ContinueErrorText,
+            ));
         })
         .with_children(|builder| {
+            spawn_button(builder, &config, TitleScreenButton::Continue);
             spawn_button(builder, &config, TitleScreenButton::Start);
-            spawn_button(builder, &config, TitleScreenButton::Settings);
+            builder
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        flex_wrap: FlexWrap::Wrap,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|builder| {
+                    spawn_button(builder, &config, TitleScreenButton::Settings);
+                    spawn_button(builder, &config, TitleScreenButton::Records);
+                    spawn_button(builder, &config, TitleScreenButton::HighScores);
+                    spawn_button(builder, &config, TitleScreenButton::Achievements);
+                });
             spawn_button(builder, &config, TitleScreenButton::Exit);
         });
 }
 
 fn button_system(
     config: Res<UiConfig>,
-    mut main_menu_state: ResMut<NextState<UiMainMenuState>>,
+    mut commands: Commands,
+    mut game_mode: ResMut<GameMode>,
+    mut castle_type: ResMut<CastleType>,
     mut global_state: ResMut<NextState<GlobalState>>,
+    mut main_menu_state: ResMut<NextState<UiMainMenuState>>,
+    mut error_text: Query<&mut Text, With<ContinueErrorText>>,
     mut interaction_query: Query<
         (&TitleScreenButton, &Interaction, &mut BackgroundColor),
         (Changed<Interaction>, With<Button>),
@@ -68,12 +109,37 @@ fn button_system(
             Interaction::Clicked => {
                 *color = config.button_color_pressed.into();
                 match button {
+                    TitleScreenButton::Continue => match save::load() {
+                        Ok(Some(run)) => {
+                            *game_mode = run.mode;
+                            *castle_type = run.castle_type;
+                            commands.insert_resource(LoadedRun(run));
+                            global_state.set(GlobalState::InGame);
+                        }
+                        Ok(None) => {
+                            error_text.single_mut().sections[0].value = "No saved run".to_string();
+                        }
+                        Err(e) => {
+                            error!("could not load the run: {e}");
+                            error_text.single_mut().sections[0].value =
+                                format!("Could not continue: {e}");
+                        }
+                    },
                     TitleScreenButton::Start => {
-                        global_state.set(GlobalState::InGame);
+                        main_menu_state.set(UiMainMenuState::ModeSelect);
                     }
                     TitleScreenButton::Settings => {
                         main_menu_state.set(UiMainMenuState::Settings);
                     }
+                    TitleScreenButton::Records => {
+                        main_menu_state.set(UiMainMenuState::Records);
+                    }
+                    TitleScreenButton::HighScores => {
+                        main_menu_state.set(UiMainMenuState::HighScores);
+                    }
+                    TitleScreenButton::Achievements => {
+                        main_menu_state.set(UiMainMenuState::Achievements);
+                    }
                     TitleScreenButton::Exit => exit.send(AppExit),
                 }
             }
@@ -26,6 +26,7 @@ pub struct UiConfig {
     pub upgrade_button_style: Style,
     pub menu_style: Style,
     pub stats_style: Style,
+    pub summary_style: Style,
     pub title_style: Style,
     pub text_style: TextStyle,
     pub buff_text_style: TextStyle,
@@ -79,6 +80,14 @@ fn setup_ui_config(game_assets: Res<GameAssets>, mut commands: Commands) {
             align_items: AlignItems::Center,
             ..default()
         },
+        summary_style: Style {
+            size: Size::new(Val::Percent(8.9 / 16.0 * 100.0), Val::Percent(100.0)),
+            padding: UiRect::all(Val::Percent(1.0)),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::SpaceEvenly,
+            align_items: AlignItems::Center,
+            ..default()
+        },
         title_style: Style {
             margin: UiRect::bottom(Val::Percent(8.0)),
             ..default()